@@ -198,6 +198,17 @@ export const createInboxItemSchema = z.object({
   filePath: z.array(z.string()),
 });
 
+// Read locally from a PDF's text by the desktop app, used where OCR
+// doesn't find a value
+export const receiptHintsSchema = z.object({
+  vendor: z.string().nullable(),
+  date: z.string().nullable(),
+  total: z.number().nullable(),
+  currency: z.string().nullable(),
+  vat: z.number().nullable(),
+  vatRate: z.number().nullable(),
+});
+
 export const processAttachmentsSchema = z.array(
   z.object({
     mimetype: z.string(),
@@ -206,7 +217,11 @@ export const processAttachmentsSchema = z.array(
     referenceId: z.string().optional(),
     website: z.string().optional(),
     senderEmail: z.string().email().optional(),
+    // Subject and date (ISO 8601) of the email the file came from
+    subject: z.string().optional(),
+    date: z.string().datetime({ offset: true }).optional(),
     inboxAccountId: z.string().uuid().optional(),
+    hints: receiptHintsSchema.optional(),
   }),
 );
 
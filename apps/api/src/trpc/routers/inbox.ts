@@ -153,7 +153,10 @@ export const inboxRouter = createTRPCRouter({
               referenceId: item.referenceId,
               website: item.website,
               senderEmail: item.senderEmail,
+              subject: item.subject,
+              date: item.date,
               inboxAccountId: item.inboxAccountId,
+              hints: item.hints,
             },
             "inbox",
           ),
@@ -1,4 +1,8 @@
 import { redirect } from "next/navigation";
+import { DesktopImports } from "@/components/desktop-imports";
+import { DesktopTimer } from "@/components/desktop-timer";
+import { DesktopUpdates } from "@/components/desktop-updates";
+import { DesktopUploads } from "@/components/desktop-uploads";
 import { ExportStatus } from "@/components/export-status";
 import { GlobalTimerProvider } from "@/components/global-timer-provider";
 import { Header } from "@/components/header";
@@ -72,6 +76,10 @@ export default async function Layout({
         <GlobalSheetsProvider />
         <GlobalTimerProvider />
         <TimezoneDetector />
+        <DesktopUploads />
+        <DesktopImports />
+        <DesktopTimer />
+        <DesktopUpdates />
       </div>
     </HydrateClient>
   );
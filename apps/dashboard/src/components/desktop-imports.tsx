@@ -0,0 +1,243 @@
+"use client";
+
+import {
+  importedCalendarEntries,
+  listenQueued,
+  markCalendarImported,
+  type NativeContact,
+  type NativeProposedEntry,
+} from "@midday/desktop-client/core";
+import { isDesktopApp } from "@midday/desktop-client/platform";
+import { ToastAction } from "@midday/ui/toast";
+import { useToast } from "@midday/ui/use-toast";
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import { useEffect, useRef } from "react";
+import { useUserQuery } from "@/hooks/use-user";
+import { useTRPC } from "@/trpc/client";
+
+type Project = { id: string; name: string };
+
+// "Acme" for an event called "Acme standup", preferring an exact match and
+// then the longest project name in the summary
+function findProject(projects: Project[], summary: string) {
+  const text = summary.trim().toLowerCase();
+  if (!text) {
+    return undefined;
+  }
+
+  return (
+    projects.find((project) => project.name.toLowerCase() === text) ??
+    projects
+      .filter((project) => text.includes(project.name.toLowerCase()))
+      .sort((a, b) => b.name.length - a.name.length)[0]
+  );
+}
+
+function plural(count: number, word: string) {
+  return `${count} ${count === 1 ? word : `${word}s`}`;
+}
+
+// Asks before creating customers from dropped .vcf files and tracker entries
+// from dropped .ics files in the desktop shell
+export function DesktopImports() {
+  const trpc = useTRPC();
+  const queryClient = useQueryClient();
+  const { toast } = useToast();
+  const { data: user } = useUserQuery();
+  // Read by the listeners, which are only registered once
+  const teamIdRef = useRef(user?.teamId);
+  teamIdRef.current = user?.teamId;
+  const upsertCustomerMutation = useMutation(
+    trpc.customers.upsert.mutationOptions(),
+  );
+  const upsertTrackerEntryMutation = useMutation(
+    trpc.trackerEntries.upsert.mutationOptions(),
+  );
+
+  const importContacts = async (contacts: NativeContact[]) => {
+    // Customers need an email
+    const results = await Promise.allSettled(
+      contacts
+        .filter((contact) => contact.email)
+        .map(({ email, ...contact }) =>
+          upsertCustomerMutation.mutateAsync({ ...contact, email: email! }),
+        ),
+    );
+    const created = results.filter(
+      (result) => result.status === "fulfilled",
+    ).length;
+
+    queryClient.invalidateQueries({
+      queryKey: trpc.customers.get.infiniteQueryKey(),
+    });
+    queryClient.invalidateQueries({
+      queryKey: trpc.customers.get.queryKey(),
+    });
+
+    toast({
+      duration: 4000,
+      title: `Added ${plural(created, "customer")}`,
+      description:
+        created < contacts.length
+          ? `${contacts.length - created} without an email or invalid`
+          : undefined,
+    });
+  };
+
+  const importEntries = async (entries: NativeProposedEntry[]) => {
+    const projects = await queryClient.fetchQuery(
+      trpc.trackerProjects.get.queryOptions({ pageSize: 100 }),
+    );
+
+    const matched = entries.flatMap((entry) => {
+      const project = findProject(projects?.data ?? [], entry.summary);
+      return project ? [{ entry, project }] : [];
+    });
+
+    const results = await Promise.allSettled(
+      matched.map(({ entry, project }) =>
+        upsertTrackerEntryMutation.mutateAsync({
+          start: entry.start,
+          stop: entry.stop,
+          dates: [entry.date],
+          projectId: project.id,
+          description: entry.description ?? entry.summary,
+          duration: entry.duration,
+        }),
+      ),
+    );
+    const imported = matched
+      .filter((_, index) => results[index]?.status === "fulfilled")
+      .map(({ entry }) => entry);
+    const created = imported.length;
+
+    const teamId = teamIdRef.current;
+    if (teamId && created > 0) {
+      markCalendarImported(teamId, imported).catch((error) => {
+        console.error("Failed to remember imported events:", error);
+      });
+    }
+
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerEntries.byRange.queryKey(),
+    });
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerProjects.get.infiniteQueryKey(),
+    });
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerEntries.getBillableHours.queryKey(),
+    });
+
+    toast({
+      duration: 4000,
+      title: `Added ${created} tracker ${created === 1 ? "entry" : "entries"}`,
+      description:
+        created < entries.length
+          ? `${entries.length - created} without a matching project`
+          : undefined,
+    });
+  };
+
+  useEffect(() => {
+    if (!isDesktopApp()) {
+      return;
+    }
+
+    const unlisteners: Promise<() => void>[] = [
+      listenQueued<{ fileName: string; contacts: NativeContact[] }>(
+        "contacts-imported",
+        ({ payload }) => {
+          const [first] = payload.contacts;
+
+          toast({
+            duration: 30000,
+            title: `Add ${plural(payload.contacts.length, "customer")} from ${payload.fileName}?`,
+            description:
+              payload.contacts.length === 1 && first
+                ? [first.name, first.email].filter(Boolean).join(" · ")
+                : payload.contacts
+                    .slice(0, 3)
+                    .map((contact) => contact.name)
+                    .join(", "),
+            footer: (
+              <div className="flex space-x-2 mt-4">
+                <ToastAction altText="Cancel" className="pl-5 pr-5">
+                  Cancel
+                </ToastAction>
+                <ToastAction
+                  altText="Import"
+                  onClick={() => importContacts(payload.contacts)}
+                  className="pl-5 pr-5 bg-primary text-primary-foreground hover:bg-primary/90"
+                >
+                  Import
+                </ToastAction>
+              </div>
+            ),
+          });
+        },
+      ),
+
+      listenQueued<{
+        fileName: string;
+        entries: NativeProposedEntry[];
+        skipped: number;
+      }>("calendar-imported", async ({ payload }) => {
+        // Events imported from this calendar before are left out
+        const teamId = teamIdRef.current;
+        const imported = teamId
+          ? await importedCalendarEntries(teamId, payload.entries).catch(
+              (error) => {
+                console.error("Failed to read imported events:", error);
+                return [];
+              },
+            )
+          : [];
+        const entries = payload.entries.filter((_, index) => !imported[index]);
+        const already = payload.entries.length - entries.length;
+        const count = entries.length;
+
+        if (count === 0) {
+          toast({
+            duration: 4000,
+            title: `Events from ${payload.fileName} were already imported`,
+          });
+          return;
+        }
+
+        toast({
+          duration: 30000,
+          title: `Track ${count} ${count === 1 ? "event" : "events"} from ${payload.fileName}?`,
+          description: [
+            "Events are added to the project named in their title",
+            already ? `${already} already imported` : null,
+            payload.skipped ? `${payload.skipped} skipped` : null,
+          ]
+            .filter(Boolean)
+            .join(" · "),
+          footer: (
+            <div className="flex space-x-2 mt-4">
+              <ToastAction altText="Cancel" className="pl-5 pr-5">
+                Cancel
+              </ToastAction>
+              <ToastAction
+                altText="Import"
+                onClick={() => importEntries(entries)}
+                className="pl-5 pr-5 bg-primary text-primary-foreground hover:bg-primary/90"
+              >
+                Import
+              </ToastAction>
+            </div>
+          ),
+        });
+      }),
+    ];
+
+    return () => {
+      for (const unlisten of unlisteners) {
+        unlisten.then((fn) => fn());
+      }
+    };
+  }, []);
+
+  return null;
+}
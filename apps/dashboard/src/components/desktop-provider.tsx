@@ -115,6 +115,10 @@ export function DesktopProvider() {
             router.push(`/${path}`);
           }
         });
+
+        // Let the shell flush deep links it buffered before we were listening
+        await currentWindow.emit("frontend-ready");
+        console.log("🔗 Frontend ready signal sent");
       } catch (error) {
         console.error("Failed to set up deep link listener:", error);
       }
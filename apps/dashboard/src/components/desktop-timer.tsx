@@ -0,0 +1,138 @@
+"use client";
+
+import {
+  listenQueued,
+  type NativeTimerAction,
+  timerResult,
+} from "@midday/desktop-client/core";
+import { isDesktopApp } from "@midday/desktop-client/platform";
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import { useEffect } from "react";
+import { useTimerStore } from "@/store/timer";
+import { useTRPC } from "@/trpc/client";
+import { secondsToHoursAndMinutes } from "@/utils/format";
+
+type Project = { id: string; name: string };
+
+// An exact name first, then the only project whose name contains the query
+function findProject(projects: Project[], query: string) {
+  const text = query.trim().toLowerCase();
+  const exact = projects.find(
+    (project) => project.name.toLowerCase() === text,
+  );
+  if (exact) {
+    return exact;
+  }
+
+  const partial = projects.filter((project) =>
+    project.name.toLowerCase().includes(text),
+  );
+  return partial.length === 1 ? partial[0] : undefined;
+}
+
+// Starts and stops the tracker timer for `midday timer start|stop` in the
+// desktop shell and reports the outcome back to it
+export function DesktopTimer() {
+  const trpc = useTRPC();
+  const queryClient = useQueryClient();
+  const setTimerStatus = useTimerStore((state) => state.setTimerStatus);
+  const startTimerMutation = useMutation(
+    trpc.trackerEntries.startTimer.mutationOptions(),
+  );
+  const stopTimerMutation = useMutation(
+    trpc.trackerEntries.stopTimer.mutationOptions(),
+  );
+
+  const invalidate = () => {
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerEntries.getTimerStatus.queryKey(),
+    });
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerEntries.getCurrentTimer.queryKey(),
+    });
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerEntries.byDate.queryKey(),
+    });
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerEntries.byRange.queryKey(),
+    });
+    queryClient.invalidateQueries({
+      queryKey: trpc.trackerProjects.get.infiniteQueryKey(),
+    });
+  };
+
+  const start = async (query: string) => {
+    const projects = await queryClient.fetchQuery(
+      trpc.trackerProjects.get.queryOptions({ q: query, pageSize: 100 }),
+    );
+    const project = findProject(projects?.data ?? [], query);
+    if (!project) {
+      throw new Error(`No single project matches "${query}"`);
+    }
+
+    await startTimerMutation.mutateAsync({ projectId: project.id });
+    setTimerStatus({
+      isRunning: true,
+      elapsedTime: 0,
+      projectName: project.name,
+      projectId: project.id,
+    });
+
+    return `Timer started for ${project.name}`;
+  };
+
+  const stop = async () => {
+    const status = await queryClient.fetchQuery(
+      trpc.trackerEntries.getTimerStatus.queryOptions(),
+    );
+    if (!status?.isRunning) {
+      throw new Error("No timer is running");
+    }
+
+    const result = await stopTimerMutation.mutateAsync({});
+    setTimerStatus({
+      isRunning: false,
+      elapsedTime: 0,
+      projectName: null,
+      projectId: null,
+    });
+
+    if (result?.discarded) {
+      return "Timer discarded, the entry was under 1 minute";
+    }
+    return `${secondsToHoursAndMinutes(status.elapsedTime)} added to ${status.currentEntry?.trackerProject?.name ?? "the project"}`;
+  };
+
+  useEffect(() => {
+    if (!isDesktopApp()) {
+      return;
+    }
+
+    const unlisten = listenQueued<NativeTimerAction>(
+      "desktop-timer",
+      async ({ payload }) => {
+        try {
+          const message =
+            payload.action === "start"
+              ? await start(payload.project)
+              : await stop();
+          await timerResult(payload.id, true, message);
+        } catch (error) {
+          await timerResult(
+            payload.id,
+            false,
+            error instanceof Error ? error.message : "Something went wrong",
+          );
+        } finally {
+          invalidate();
+        }
+      },
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return null;
+}
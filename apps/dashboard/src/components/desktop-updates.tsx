@@ -0,0 +1,108 @@
+"use client";
+
+import {
+  listen,
+  type NativeReadyUpdate,
+  pendingUpdate,
+  updateAction,
+} from "@midday/desktop-client/core";
+import { isDesktopApp } from "@midday/desktop-client/platform";
+import { Button } from "@midday/ui/button";
+import {
+  DropdownMenu,
+  DropdownMenuContent,
+  DropdownMenuItem,
+  DropdownMenuTrigger,
+} from "@midday/ui/dropdown-menu";
+import { ToastAction } from "@midday/ui/toast";
+import { useToast } from "@midday/ui/use-toast";
+import { useEffect } from "react";
+
+const REMIND_OPTIONS = [
+  { days: 1, label: "Tomorrow" },
+  { days: 3, label: "In 3 days" },
+  { days: 7, label: "In a week" },
+];
+
+// Offers updates the desktop shell downloaded in the background
+export function DesktopUpdates() {
+  const { toast } = useToast();
+
+  useEffect(() => {
+    if (!isDesktopApp()) {
+      return;
+    }
+
+    const offer = (update: NativeReadyUpdate) => {
+      if (update.installOnQuit) {
+        return;
+      }
+
+      const { dismiss } = toast({
+        duration: 60000,
+        title: `Midday ${update.version} is ready to install`,
+        description: update.notes ?? undefined,
+        footer: (
+          <div className="flex flex-wrap gap-2 mt-4">
+            <DropdownMenu>
+              <DropdownMenuTrigger asChild>
+                <Button variant="outline" size="sm" className="pl-5 pr-5">
+                  Remind me
+                </Button>
+              </DropdownMenuTrigger>
+              <DropdownMenuContent align="start">
+                {REMIND_OPTIONS.map(({ days, label }) => (
+                  <DropdownMenuItem
+                    key={days}
+                    onSelect={() => {
+                      dismiss();
+                      updateAction("remindLater", days);
+                    }}
+                  >
+                    {label}
+                  </DropdownMenuItem>
+                ))}
+              </DropdownMenuContent>
+            </DropdownMenu>
+            <ToastAction
+              altText="Skip this version"
+              onClick={() => updateAction("skip")}
+              className="pl-5 pr-5"
+            >
+              Skip
+            </ToastAction>
+            <ToastAction
+              altText="Install when I quit"
+              onClick={() => updateAction("installOnQuit")}
+              className="pl-5 pr-5"
+            >
+              On quit
+            </ToastAction>
+            <ToastAction
+              altText="Install now"
+              onClick={() => updateAction("installNow")}
+              className="pl-5 pr-5 bg-primary text-primary-foreground hover:bg-primary/90"
+            >
+              Install now
+            </ToastAction>
+          </div>
+        ),
+      });
+    };
+
+    // Downloaded before the dashboard loaded
+    pendingUpdate()
+      .then((update) => update && offer(update))
+      .catch(() => {});
+
+    const unlisten = listen<NativeReadyUpdate>("update-ready", ({ payload }) =>
+      offer(payload),
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return null;
+}
@@ -0,0 +1,404 @@
+"use client";
+
+import {
+  ackUpload,
+  discardDrop,
+  finishedUploads,
+  listen,
+  listenQueued,
+  type NativeArchiveProgress,
+  type NativeDuplicateMatch,
+  type NativePaymentCode,
+  type NativePluginUpload,
+  type NativeUploadedFile,
+  previewReceipts,
+  runPluginUpload,
+  setUploadSession,
+  uploadDroppedFiles,
+} from "@midday/desktop-client/core";
+import { isDesktopApp } from "@midday/desktop-client/platform";
+import { createClient } from "@midday/supabase/client";
+import { ToastAction } from "@midday/ui/toast";
+import { useToast } from "@midday/ui/use-toast";
+import { useMutation, useQueryClient } from "@tanstack/react-query";
+import { useEffect, useRef } from "react";
+import { useTransactionParams } from "@/hooks/use-transaction-params";
+import { useUserQuery } from "@/hooks/use-user";
+import { useTRPC } from "@/trpc/client";
+
+type Position = { x: number; y: number };
+
+type NativeDrop = {
+  dropId: number;
+  paths: string[];
+  position: Position;
+  // Read by the shell, the webview gets no key events during an OS drag
+  shiftKey: boolean;
+};
+
+// Upload zones mark themselves with `data-desktop-drop-target="inbox|vault"`
+// and Vault zones their folder with `data-desktop-drop-folder="a/b"`. The
+// transaction import marks itself with "import" and gets the drop as a
+// `desktop-import-drop` event.
+function findDropZone({ x, y }: Position) {
+  return (
+    document
+      .elementFromPoint(x, y)
+      ?.closest<HTMLElement>("[data-desktop-drop-target]") ?? null
+  );
+}
+
+function findDropTarget(position: Position) {
+  const element = findDropZone(position);
+  const target = element?.dataset.desktopDropTarget;
+  if (target !== "inbox" && target !== "vault") {
+    return null;
+  }
+
+  const folder = element?.dataset.desktopDropFolder
+    ?.split("/")
+    .filter(Boolean);
+  return {
+    target,
+    folder: folder?.length ? folder : undefined,
+  };
+}
+
+// Reference or message for the payer, then where the money goes
+function paymentNote(code: NativePaymentCode) {
+  return [code.reference ?? code.message, `IBAN ${code.iban}`]
+    .filter(Boolean)
+    .join("\n");
+}
+
+// Bridges native drag and drop and uploads in the desktop shell
+export function DesktopUploads() {
+  const trpc = useTRPC();
+  const queryClient = useQueryClient();
+  const { data: user } = useUserQuery();
+  const { toast } = useToast();
+  const { setParams: setTransactionParams } = useTransactionParams();
+  // Ids of finished uploads being processed
+  const processingRef = useRef(new Set<string>());
+  const processAttachmentsMutation = useMutation(
+    trpc.inbox.processAttachments.mutationOptions(),
+  );
+  const processDocumentMutation = useMutation(
+    trpc.documents.processDocument.mutationOptions(),
+  );
+
+  // Keep the shell's storage session in sync with ours
+  useEffect(() => {
+    if (!isDesktopApp() || !user?.teamId) {
+      return;
+    }
+
+    const supabase = createClient();
+    const teamId = user.teamId;
+
+    const sync = async (accessToken?: string) => {
+      try {
+        await setUploadSession(
+          accessToken
+            ? {
+                storageUrl: `${process.env.NEXT_PUBLIC_SUPABASE_URL}/storage/v1`,
+                accessToken,
+                apiKey: process.env.NEXT_PUBLIC_SUPABASE_PUBLISHABLE_KEY!,
+                teamId,
+              }
+            : null,
+        );
+      } catch (error) {
+        console.error("Failed to set upload session:", error);
+      }
+    };
+
+    supabase.auth.getSession().then(({ data }) => {
+      sync(data.session?.access_token);
+    });
+
+    const {
+      data: { subscription },
+    } = supabase.auth.onAuthStateChange((_event, session) => {
+      sync(session?.access_token);
+    });
+
+    return () => subscription.unsubscribe();
+  }, [user?.teamId]);
+
+  useEffect(() => {
+    if (!isDesktopApp()) {
+      return;
+    }
+
+    // Creates the Inbox item or Vault document for a file the shell uploaded.
+    // The shell keeps it until acknowledged, so a file that finished while
+    // the dashboard wasn't open or failed to process is handled next time.
+    const processing = processingRef.current;
+    const processUpload = (file: NativeUploadedFile) => {
+      if (processing.has(file.id)) {
+        return;
+      }
+      processing.add(file.id);
+
+      const input = [
+        {
+          filePath: file.filePath,
+          mimetype: file.mimetype,
+          size: file.size,
+          senderEmail: file.email?.senderEmail ?? undefined,
+          subject: file.email?.subject ?? undefined,
+          date: file.email?.date ?? undefined,
+          hints: file.hints ?? undefined,
+        },
+      ];
+      const options = (queryKey: readonly unknown[]) => ({
+        onSuccess: () => {
+          queryClient.invalidateQueries({ queryKey });
+          ackUpload(file.id).catch((error) => {
+            console.error("Failed to acknowledge upload:", error);
+          });
+        },
+        onError: () => {
+          processing.delete(file.id);
+        },
+      });
+
+      if (file.target === "inbox") {
+        processAttachmentsMutation.mutate(
+          input,
+          options(trpc.inbox.get.infiniteQueryKey()),
+        );
+      } else {
+        processDocumentMutation.mutate(
+          input,
+          options(trpc.documents.get.infiniteQueryKey()),
+        );
+      }
+    };
+
+    finishedUploads()
+      .then((files) => {
+        for (const file of files) {
+          processUpload(file);
+        }
+      })
+      .catch((error) => {
+        console.error("Failed to read finished uploads:", error);
+      });
+
+    // The zone under the pointer is highlighted while files are dragged over
+    // the window, like react-dropzone does for browser drags
+    let highlighted: HTMLElement | null = null;
+    const highlight = (position: Position | null) => {
+      const element = position ? findDropZone(position) : null;
+      if (element === highlighted) {
+        return;
+      }
+
+      highlighted?.removeAttribute("data-desktop-drag-active");
+      element?.setAttribute("data-desktop-drag-active", "true");
+      highlighted = element;
+    };
+
+    const unlisteners: Promise<() => void>[] = [
+      listen<{ position: Position }>("native-drag-enter", ({ payload }) => {
+        highlight(payload.position);
+      }),
+
+      listen<Position>("native-drag-over", ({ payload }) => {
+        highlight(payload);
+      }),
+
+      listen("native-drag-leave", () => {
+        highlight(null);
+      }),
+
+      listen<NativeDrop>("native-drop", async ({ payload }) => {
+        highlight(null);
+
+        const zone = findDropZone(payload.position);
+        if (zone?.dataset.desktopDropTarget === "import") {
+          zone.dispatchEvent(
+            new CustomEvent("desktop-import-drop", { detail: payload.dropId }),
+          );
+          return;
+        }
+
+        const drop = findDropTarget(payload.position);
+        if (!drop) {
+          await discardDrop(payload.dropId);
+          return;
+        }
+
+        // Holding Shift while dropping merges the dropped images into one PDF
+        const merge = payload.shiftKey;
+
+        try {
+          await uploadDroppedFiles(
+            payload.dropId,
+            drop.target,
+            drop.folder,
+            merge,
+          );
+          // Contacts and calendars are imported instead, see DesktopImports
+          const uploads = payload.paths.filter(
+            (path) => !/\.(vcf|vcard|ics|ical)$/i.test(path),
+          ).length;
+          if (uploads > 0) {
+            toast({
+              title: merge
+                ? "Merging images into one PDF"
+                : `Uploading ${uploads} files`,
+              duration: 2000,
+            });
+          }
+        } catch (error) {
+          console.error("Failed to upload dropped files:", error);
+          await discardDrop(payload.dropId);
+          return;
+        }
+
+        // Read from the PDF locally, so it shows before the upload is done
+        if (!merge && payload.paths.length === 1) {
+          const [preview] = await previewReceipts(payload.paths).catch(
+            () => [],
+          );
+          const hints = preview?.hints;
+
+          if (hints) {
+            toast({
+              duration: 4000,
+              title: hints.vendor ?? "Receipt",
+              description: [
+                hints.date,
+                hints.total !== null
+                  ? `${hints.currency ?? ""} ${hints.total.toFixed(2)}`.trim()
+                  : null,
+              ]
+                .filter(Boolean)
+                .join(" · "),
+            });
+          }
+        }
+      }),
+
+      listen<NativeUploadedFile>("upload-finished", ({ payload }) => {
+        processUpload(payload);
+      }),
+
+      // Watched files are uploaded through the upload plugin from here
+      listenQueued<NativePluginUpload>(
+        "plugin-upload-requested",
+        ({ payload }) => {
+          runPluginUpload(payload).catch((error) => {
+            console.error("Failed to upload watched file:", error);
+          });
+        },
+      ),
+
+      listen<{ fileName: string; codes: NativePaymentCode[] }>(
+        "payment-code-detected",
+        ({ payload }) => {
+          const [code] = payload.codes;
+          if (!code) {
+            return;
+          }
+
+          toast({
+            duration: 15000,
+            title: `Payment details found in ${payload.fileName}`,
+            description: [
+              code.creditor.name,
+              code.amount !== null
+                ? `${code.currency} ${code.amount.toFixed(2)}`
+                : null,
+            ]
+              .filter(Boolean)
+              .join(" · "),
+            footer: (
+              <div className="flex space-x-2 mt-4">
+                <ToastAction altText="Dismiss" className="pl-5 pr-5">
+                  Dismiss
+                </ToastAction>
+                <ToastAction
+                  altText="Create transaction"
+                  onClick={() =>
+                    setTransactionParams({
+                      createTransaction: true,
+                      prefillName: code.creditor.name || null,
+                      prefillAmount: code.amount,
+                      prefillCurrency: code.currency,
+                      prefillNote: paymentNote(code),
+                    })
+                  }
+                  className="pl-5 pr-5 bg-primary text-primary-foreground hover:bg-primary/90"
+                >
+                  Create transaction
+                </ToastAction>
+              </div>
+            ),
+          });
+        },
+      ),
+
+      listen<NativeArchiveProgress>("archive-progress", ({ payload }) => {
+        // Only announce the batch, the desktop app notifies when it's done
+        if (payload.uploaded + payload.failed + payload.duplicates > 0) {
+          return;
+        }
+
+        toast({
+          duration: 4000,
+          title: `Uploading ${payload.total} ${payload.total === 1 ? "file" : "files"} from ${payload.fileName}`,
+          description: payload.skipped
+            ? `${payload.skipped} other ${payload.skipped === 1 ? "entry" : "entries"} skipped`
+            : undefined,
+        });
+      }),
+
+      listen<{
+        fileName: string;
+        duplicate: NativeDuplicateMatch;
+        skipped: boolean;
+      }>("upload-duplicate", ({ payload }) => {
+        toast({
+          duration: 4000,
+          title: payload.skipped
+            ? `Skipped ${payload.fileName}`
+            : `${payload.fileName} may be a duplicate`,
+          description: `${payload.duplicate.kind === "exact" ? "Already uploaded" : "Looks like"} ${payload.duplicate.fileName}`,
+        });
+      }),
+
+      listen<{
+        fileName: string;
+        error: string;
+        retryAt: number | null;
+        waiting: boolean;
+      }>("upload-failed", ({ payload }) => {
+        // The desktop queue retries on its own and waits while offline or
+        // signed out, only report uploads it gave up on
+        if (payload.retryAt || payload.waiting) {
+          return;
+        }
+
+        toast({
+          duration: 4000,
+          variant: "error",
+          title: `Failed to upload ${payload.fileName}`,
+          description: payload.error,
+        });
+      }),
+    ];
+
+    return () => {
+      highlight(null);
+      for (const unlisten of unlisteners) {
+        unlisten.then((fn) => fn());
+      }
+    };
+  }, []);
+
+  return null;
+}
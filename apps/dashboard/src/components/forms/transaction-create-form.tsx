@@ -102,7 +102,13 @@ export function TransactionCreateForm() {
   const _queryClient = useQueryClient();
   const { track } = useOpenPanel();
   const invalidateTransactionQueries = useInvalidateTransactionQueries();
-  const { setParams } = useTransactionParams();
+  const {
+    setParams,
+    prefillName,
+    prefillAmount,
+    prefillCurrency,
+    prefillNote,
+  } = useTransactionParams();
   const [isOpen, setIsOpen] = useState(false);
   const [isUploadingAttachments, setIsUploadingAttachments] = useState(false);
   const [temporaryAttachmentId] = useState(() => nanoid());
@@ -130,13 +136,15 @@ export function TransactionCreateForm() {
 
   const form = useZodForm(formSchema, {
     defaultValues: {
-      name: undefined,
+      name: prefillName ?? undefined,
+      // Prefilled amounts are payments, stored as negative expenses
+      amount: prefillAmount !== null ? -Math.abs(prefillAmount) : undefined,
       categorySlug: undefined,
       date: formatISO(new Date(), { representation: "date" }),
       bankAccountId: accounts?.at(0)?.id,
       assignedId: user?.id,
-      note: undefined,
-      currency: team?.baseCurrency ?? undefined,
+      note: prefillNote ?? undefined,
+      currency: prefillCurrency ?? team?.baseCurrency ?? undefined,
       attachments: undefined,
       internal: undefined,
       transactionType: "expense" as const,
@@ -154,7 +162,7 @@ export function TransactionCreateForm() {
       if (firstAccount?.id) {
         form.setValue("bankAccountId", firstAccount.id);
         // Also set currency from the account if available
-        if (firstAccount.currency) {
+        if (firstAccount.currency && !prefillCurrency) {
           form.setValue("currency", firstAccount.currency);
         }
       }
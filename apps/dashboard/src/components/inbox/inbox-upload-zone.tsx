@@ -199,12 +199,13 @@ export function UploadZone({ children, onUploadComplete }: Props) {
   return (
     <div
       {...getRootProps({ onClick: (evt) => evt.stopPropagation() })}
-      className="relative h-full"
+      className="group/drop relative h-full"
+      data-desktop-drop-target="inbox"
     >
       <div className="absolute top-0 bottom-0 right-0 left-0 z-[51] pointer-events-none">
         <div
           className={cn(
-            "bg-background h-full flex items-center justify-center text-center invisible",
+            "bg-background h-full flex items-center justify-center text-center invisible group-data-[desktop-drag-active=true]/drop:visible",
             isDragActive && "visible",
           )}
         >
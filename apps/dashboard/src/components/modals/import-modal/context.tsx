@@ -1,6 +1,7 @@
 import { createContext, useContext } from "react";
 import type { Control, UseFormSetValue, UseFormWatch } from "react-hook-form";
 import { z } from "zod/v3";
+import type { NativeCsv } from "./utils";
 
 export const mappableFields = {
   date: {
@@ -61,6 +62,9 @@ export const ImportCsvContext = createContext<{
   setFileColumns: (columns: string[] | null) => void;
   firstRows: Record<string, string>[] | null;
   setFirstRows: (rows: Record<string, string>[] | null) => void;
+  // Set instead of `file` for a CSV the desktop app reads
+  nativeFile: NativeCsv | null;
+  setNativeFile: (file: NativeCsv | null) => void;
   control: Control<ImportCsvFormData>;
   watch: UseFormWatch<ImportCsvFormData>;
   setValue: UseFormSetValue<ImportCsvFormData>;
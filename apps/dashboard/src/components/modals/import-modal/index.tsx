@@ -29,6 +29,7 @@ import { ImportCsvContext, importSchema } from "./context";
 import { FieldMapping } from "./field-mapping";
 import { getBalanceFromLatestDate } from "./field-mapping.utils";
 import { SelectFile } from "./select-file";
+import { closeNativeFile, type NativeCsv, nativeCsvToFile } from "./utils";
 
 const pages = ["select-file", "confirm-import"] as const;
 
@@ -48,6 +49,8 @@ export function ImportModal() {
   const [firstRows, setFirstRows] = useState<Record<string, string>[] | null>(
     null,
   );
+  const [nativeFile, setNativeFile] = useState<NativeCsv | null>(null);
+  const nativeFileRef = useRef<NativeCsv | null>(null);
   const [visibleProgressStep, setVisibleProgressStep] = useState<
     string | undefined
   >();
@@ -120,6 +123,16 @@ export function ImportModal() {
 
   const file = watch("file");
 
+  // Lets the desktop app forget a picked CSV that is replaced or done with
+  const changeNativeFile = (next: NativeCsv | null) => {
+    const current = nativeFileRef.current;
+    if (current && current.id !== next?.id) {
+      closeNativeFile(current.id);
+    }
+    nativeFileRef.current = next;
+    setNativeFile(next);
+  };
+
   const requestClose = () => {
     setParams({
       step: null,
@@ -139,6 +152,7 @@ export function ImportModal() {
     }
     setFileColumns(null);
     setFirstRows(null);
+    changeNativeFile(null);
     setPageNumber(0);
     setJobId(undefined);
     reset();
@@ -291,10 +305,10 @@ export function ImportModal() {
 
   // Go to second page if file looks good
   useEffect(() => {
-    if (file && fileColumns && firstRows && pageNumber === 0) {
+    if ((file || nativeFile) && fileColumns && firstRows && pageNumber === 0) {
       setPageNumber(1);
     }
-  }, [file, fileColumns, firstRows, pageNumber]);
+  }, [file, nativeFile, fileColumns, firstRows, pageNumber]);
 
   const importStepLabels: Record<string, string> = {
     analyzing: "Analyzing...",
@@ -373,6 +387,8 @@ export function ImportModal() {
                   setFileColumns,
                   firstRows,
                   setFirstRows,
+                  nativeFile,
+                  setNativeFile: changeNativeFile,
                   control,
                   watch,
                   setValue,
@@ -388,11 +404,27 @@ export function ImportModal() {
 
                       setIsImporting(true);
 
-                      const filename = stripSpecialCharacters(data.file.name);
+                      let importFile = data.file;
+                      if (nativeFile) {
+                        try {
+                          importFile = await nativeCsvToFile(nativeFile);
+                        } catch (error) {
+                          console.error("Failed to read CSV file:", error);
+                          setIsImporting(false);
+                          toast({
+                            duration: 3500,
+                            variant: "error",
+                            title: "Failed to read CSV file.",
+                          });
+                          return;
+                        }
+                      }
+
+                      const filename = stripSpecialCharacters(importFile.name);
                       const { path } = await uploadFile({
                         bucket: "vault",
                         path: [user?.team?.id ?? "", "imports", filename],
-                        file,
+                        file: importFile,
                       });
 
                       const currentBalance =
@@ -441,6 +473,7 @@ export function ImportModal() {
                             reset();
                             setFileColumns(null);
                             setFirstRows(null);
+                            changeNativeFile(null);
                           }}
                         >
                           Choose another file
@@ -1,3 +1,5 @@
+import type { NativeImportFile } from "@midday/desktop-client/core";
+import { isDesktopApp } from "@midday/desktop-client/platform";
 import { cn } from "@midday/ui/cn";
 import { Spinner } from "@midday/ui/spinner";
 import Papa from "papaparse";
@@ -5,13 +7,45 @@ import { useCallback, useEffect, useRef, useState } from "react";
 import Dropzone, { type FileRejection } from "react-dropzone";
 import { Controller } from "react-hook-form";
 import { useCsvContext } from "./context";
-import { readLines } from "./utils";
+import {
+  closeNativeFile,
+  isBankStatement,
+  nativeColumns,
+  nativeFirstRows,
+  nativeStatementToCsv,
+  readLines,
+  statementToCsv,
+} from "./utils";
+
+const csvTypes = {
+  "text/csv": [".csv"],
+  "application/csv": [".csv"],
+  "text/plain": [".csv"],
+  "application/vnd.ms-excel": [".csv"],
+};
+
+// The desktop app converts bank statements to CSV
+const desktopTypes = {
+  ...csvTypes,
+  "text/plain": [".csv", ".sta", ".mt940", ".940"],
+  "application/x-ofx": [".ofx", ".qfx"],
+  "application/xml": [".xml"],
+  "text/xml": [".xml"],
+};
 
 export function SelectFile() {
-  const { watch, control, setFileColumns, setFirstRows } = useCsvContext();
+  const {
+    watch,
+    control,
+    setValue,
+    setFileColumns,
+    setFirstRows,
+    setNativeFile,
+  } = useCsvContext();
   const [error, setError] = useState<string | null>(null);
   const [isLoading, setIsLoading] = useState(false);
   const processingRef = useRef<string | null>(null);
+  const dropZoneRef = useRef<HTMLDivElement>(null);
 
   const file = watch("file");
 
@@ -83,20 +117,124 @@ export function SelectFile() {
     }
   }, [file, processFile]);
 
+  // The desktop app reads CSV files itself, without a size limit and with
+  // the encoding, delimiter and formats detected from the whole file
+  const openNativeFile = useCallback(
+    async (picked: NativeImportFile) => {
+      setIsLoading(true);
+      setError(null);
+      setFileColumns(null);
+      setFirstRows(null);
+      setNativeFile(null);
+
+      const { inspectCsv } = await import("@midday/desktop-client/core");
+
+      try {
+        if (isBankStatement(picked.fileName)) {
+          const statement = await nativeStatementToCsv(picked);
+          closeNativeFile(picked.id);
+          setValue("file", statement);
+          setIsLoading(false);
+          return;
+        }
+
+        const inspection = await inspectCsv(picked.id);
+        const nativeFile = { ...picked, inspection };
+        if (inspection.rows < 2) {
+          closeNativeFile(picked.id);
+          setError("CSV file must have at least 2 rows.");
+          setIsLoading(false);
+          return;
+        }
+
+        setNativeFile(nativeFile);
+        setFileColumns(nativeColumns(nativeFile));
+        setFirstRows(nativeFirstRows(nativeFile));
+      } catch (err) {
+        console.error("Error reading file:", err);
+        closeNativeFile(picked.id);
+        setError(typeof err === "string" ? err : "Failed to read CSV file.");
+      }
+      setIsLoading(false);
+    },
+    [setValue, setFileColumns, setFirstRows, setNativeFile],
+  );
+
+  const pickNativeFile = async () => {
+    const { pickImportFile } = await import("@midday/desktop-client/core");
+    try {
+      const picked = await pickImportFile();
+      if (picked) {
+        await openNativeFile(picked);
+      }
+    } catch (err) {
+      console.error("Error picking file:", err);
+      setError("Failed to open file.");
+    }
+  };
+
+  // Files dropped onto the zone in the desktop app, see DesktopUploads
+  useEffect(() => {
+    const element = dropZoneRef.current;
+    if (!isDesktopApp() || !element) {
+      return;
+    }
+
+    const onDrop = async (event: Event) => {
+      const { importDroppedFile } = await import(
+        "@midday/desktop-client/core"
+      );
+      try {
+        await openNativeFile(
+          await importDroppedFile((event as CustomEvent<number>).detail),
+        );
+      } catch (err) {
+        setError(typeof err === "string" ? err : "Failed to open file.");
+      }
+    };
+
+    element.addEventListener("desktop-import-drop", onDrop);
+    return () => element.removeEventListener("desktop-import-drop", onDrop);
+  }, [openNativeFile]);
+
   return (
-    <div className="flex flex-col gap-3">
+    <div
+      ref={dropZoneRef}
+      className="group/drop flex flex-col gap-3"
+      data-desktop-drop-target={isDesktopApp() ? "import" : undefined}
+    >
       <Controller
         control={control}
         name="file"
         render={({ field: { onChange, onBlur } }) => (
           <Dropzone
-            onDrop={(acceptedFiles) => {
-              const file = acceptedFiles[0];
-              if (file) {
-                onChange(file);
-                // Process file immediately to avoid waiting for watch to update
-                processFile(file);
+            onDrop={async (acceptedFiles) => {
+              let file = acceptedFiles[0];
+              if (!file) {
+                return;
+              }
+
+              if (isDesktopApp() && isBankStatement(file.name)) {
+                setIsLoading(true);
+                setError(null);
+                try {
+                  file = await statementToCsv(file);
+                } catch (err) {
+                  console.error("Error parsing bank statement:", err);
+                  setError(
+                    typeof err === "string"
+                      ? err
+                      : "Failed to read bank statement.",
+                  );
+                  setIsLoading(false);
+                  return;
+                }
               }
+
+              setNativeFile(null);
+              onChange(file);
+              // Process file immediately to avoid waiting for watch to update
+              processFile(file);
             }}
             onDropRejected={(fileRejections: FileRejection[]) => {
               const rejection = fileRejections[0];
@@ -113,20 +251,21 @@ export function SelectFile() {
               }
             }}
             maxFiles={1}
-            accept={{
-              "text/csv": [".csv"],
-              "application/csv": [".csv"],
-              "text/plain": [".csv"],
-              "application/vnd.ms-excel": [".csv"],
-            }}
-            maxSize={5000000}
+            accept={isDesktopApp() ? desktopTypes : csvTypes}
+            // The desktop app picks and reads the file itself
+            maxSize={isDesktopApp() ? undefined : 5000000}
+            noClick={isDesktopApp()}
+            noKeyboard={isDesktopApp()}
           >
             {({ getRootProps, getInputProps, isDragActive, isDragReject }) => (
               <div
-                {...getRootProps()}
+                {...getRootProps(
+                  isDesktopApp() ? { onClick: pickNativeFile } : undefined,
+                )}
                 className={cn(
                   "w-full border border-dashed h-[200px] mt-8 mb-8 flex items-center justify-center",
                   isDragActive && "bg-secondary text-primary",
+                  "group-data-[desktop-drag-active=true]/drop:bg-secondary group-data-[desktop-drag-active=true]/drop:text-primary",
                   isDragReject && "border-destructive",
                 )}
               >
@@ -141,8 +280,12 @@ export function SelectFile() {
                   ) : (
                     <div>
                       <p>Drop your file here, or click to browse.</p>
-                      <span>5MB file limit. </span>
-                      <span className="mt-2 text-[10px]">CSV format</span>
+                      {!isDesktopApp() && <span>5MB file limit. </span>}
+                      <span className="mt-2 text-[10px]">
+                        {isDesktopApp()
+                          ? "CSV, OFX, CAMT.053 or MT940 format"
+                          : "CSV format"}
+                      </span>
                     </div>
                   )}
 
@@ -1,3 +1,17 @@
+import type {
+  NativeCsvInspection,
+  NativeImportFile,
+  NativeStatement,
+} from "@midday/desktop-client/core";
+import { format, isValid, parse } from "date-fns";
+import Papa from "papaparse";
+
+// Rows read from the desktop app per call
+const CHUNK_ROWS = 5000;
+
+/** A CSV picked in the desktop app, read there instead of in the browser */
+export type NativeCsv = NativeImportFile & { inspection: NativeCsvInspection };
+
 export const readLines = async (file: File, count = 4): Promise<string> => {
   const reader = file.stream().getReader();
   const decoder = new TextDecoder("utf-8");
@@ -17,3 +31,129 @@ export const readLines = async (file: File, count = 4): Promise<string> => {
 
   return result.join("\n");
 };
+
+const statementExtensions = [".ofx", ".qfx", ".xml", ".sta", ".mt940", ".940"];
+
+export const isBankStatement = (fileName: string) => {
+  const name = fileName.toLowerCase();
+  return statementExtensions.some((extension) => name.endsWith(extension));
+};
+
+const statementCsv = (statement: NativeStatement, fileName: string) => {
+  const csv = Papa.unparse(
+    statement.transactions.map((transaction) => ({
+      Date: transaction.date,
+      Description: transaction.description ?? "",
+      Counterparty: transaction.counterparty ?? "",
+      Amount: transaction.amount,
+      Balance: transaction.balance ?? "",
+      Currency: transaction.currency,
+    })),
+  );
+
+  return new File([csv], `${fileName.replace(/\.[^.]+$/, "")}.csv`, {
+    type: "text/csv",
+  });
+};
+
+/**
+ * Convert an OFX, CAMT.053 or MT940 statement to a CSV the import can map.
+ * Parsed by the desktop app, so only call this in it.
+ */
+export const statementToCsv = async (file: File): Promise<File> => {
+  const { parseStatement } = await import("@midday/desktop-client/core");
+  return statementCsv(await parseStatement(file), file.name);
+};
+
+/** Like `statementToCsv`, for a statement picked in the desktop app */
+export const nativeStatementToCsv = async (
+  file: NativeImportFile,
+): Promise<File> => {
+  const { parseStatementFile } = await import("@midday/desktop-client/core");
+  return statementCsv(await parseStatementFile(file.id), file.fileName);
+};
+
+/** Let the desktop app forget a picked file */
+export const closeNativeFile = (id: number) =>
+  import("@midday/desktop-client/core")
+    .then(({ closeImportFile }) => closeImportFile(id))
+    .catch((error) => {
+      console.error("Failed to close import file:", error);
+    });
+
+export const nativeColumns = ({ inspection }: NativeCsv) =>
+  inspection.columns.map((column) => column.name);
+
+// `-1.234,56`, `(12.50)` or `€ 3,00` as `-1234.56`, `-12.50` and `3.00`
+const toAmount = (value: string, decimalSeparator: "." | ",") => {
+  const trimmed = value.trim();
+  if (!trimmed) {
+    return trimmed;
+  }
+
+  const negative = /^[-−(]|[-)]$/.test(trimmed);
+  const digits = trimmed
+    .replace(decimalSeparator === "," ? /[^\d,]/g : /[^\d.]/g, "")
+    .replace(",", ".");
+  return `${negative ? "-" : ""}${digits}`;
+};
+
+// Dates the desktop app recognized as yyyy-MM-dd, others are left as is.
+// Days and months may have one digit, like the desktop app accepts.
+const toDate = (value: string, dateFormat: string) => {
+  const lenient = dateFormat.replace(/\bdd\b/, "d").replace(/\bMM\b/, "M");
+  const date = parse(value.trim(), lenient, new Date());
+  return isValid(date) ? format(date, "yyyy-MM-dd") : value;
+};
+
+/**
+ * Write dates and amounts in the formats the desktop app detected as
+ * yyyy-MM-dd and with a `.` decimal separator, so the import doesn't have
+ * to guess them again.
+ */
+export const normalizeRow = ({ inspection }: NativeCsv, row: string[]) =>
+  inspection.columns.map((column, index) => {
+    const value = row[index] ?? "";
+    if (column.kind === "date" && column.dateFormat) {
+      return toDate(value, column.dateFormat);
+    }
+    if (column.kind === "number") {
+      return toAmount(value, inspection.decimalSeparator);
+    }
+    return value;
+  });
+
+/** The preview rows of a picked CSV, by column name */
+export const nativeFirstRows = (file: NativeCsv) => {
+  const columns = nativeColumns(file);
+  return file.inspection.preview.map((row) =>
+    Object.fromEntries(
+      normalizeRow(file, row).map((value, index) => [columns[index], value]),
+    ),
+  );
+};
+
+/**
+ * Read a CSV picked in the desktop app in chunks into a UTF-8, comma
+ * separated file with normalized dates and amounts, ready to upload.
+ */
+export const nativeCsvToFile = async (file: NativeCsv): Promise<File> => {
+  const { readCsvChunk } = await import("@midday/desktop-client/core");
+  const rows: string[][] = [];
+  let offset = 0;
+  let done = false;
+
+  while (!done) {
+    const chunk = await readCsvChunk(file.id, offset, CHUNK_ROWS);
+    for (const row of chunk.rows) {
+      rows.push(normalizeRow(file, row));
+    }
+    offset += chunk.rows.length;
+    done = chunk.done;
+  }
+
+  const csv = Papa.unparse({ fields: nativeColumns(file), data: rows });
+  return new File([csv], `${file.fileName.replace(/\.[^.]+$/, "")}.csv`, {
+    type: "text/csv",
+  });
+};
@@ -19,6 +19,8 @@ type UploadResult = {
 
 type Props = {
   children: ReactNode;
+  /** Folder path files dropped in the desktop app are uploaded to */
+  folder?: string[];
   onUpload?: (
     results: {
       file_path: string[];
@@ -28,7 +30,7 @@ type Props = {
   ) => void;
 };
 
-export function VaultUploadZone({ onUpload, children }: Props) {
+export function VaultUploadZone({ onUpload, folder, children }: Props) {
   const trpc = useTRPC();
   const { data: user } = useUserQuery();
   const { track } = useOpenPanel();
@@ -76,7 +78,7 @@ export function VaultUploadZone({ onUpload, children }: Props) {
     setShowProgress(true);
 
     // Add uploaded (team_id)
-    const path = [user?.teamId] as string[];
+    const path = [user?.teamId, ...(folder ?? [])] as string[];
 
     try {
       const results = (await Promise.all(
@@ -192,13 +194,15 @@ export function VaultUploadZone({ onUpload, children }: Props) {
 
   return (
     <div
-      className="relative h-full"
+      className="group/drop relative h-full"
+      data-desktop-drop-target="vault"
+      data-desktop-drop-folder={folder?.join("/")}
       {...getRootProps({ onClick: (evt) => evt.stopPropagation() })}
     >
       <div className="absolute top-0 right-0 left-0 z-[51] w-full pointer-events-none h-[calc(100vh-150px)]">
         <div
           className={cn(
-            "bg-background h-full w-full flex items-center justify-center text-center",
+            "bg-background h-full w-full flex items-center justify-center text-center group-data-[desktop-drag-active=true]/drop:visible",
             isDragActive ? "visible" : "invisible",
           )}
         >
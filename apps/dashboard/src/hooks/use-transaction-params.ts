@@ -1,11 +1,16 @@
 import { useQueryStates } from "nuqs";
-import { parseAsBoolean, parseAsString } from "nuqs/server";
+import { parseAsBoolean, parseAsFloat, parseAsString } from "nuqs/server";
 
 export function useTransactionParams() {
   const [params, setParams] = useQueryStates({
     transactionId: parseAsString,
     createTransaction: parseAsBoolean,
     editTransaction: parseAsString,
+    // Prefill the create form, e.g. from a payment code on a receipt
+    prefillName: parseAsString,
+    prefillAmount: parseAsFloat,
+    prefillCurrency: parseAsString,
+    prefillNote: parseAsString,
   });
 
   return {
@@ -12,33 +12,17 @@ export async function downloadFile(url: string, filename: string) {
     return;
   }
 
-  // Desktop mode - fetch through the webview (which has cookies/session),
-  // then save the blob natively to ~/Downloads via Tauri fs plugin.
+  // Desktop mode - the desktop shell downloads the URL with the webview's
+  // cookies and streams it straight to the file picked in the save dialog.
   const downloadUrl =
     url.startsWith("http://") || url.startsWith("https://")
       ? url
       : `${window.location.origin}${url}`;
 
-  const response = await fetch(downloadUrl);
-
-  if (!response.ok) {
-    throw new Error(
-      `Download failed: ${response.status} ${response.statusText}`,
-    );
-  }
-
-  const blob = await response.blob();
-  console.log("[downloadFile] Fetched blob:", {
-    size: blob.size,
-    type: blob.type,
-    filename,
-  });
-
-  const { nativeSaveFile } = await import("@midday/desktop-client/core");
+  const { nativeSaveUrl } = await import("@midday/desktop-client/core");
 
   try {
-    await nativeSaveFile(blob, filename);
-    console.log("[downloadFile] File download completed successfully");
+    await nativeSaveUrl(downloadUrl, filename);
   } catch (error) {
     console.error("[downloadFile] Failed to save file:", error);
     throw error;
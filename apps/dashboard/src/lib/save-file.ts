@@ -7,7 +7,8 @@ export async function saveFile(blob: Blob, filename: string) {
     return;
   }
 
-  // Desktop mode - write blob bytes to ~/Downloads via Tauri fs plugin
+  // Desktop mode - stream the blob to the desktop shell, which writes it to
+  // the file picked in the save dialog
   const { nativeSaveFile } = await import("@midday/desktop-client/core");
   try {
     await nativeSaveFile(blob, filename);
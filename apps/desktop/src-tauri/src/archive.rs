@@ -0,0 +1,556 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Listener, Manager};
+
+use crate::capture;
+use crate::download;
+use crate::email;
+use crate::merge;
+use crate::upload::{self, UploadSource, UploadTarget};
+
+/// Extracted files live here until uploaded.
+const CACHE_DIR: &str = "archives";
+
+/// Archives with more entries than this are rejected, expense tools export
+/// a few hundred receipts at most.
+const MAX_ENTRIES: usize = 5_000;
+/// Larger entries are skipped.
+const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+/// Extraction stops beyond this, against zip bombs.
+const MAX_TOTAL_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Upload progress of the files from one archive, emitted as
+/// `archive-progress` whenever one of them finishes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveProgress {
+    pub id: String,
+    pub file_name: String,
+    /// Files queued for upload
+    pub total: usize,
+    pub uploaded: usize,
+    /// Failed for good, or cancelled
+    pub failed: usize,
+    /// Already uploaded before
+    pub duplicates: usize,
+    /// Entries that aren't documents, or too large
+    pub skipped: usize,
+}
+
+impl ArchiveProgress {
+    fn is_done(&self) -> bool {
+        self.uploaded + self.failed + self.duplicates >= self.total
+    }
+}
+
+struct Batch {
+    progress: ArchiveProgress,
+    /// Upload ids still in the queue
+    pending: HashSet<String>,
+}
+
+#[derive(Default)]
+struct ArchivesInner {
+    batches: Vec<Batch>,
+    /// Archives whose files are being queued right now
+    queueing: usize,
+    /// Outcomes of uploads that finished before their archive was fully
+    /// queued, e.g. duplicates skipped right away
+    early: Vec<(String, Outcome)>,
+}
+
+/// Archives whose files are still uploading.
+#[derive(Default)]
+pub struct Archives {
+    inner: Mutex<ArchivesInner>,
+}
+
+/// Outcome of a queued upload, as far as the batch is concerned.
+#[derive(Clone, Copy)]
+enum Outcome {
+    Uploaded,
+    Failed,
+    Duplicate,
+}
+
+struct Extracted {
+    files: Vec<PathBuf>,
+    skipped: usize,
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Follow the upload events of queued archive files.
+pub fn setup(app: &tauri::AppHandle) {
+    app.manage(Archives::default());
+
+    let app_handle = app.clone();
+    app.listen_any("upload-finished", move |event| {
+        if let Some(id) = payload_id(event.payload()) {
+            record(&app_handle, &id, Outcome::Uploaded);
+        }
+    });
+
+    let app_handle = app.clone();
+    app.listen_any("upload-failed", move |event| {
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+            return;
+        };
+        // Jobs that will be retried aren't done yet
+        if payload["retryAt"].is_null()
+            && let Some(id) = payload["id"].as_str()
+        {
+            record(&app_handle, id, Outcome::Failed);
+        }
+    });
+
+    let app_handle = app.clone();
+    app.listen_any("upload-duplicate", move |event| {
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+            return;
+        };
+        if payload["skipped"].as_bool() == Some(true)
+            && let Some(id) = payload["id"].as_str()
+        {
+            record(&app_handle, id, Outcome::Duplicate);
+        }
+    });
+
+    let app_handle = app.clone();
+    app.listen_any("upload-cancelled", move |event| {
+        if let Ok(id) = serde_json::from_str::<String>(event.payload()) {
+            record(&app_handle, &id, Outcome::Failed);
+        }
+    });
+}
+
+fn payload_id(payload: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(payload)
+        .ok()?
+        .get("id")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn record(app: &tauri::AppHandle, id: &str, outcome: Outcome) {
+    let Some(archives) = app.try_state::<Archives>() else {
+        return;
+    };
+
+    let progress = {
+        let mut inner = archives.inner.lock().unwrap();
+        let Some(index) = inner
+            .batches
+            .iter()
+            .position(|batch| batch.pending.contains(id))
+        else {
+            if inner.queueing > 0 {
+                inner.early.push((id.to_string(), outcome));
+            }
+            return;
+        };
+
+        let batch = &mut inner.batches[index];
+        apply(batch, id, outcome);
+        let progress = batch.progress.clone();
+        if batch.pending.is_empty() {
+            inner.batches.remove(index);
+        }
+        progress
+    };
+
+    publish(app, &progress);
+}
+
+fn apply(batch: &mut Batch, id: &str, outcome: Outcome) {
+    if !batch.pending.remove(id) {
+        return;
+    }
+    match outcome {
+        Outcome::Uploaded => batch.progress.uploaded += 1,
+        Outcome::Failed => batch.progress.failed += 1,
+        Outcome::Duplicate => batch.progress.duplicates += 1,
+    }
+}
+
+fn publish(app: &tauri::AppHandle, progress: &ArchiveProgress) {
+    let _ = app.emit("archive-progress", progress);
+    if progress.total > 0 && progress.is_done() {
+        println!(
+            "🗜️ Finished {}: {} uploaded, {} failed, {} duplicates",
+            progress.file_name, progress.uploaded, progress.failed, progress.duplicates
+        );
+        capture::notify(app, "Archive uploaded", &summary(progress));
+    }
+}
+
+fn summary(progress: &ArchiveProgress) -> String {
+    let mut summary = format!(
+        "{} of {} files from {} uploaded",
+        progress.uploaded, progress.total, progress.file_name
+    );
+    if progress.duplicates > 0 {
+        summary.push_str(&format!(
+            ", {} already uploaded before",
+            progress.duplicates
+        ));
+    }
+    if progress.failed > 0 {
+        summary.push_str(&format!(", {} failed", progress.failed));
+    }
+    summary
+}
+
+/// Extract `.zip` files and queue the documents in them. Returns the upload
+/// ids of all extracted files.
+pub async fn enqueue_archives(
+    app: &tauri::AppHandle,
+    paths: Vec<PathBuf>,
+    target: UploadTarget,
+    folder: Vec<String>,
+    source: UploadSource,
+) -> Vec<String> {
+    let dir = match app.path().app_cache_dir() {
+        Ok(dir) => dir.join(CACHE_DIR),
+        Err(e) => {
+            eprintln!("🗜️ Failed to resolve cache directory: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut ids = Vec::new();
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let extract_dir = dir.clone();
+        let archive_path = path.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            std::fs::create_dir_all(&extract_dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", extract_dir, e))?;
+            extract(&archive_path, &extract_dir)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+        let extracted = match result {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                eprintln!("🗜️ Failed to extract {:?}: {}", path, e);
+                capture::notify(app, "Couldn't open archive", &e);
+                continue;
+            }
+        };
+
+        let (emails, files): (Vec<PathBuf>, Vec<PathBuf>) = extracted
+            .files
+            .into_iter()
+            .partition(|file| email::is_email(file));
+
+        if let Some(archives) = app.try_state::<Archives>() {
+            archives.inner.lock().unwrap().queueing += 1;
+        }
+
+        let mut batch_ids: Vec<String> = files
+            .into_iter()
+            .filter_map(|file| upload::enqueue_temporary(app, file, target, folder.clone(), source))
+            .collect();
+        if !emails.is_empty() {
+            batch_ids.extend(
+                email::enqueue_emails(app, emails.clone(), target, folder.clone(), source).await,
+            );
+            for email in emails {
+                let _ = std::fs::remove_file(email);
+            }
+        }
+
+        println!(
+            "🗜️ Queued {} file(s) from {:?}, skipped {}",
+            batch_ids.len(),
+            path,
+            extracted.skipped
+        );
+        track(app, file_name, &batch_ids, extracted.skipped);
+        ids.extend(batch_ids);
+    }
+    ids
+}
+
+/// Start a progress summary for the files queued from one archive.
+fn track(app: &tauri::AppHandle, file_name: String, ids: &[String], skipped: usize) {
+    let mut batch = Batch {
+        progress: ArchiveProgress {
+            id: upload::next_upload_id(),
+            file_name,
+            total: ids.len(),
+            uploaded: 0,
+            failed: 0,
+            duplicates: 0,
+            skipped,
+        },
+        pending: ids.iter().cloned().collect(),
+    };
+
+    // Draining the early outcomes and adding the batch happen under one lock,
+    // so no outcome slips through in between
+    let progress = match app.try_state::<Archives>() {
+        Some(archives) => {
+            let mut inner = archives.inner.lock().unwrap();
+            inner.queueing = inner.queueing.saturating_sub(1);
+            for (id, outcome) in std::mem::take(&mut inner.early) {
+                if batch.pending.contains(&id) {
+                    apply(&mut batch, &id, outcome);
+                } else if inner.queueing > 0 {
+                    inner.early.push((id, outcome));
+                }
+            }
+
+            let progress = batch.progress.clone();
+            if !batch.pending.is_empty() {
+                inner.batches.push(batch);
+            }
+            progress
+        }
+        None => batch.progress,
+    };
+
+    publish(app, &progress);
+    if ids.is_empty() {
+        capture::notify(
+            app,
+            "Nothing to upload",
+            &format!("{} contains no PDFs or images", progress.file_name),
+        );
+    }
+}
+
+/// PDFs, images and emails. Everything else in an export (spreadsheets,
+/// manifests) is skipped.
+fn is_supported(path: &Path) -> bool {
+    merge::is_image(path)
+        || email::is_email(path)
+        || path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// macOS resource forks and hidden files.
+fn is_hidden(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            name.starts_with('.') || name == "__MACOSX"
+        }
+        _ => false,
+    })
+}
+
+/// Extract supported files into `dir`, flattening folders. Entries whose
+/// path would leave `dir` are skipped.
+fn extract(path: &Path, dir: &Path) -> Result<Extracted, String> {
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut archive = zip::ZipArchive::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(format!(
+            "{:?} has {} entries, at most {} are supported",
+            path,
+            archive.len(),
+            MAX_ENTRIES
+        ));
+    }
+
+    let mut extracted = Extracted {
+        files: Vec::new(),
+        skipped: 0,
+    };
+    if let Err(e) = extract_entries(&mut archive, path, dir, &mut extracted) {
+        // Don't leave half an archive behind
+        for file in extracted.files {
+            let _ = std::fs::remove_file(file);
+        }
+        return Err(e);
+    }
+    Ok(extracted)
+}
+
+fn extract_entries<R: std::io::Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    path: &Path,
+    dir: &Path,
+    extracted: &mut Extracted,
+) -> Result<(), String> {
+    let mut total_size = 0u64;
+
+    for index in 0..archive.len() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            // Encrypted or using an unsupported compression method
+            Err(e) => {
+                eprintln!("🗜️ Skipping entry {} of {:?}: {}", index, path, e);
+                extracted.skipped += 1;
+                continue;
+            }
+        };
+        if entry.is_dir() {
+            continue;
+        }
+
+        // `enclosed_name` rejects absolute paths and `..`
+        let Some(name) = entry.enclosed_name() else {
+            eprintln!("🗜️ Skipping unsafe entry {:?}", entry.name());
+            extracted.skipped += 1;
+            continue;
+        };
+        if is_hidden(&name) {
+            continue;
+        }
+        if !is_supported(&name) || entry.size() > MAX_FILE_SIZE {
+            extracted.skipped += 1;
+            continue;
+        }
+        if total_size + entry.size() > MAX_TOTAL_SIZE {
+            return Err(format!("{:?} is too large to extract", path));
+        }
+
+        let file_name = name
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let output = download::unique_path(dir, &download::safe_file_name(&file_name));
+        let mut out = std::fs::File::create(&output)
+            .map_err(|e| format!("Failed to create {:?}: {}", output, e))?;
+
+        // Sizes in the archive can lie, count what is actually written
+        let written = std::io::copy(&mut (&mut entry).take(MAX_FILE_SIZE + 1), &mut out)
+            .map_err(|e| format!("Failed to extract {:?}: {}", name, e))?;
+        if written > MAX_FILE_SIZE {
+            let _ = std::fs::remove_file(&output);
+            extracted.skipped += 1;
+            continue;
+        }
+        total_size += written;
+        extracted.files.push(output);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+
+    /// A zip with one entry per `(name, contents)`.
+    fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// An empty folder of its own in the temp folder.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "midday-archive-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn extract_bytes(bytes: Vec<u8>, dir: &Path) -> Result<Extracted, String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut extracted = Extracted {
+            files: Vec::new(),
+            skipped: 0,
+        };
+        extract_entries(&mut archive, Path::new("test.zip"), dir, &mut extracted)
+            .map(|()| extracted)
+    }
+
+    #[test]
+    fn skips_entries_outside_the_folder() {
+        let dir = temp_dir("unsafe");
+        let extracted = extract_bytes(
+            archive(&[
+                ("../escaped.pdf", b"%PDF"),
+                ("/etc/absolute.pdf", b"%PDF"),
+                ("receipts/../../nested.pdf", b"%PDF"),
+                ("receipts/march.pdf", b"%PDF"),
+                ("notes.txt", b"notes"),
+                ("__MACOSX/receipts/._march.pdf", b""),
+            ]),
+            &dir,
+        )
+        .unwrap();
+
+        assert_eq!(extracted.files, [dir.join("march.pdf")]);
+        assert_eq!(extracted.skipped, 4);
+        assert_eq!(std::fs::read(dir.join("march.pdf")).unwrap(), b"%PDF");
+        assert!(!dir.parent().unwrap().join("escaped.pdf").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn skips_entries_over_the_size_limit() {
+        let dir = temp_dir("large");
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("scan.pdf", SimpleFileOptions::default())
+            .unwrap();
+        let chunk = vec![0u8; 1024 * 1024];
+        for _ in 0..MAX_FILE_SIZE / chunk.len() as u64 {
+            writer.write_all(&chunk).unwrap();
+        }
+        writer.write_all(b"!").unwrap();
+        writer
+            .start_file("receipt.pdf", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"%PDF").unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let extracted = extract_bytes(bytes, &dir).unwrap();
+        assert_eq!(extracted.files, [dir.join("receipt.pdf")]);
+        assert_eq!(extracted.skipped, 1);
+        assert!(!dir.join("scan.pdf").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_too_many_entries() {
+        let dir = temp_dir("entries");
+        let names: Vec<String> = (0..=MAX_ENTRIES)
+            .map(|index| format!("{}.pdf", index))
+            .collect();
+        let entries: Vec<(&str, &[u8])> = names
+            .iter()
+            .map(|name| (name.as_str(), b"%PDF".as_slice()))
+            .collect();
+        let path = dir.join("many.zip");
+        std::fs::write(&path, archive(&entries)).unwrap();
+
+        let error = extract(&path, &dir).err().unwrap();
+        assert!(error.contains("at most 5000"), "{}", error);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,395 @@
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::upload::{self, UploadSource, UploadTarget};
+
+/// Captures larger than this as PNG are stored as JPEG instead.
+const MAX_PNG_BYTES: usize = 4 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+/// Only one region selection can be on screen at a time.
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+/// Let the user select a screen region and upload it to Inbox. Safe to call
+/// from any thread, e.g. a shortcut handler.
+pub fn capture_to_inbox(app: &tauri::AppHandle) {
+    if CAPTURING.swap(true, Ordering::SeqCst) {
+        println!("📸 Capture already in progress");
+        return;
+    }
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = capture_and_upload(&app_handle).await;
+        CAPTURING.store(false, Ordering::SeqCst);
+
+        match result {
+            Ok(Some(file_name)) => notify(
+                &app_handle,
+                "Receipt captured",
+                &format!("Uploading {} to Inbox", file_name),
+            ),
+            Ok(None) => println!("📸 Capture cancelled"),
+            Err(e) => {
+                eprintln!("📸 Capture failed: {}", e);
+                notify(&app_handle, "Capture failed", &e);
+            }
+        }
+    });
+}
+
+pub fn notify(app: &tauri::AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("📸 Failed to show notification: {}", e);
+    }
+}
+
+/// Returns the uploaded file name, or `None` when the selection was cancelled.
+async fn capture_and_upload(app: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let Some(image) = select_region(app).await? else {
+        return Ok(None);
+    };
+
+    let (bytes, extension) = tauri::async_runtime::spawn_blocking(move || encode(&image))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let file_name = format!(
+        "screen-capture-{}.{}",
+        chrono::Local::now().format("%Y-%m-%d-%H%M%S"),
+        extension
+    );
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?
+        .join("captures");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+
+    let path = dir.join(&file_name);
+    tokio::fs::write(&path, bytes)
+        .await
+        .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+
+    println!("📸 Captured {:?}", path);
+    upload::enqueue_temporary(
+        app,
+        path,
+        UploadTarget::Inbox,
+        Vec::new(),
+        UploadSource::Capture,
+    )
+    .ok_or_else(|| "Failed to queue capture".to_string())?;
+
+    Ok(Some(file_name))
+}
+
+/// PNG keeps text sharp, large images (photos, big screens) fall back to JPEG.
+pub fn encode(image: &image::DynamicImage) -> Result<(Vec<u8>, &'static str), String> {
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    if png.get_ref().len() <= MAX_PNG_BYTES {
+        return Ok((png.into_inner(), "png"));
+    }
+
+    let mut jpeg = Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(&mut jpeg, image::ImageOutputFormat::Jpeg(JPEG_QUALITY))
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok((jpeg.into_inner(), "jpg"))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn open_image(path: &Path) -> Result<image::DynamicImage, String> {
+    image::open(path).map_err(|e| format!("Failed to read capture: {}", e))
+}
+
+/// macOS ships an interactive region picker.
+#[cfg(target_os = "macos")]
+async fn select_region(_app: &tauri::AppHandle) -> Result<Option<image::DynamicImage>, String> {
+    let path = std::env::temp_dir().join(format!("midday-capture-{}.png", std::process::id()));
+    let command_path = path.clone();
+    let status = tauri::async_runtime::spawn_blocking(move || {
+        std::process::Command::new("screencapture")
+            .args(["-i", "-x"])
+            .arg(&command_path)
+            .status()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("Failed to run screencapture: {}", e))?;
+
+    // Pressing Escape exits successfully without writing the file
+    if !status.success() || !path.exists() {
+        return Ok(None);
+    }
+
+    let image = open_image(&path);
+    let _ = std::fs::remove_file(&path);
+    image.map(Some)
+}
+
+/// The Screenshot portal lets the user pick a region on Wayland and on X11
+/// desktops running xdg-desktop-portal. Plain X11 sessions without it get
+/// our own selection overlay.
+#[cfg(target_os = "linux")]
+async fn select_region(app: &tauri::AppHandle) -> Result<Option<image::DynamicImage>, String> {
+    use ashpd::desktop::ResponseError;
+    use ashpd::desktop::screenshot::Screenshot;
+
+    let response = async {
+        Screenshot::request()
+            .interactive(true)
+            .modal(true)
+            .send()
+            .await?
+            .response()
+    }
+    .await;
+
+    match response {
+        Ok(screenshot) => {
+            let path = screenshot
+                .uri()
+                .to_file_path()
+                .map_err(|_| format!("Unexpected screenshot location: {}", screenshot.uri()))?;
+            open_image(&path).map(Some)
+        }
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(None),
+        Err(e) => {
+            let is_wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+            if is_wayland {
+                return Err(format!("Screenshot portal unavailable: {}", e));
+            }
+            println!("📸 Screenshot portal unavailable ({}), using overlay", e);
+            overlay::select_region(app).await
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn select_region(app: &tauri::AppHandle) -> Result<Option<image::DynamicImage>, String> {
+    overlay::select_region(app).await
+}
+
+/// Screenshot of the monitor under the cursor, shown full screen in a
+/// borderless window where the user drags out the region.
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+mod overlay {
+    use base64::Engine;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+    use tauri::{
+        Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindowBuilder, WindowEvent,
+    };
+    use tokio::sync::oneshot;
+
+    const OVERLAY_LABEL: &str = "capture";
+
+    /// Selected region as fractions of the screenshot.
+    #[derive(Debug, Clone, Copy)]
+    struct Selection {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    const OVERLAY_HTML: &str = r#"<!doctype html>
+<html>
+<head>
+<style>
+html, body { margin: 0; height: 100%; overflow: hidden; cursor: crosshair; user-select: none; }
+body { background: url(__IMAGE__) 0 0 / 100% 100% no-repeat; }
+#shade { position: fixed; inset: 0; background: rgba(0, 0, 0, 0.35); }
+#selection { position: fixed; display: none; border: 1px solid #fff; box-shadow: 0 0 0 9999px rgba(0, 0, 0, 0.35); }
+#hint { position: fixed; top: 24px; left: 50%; transform: translateX(-50%); padding: 8px 14px; border-radius: 8px;
+  background: rgba(0, 0, 0, 0.75); color: #fff; font: 13px system-ui, sans-serif; }
+</style>
+</head>
+<body>
+<div id="shade"></div>
+<div id="selection"></div>
+<div id="hint">Drag to select the receipt, press Esc to cancel</div>
+<script>
+var selection = document.getElementById("selection");
+var start = null;
+function send(path) { location.href = "midday-capture://" + path; }
+function rect(e) {
+  return {
+    x: Math.min(start.x, e.clientX), y: Math.min(start.y, e.clientY),
+    w: Math.abs(e.clientX - start.x), h: Math.abs(e.clientY - start.y)
+  };
+}
+addEventListener("keydown", function (e) { if (e.key === "Escape") send("cancel"); });
+addEventListener("mousedown", function (e) {
+  start = { x: e.clientX, y: e.clientY };
+  document.getElementById("shade").style.display = "none";
+  document.getElementById("hint").style.display = "none";
+  selection.style.display = "block";
+});
+addEventListener("mousemove", function (e) {
+  if (!start) return;
+  var r = rect(e);
+  selection.style.left = r.x + "px"; selection.style.top = r.y + "px";
+  selection.style.width = r.w + "px"; selection.style.height = r.h + "px";
+});
+addEventListener("mouseup", function (e) {
+  if (!start) return;
+  var r = rect(e);
+  start = null;
+  if (r.w < 4 || r.h < 4) { send("cancel"); return; }
+  send("select?x=" + r.x / innerWidth + "&y=" + r.y / innerHeight +
+    "&w=" + r.w / innerWidth + "&h=" + r.h / innerHeight);
+});
+</script>
+</body>
+</html>"#;
+
+    fn parse_selection(url: &tauri::Url) -> Option<Selection> {
+        if url.host_str() != Some("select") {
+            return None;
+        }
+
+        let value = |key: &str| {
+            url.query_pairs()
+                .find(|(name, _)| name == key)
+                .and_then(|(_, value)| value.parse::<f64>().ok())
+                .map(|value| value.clamp(0.0, 1.0))
+        };
+
+        Some(Selection {
+            x: value("x")?,
+            y: value("y")?,
+            width: value("w")?,
+            height: value("h")?,
+        })
+    }
+
+    pub async fn select_region(
+        app: &tauri::AppHandle,
+    ) -> Result<Option<image::DynamicImage>, String> {
+        let cursor = app.cursor_position().unwrap_or_default();
+
+        let (monitor, screenshot) = tauri::async_runtime::spawn_blocking(move || {
+            let monitor = xcap::Monitor::from_point(cursor.x as i32, cursor.y as i32)
+                .or_else(|_| {
+                    xcap::Monitor::all()?
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| xcap::XCapError::new("No monitor found"))
+                })
+                .map_err(|e| format!("Failed to find monitor: {}", e))?;
+            let capture = monitor
+                .capture_image()
+                .map_err(|e| format!("Failed to capture screen: {}", e))?;
+
+            // xcap uses a newer `image` release, convert through raw pixels
+            let (width, height) = (capture.width(), capture.height());
+            let screenshot = image::RgbaImage::from_raw(width, height, capture.into_raw())
+                .map(image::DynamicImage::ImageRgba8)
+                .ok_or_else(|| "Invalid screen capture".to_string())?;
+
+            let bounds = (
+                monitor.x().unwrap_or_default(),
+                monitor.y().unwrap_or_default(),
+                monitor.width().unwrap_or(width),
+                monitor.height().unwrap_or(height),
+            );
+            Ok::<_, String>((bounds, screenshot))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        // JPEG keeps the data URL small, it is only used as a backdrop
+        let mut preview = Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(screenshot.to_rgb8())
+            .write_to(&mut preview, image::ImageOutputFormat::Jpeg(80))
+            .map_err(|e| format!("Failed to encode image: {}", e))?;
+        let image_url = format!(
+            "data:image/jpeg;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(preview.get_ref())
+        );
+        let html = OVERLAY_HTML.replace("__IMAGE__", &image_url);
+        let page_url = format!(
+            "data:text/html;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(html)
+        );
+        let page_url = tauri::Url::parse(&page_url).map_err(|e| e.to_string())?;
+
+        let (sender, receiver) = oneshot::channel::<Option<Selection>>();
+        let sender = Arc::new(Mutex::new(Some(sender)));
+        let send = {
+            let sender = sender.clone();
+            move |selection: Option<Selection>| {
+                if let Some(sender) = sender.lock().unwrap().take() {
+                    let _ = sender.send(selection);
+                }
+            }
+        };
+
+        if let Some(existing) = app.get_webview_window(OVERLAY_LABEL) {
+            let _ = existing.destroy();
+        }
+
+        let on_navigation_send = send.clone();
+        let window = WebviewWindowBuilder::new(app, OVERLAY_LABEL, WebviewUrl::External(page_url))
+            .title("Capture")
+            .decorations(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .resizable(false)
+            .visible(false)
+            .on_navigation(move |url| {
+                if url.scheme() != "midday-capture" {
+                    return true;
+                }
+                on_navigation_send(parse_selection(url));
+                false
+            })
+            .build()
+            .map_err(|e| format!("Failed to open capture overlay: {}", e))?;
+
+        let (x, y, width, height) = monitor;
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+        let _ = window.set_size(PhysicalSize::new(width, height));
+        let _ = window.show();
+        let _ = window.set_focus();
+
+        let on_close_send = send.clone();
+        window.on_window_event(move |event| {
+            if matches!(event, WindowEvent::Destroyed) {
+                on_close_send(None);
+            }
+        });
+
+        let selection = receiver.await.ok().flatten();
+        let _ = window.destroy();
+
+        let Some(selection) = selection else {
+            return Ok(None);
+        };
+
+        let (image_width, image_height) = (screenshot.width() as f64, screenshot.height() as f64);
+        let crop_x = (selection.x * image_width).round() as u32;
+        let crop_y = (selection.y * image_height).round() as u32;
+        let crop_width = ((selection.width * image_width).round() as u32)
+            .clamp(1, screenshot.width().saturating_sub(crop_x).max(1));
+        let crop_height = ((selection.height * image_height).round() as u32)
+            .clamp(1, screenshot.height().saturating_sub(crop_y).max(1));
+
+        Ok(Some(screenshot.crop_imm(
+            crop_x,
+            crop_y,
+            crop_width,
+            crop_height,
+        )))
+    }
+}
@@ -0,0 +1,313 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::archive;
+use crate::capture;
+use crate::email;
+use crate::frontend_queue::FrontendQueue;
+use crate::ics;
+use crate::upload::{self, UploadSource, UploadTarget};
+use crate::vcard;
+
+pub const USAGE: &str = "Usage: midday [--env <environment>] [--profile <name>] [command]
+
+Commands:
+  open <path>          Open a dashboard path, e.g. `midday open inbox`
+  search               Toggle the search window
+  upload <files...>    Upload files to Inbox; .vcf and .ics files are imported
+                       as customers and tracker entries
+  capture              Select a screen region and upload it to Inbox
+  clipboard            Upload the clipboard contents to Inbox
+  timer start <project>
+                       Start the tracker timer for a project
+  timer stop           Stop the running tracker timer
+
+Options:
+  --env <environment>  development, staging or production
+  --profile <name>     Keep sessions for this profile separate from the default one
+  --toggle-search      Same as `search`, for binding in desktop environment settings
+  -h, --help           Print this help
+
+Commands are forwarded to the running instance when Midday is already open.";
+
+/// How long the dashboard gets to report back on a `timer` command.
+const TIMER_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimerAction {
+    /// The name of the project to track
+    Start(String),
+    Stop,
+}
+
+/// The `timer` command waiting for the dashboard to report back.
+#[derive(Default)]
+pub struct TimerRequests {
+    inner: Mutex<TimerInner>,
+}
+
+#[derive(Default)]
+struct TimerInner {
+    next_id: u64,
+    pending: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Open { path: String },
+    Search,
+    Upload { files: Vec<PathBuf> },
+    Capture,
+    Clipboard,
+    Timer(TimerAction),
+}
+
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub env: Option<String>,
+    pub profile: Option<String>,
+    pub help: bool,
+    pub command: Option<CliCommand>,
+    /// Deep link URLs passed as plain arguments (Linux/Windows)
+    pub urls: Vec<String>,
+}
+
+/// Parse the process arguments. `args` includes the binary path, `cwd` is used
+/// to resolve relative file paths (it may differ from ours when the arguments
+/// were forwarded from a second instance).
+pub fn parse(args: impl IntoIterator<Item = String>, cwd: &Path) -> Result<CliArgs, String> {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter().skip(1);
+    let mut positional: Vec<String> = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => parsed.help = true,
+            // Bindable in desktop environments where global shortcuts don't work
+            "--toggle-search" => parsed.command = Some(CliCommand::Search),
+            "--env" => {
+                parsed.env = Some(args.next().ok_or("--env requires a value")?);
+            }
+            "--profile" => {
+                let profile = args.next().ok_or("--profile requires a value")?;
+                if !is_valid_profile_name(&profile) {
+                    return Err(format!("Invalid profile name: {}", profile));
+                }
+                parsed.profile = Some(profile);
+            }
+            _ if arg.starts_with("--env=") => {
+                parsed.env = Some(arg["--env=".len()..].to_string());
+            }
+            _ if arg.starts_with("--profile=") => {
+                let profile = &arg["--profile=".len()..];
+                if !is_valid_profile_name(profile) {
+                    return Err(format!("Invalid profile name: {}", profile));
+                }
+                parsed.profile = Some(profile.to_string());
+            }
+            _ if arg.contains("://") => parsed.urls.push(arg),
+            // macOS passes a process serial number when launched from Finder
+            _ if arg.starts_with("-psn_") => {}
+            _ if arg.starts_with('-') && positional.is_empty() => {
+                return Err(format!("Unknown option: {}", arg));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.is_empty() {
+        return Ok(parsed);
+    }
+
+    if parsed.command.is_some() {
+        return Err("--toggle-search can't be combined with a command".to_string());
+    }
+
+    let command = positional.remove(0);
+    parsed.command = Some(match command.as_str() {
+        "open" => {
+            let path = positional.first().ok_or("open requires a path")?;
+            CliCommand::Open {
+                path: path.trim_start_matches('/').to_string(),
+            }
+        }
+        "search" => CliCommand::Search,
+        "upload" => {
+            if positional.is_empty() {
+                return Err("upload requires at least one file".to_string());
+            }
+            CliCommand::Upload {
+                files: positional.iter().map(|file| cwd.join(file)).collect(),
+            }
+        }
+        "capture" => CliCommand::Capture,
+        "clipboard" => CliCommand::Clipboard,
+        "timer" => match positional.first().map(String::as_str) {
+            Some("start") => {
+                let project = positional[1..].join(" ");
+                if project.trim().is_empty() {
+                    return Err("timer start requires a project".to_string());
+                }
+                CliCommand::Timer(TimerAction::Start(project))
+            }
+            Some("stop") => CliCommand::Timer(TimerAction::Stop),
+            _ => return Err("timer requires `start` or `stop`".to_string()),
+        },
+        other => return Err(format!("Unknown command: {}", other)),
+    });
+
+    Ok(parsed)
+}
+
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Execute a command in the running instance.
+pub fn run_command(app: &tauri::AppHandle, command: CliCommand) {
+    println!("⌨️ Running CLI command: {:?}", command);
+
+    match command {
+        CliCommand::Open { path } => {
+            crate::handle_deep_link_event(app, vec![format!("midday://{}", path)]);
+        }
+        CliCommand::Search => crate::shortcuts::toggle_search(app),
+        CliCommand::Upload { files } => {
+            let (emails, files): (Vec<PathBuf>, Vec<PathBuf>) =
+                files.into_iter().partition(|file| email::is_email(file));
+            let (archives, files): (Vec<PathBuf>, Vec<PathBuf>) = files
+                .into_iter()
+                .partition(|file| archive::is_archive(file));
+            let (contacts, files): (Vec<PathBuf>, Vec<PathBuf>) =
+                files.into_iter().partition(|file| vcard::is_vcard(file));
+            let (calendars, files): (Vec<PathBuf>, Vec<PathBuf>) =
+                files.into_iter().partition(|file| ics::is_calendar(file));
+            upload::enqueue(
+                app,
+                files,
+                UploadTarget::Inbox,
+                Vec::new(),
+                UploadSource::Cli,
+            );
+
+            if !emails.is_empty() {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    email::enqueue_emails(
+                        &app,
+                        emails,
+                        UploadTarget::Inbox,
+                        Vec::new(),
+                        UploadSource::Cli,
+                    )
+                    .await;
+                });
+            }
+
+            if !archives.is_empty() {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    archive::enqueue_archives(
+                        &app,
+                        archives,
+                        UploadTarget::Inbox,
+                        Vec::new(),
+                        UploadSource::Cli,
+                    )
+                    .await;
+                });
+            }
+
+            if !contacts.is_empty() || !calendars.is_empty() {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    vcard::import(&app, contacts).await;
+                    ics::import(&app, calendars).await;
+                });
+            }
+        }
+        CliCommand::Capture => crate::capture::capture_to_inbox(app),
+        CliCommand::Clipboard => crate::clipboard::upload_clipboard(app),
+        CliCommand::Timer(action) => send_timer_action(app, action),
+    }
+}
+
+/// Ask the dashboard to start or stop the timer. It answers with
+/// `timer_result`; without an answer, e.g. when signed out, the command is
+/// reported as failed after `TIMER_TIMEOUT`.
+fn send_timer_action(app: &tauri::AppHandle, action: TimerAction) {
+    let (Some(queue), Some(requests)) = (
+        app.try_state::<FrontendQueue>(),
+        app.try_state::<TimerRequests>(),
+    ) else {
+        return;
+    };
+
+    let id = {
+        let mut inner = requests.inner.lock().unwrap();
+        inner.next_id += 1;
+        inner.pending = Some(inner.next_id);
+        inner.next_id
+    };
+
+    let payload = match action {
+        TimerAction::Start(project) => {
+            serde_json::json!({ "id": id, "action": "start", "project": project })
+        }
+        TimerAction::Stop => serde_json::json!({ "id": id, "action": "stop" }),
+    };
+    // Dropped once it was reported as failed, instead of running late
+    queue.push_to_listener(app, "desktop-timer", payload, TIMER_TIMEOUT);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(TIMER_TIMEOUT).await;
+        let Some(requests) = app.try_state::<TimerRequests>() else {
+            return;
+        };
+        let unanswered = {
+            let mut inner = requests.inner.lock().unwrap();
+            let unanswered = inner.pending == Some(id);
+            if unanswered {
+                inner.pending = None;
+            }
+            unanswered
+        };
+        if unanswered {
+            let message = "Midday didn't respond, make sure you're signed in";
+            eprintln!("⏱️ Timer command failed: {}", message);
+            capture::notify(&app, "Timer failed", message);
+        }
+    });
+}
+
+/// The outcome of a `timer` command, reported by the dashboard.
+#[tauri::command]
+pub fn timer_result(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimerRequests>,
+    id: u64,
+    ok: bool,
+    message: String,
+) {
+    {
+        let mut inner = state.inner.lock().unwrap();
+        if inner.pending != Some(id) {
+            return;
+        }
+        inner.pending = None;
+    }
+
+    if ok {
+        println!("⏱️ {}", message);
+        capture::notify(&app, "Timer", &message);
+    } else {
+        eprintln!("⏱️ Timer command failed: {}", message);
+        capture::notify(&app, "Timer failed", &message);
+    }
+}
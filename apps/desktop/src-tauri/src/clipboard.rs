@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Manager;
+
+use crate::capture::{encode, notify};
+use crate::upload::{self, UploadSource, UploadTarget};
+
+/// Ignore repeated shortcut presses while the clipboard is being read.
+static READING: AtomicBool = AtomicBool::new(false);
+
+/// What was found on the clipboard, in order of preference.
+enum ClipboardContent {
+    Files(Vec<PathBuf>),
+    Image(image::DynamicImage),
+    Html(String),
+    Text(String),
+}
+
+/// Upload whatever is on the clipboard to Inbox: copied files as they are,
+/// an image as PNG, HTML or text as a receipt file. Safe to call from any
+/// thread, e.g. a tray or shortcut handler.
+pub fn upload_clipboard(app: &tauri::AppHandle) {
+    if READING.swap(true, Ordering::SeqCst) {
+        println!("📋 Clipboard upload already in progress");
+        return;
+    }
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = read_and_upload(&app_handle).await;
+        READING.store(false, Ordering::SeqCst);
+
+        match result {
+            Ok(Some(description)) => notify(
+                &app_handle,
+                "Clipboard uploaded",
+                &format!("Uploading {} to Inbox", description),
+            ),
+            Ok(None) => {
+                println!("📋 Nothing to upload on the clipboard");
+                notify(
+                    &app_handle,
+                    "Clipboard is empty",
+                    "Copy an image, files or text first",
+                );
+            }
+            Err(e) => {
+                eprintln!("📋 Clipboard upload failed: {}", e);
+                notify(&app_handle, "Clipboard upload failed", &e);
+            }
+        }
+    });
+}
+
+/// Returns a description of what was queued, or `None` when the clipboard
+/// holds nothing we can upload.
+async fn read_and_upload(app: &tauri::AppHandle) -> Result<Option<String>, String> {
+    let content = tauri::async_runtime::spawn_blocking(read_clipboard)
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let Some(content) = content else {
+        return Ok(None);
+    };
+
+    let (bytes, extension) = match content {
+        ClipboardContent::Files(paths) => {
+            let count = paths.len();
+            println!("📋 Uploading {} copied files", count);
+            upload::enqueue(
+                app,
+                paths,
+                UploadTarget::Inbox,
+                Vec::new(),
+                UploadSource::Clipboard,
+            );
+            return Ok(Some(if count == 1 {
+                "1 file".to_string()
+            } else {
+                format!("{} files", count)
+            }));
+        }
+        ClipboardContent::Image(image) => {
+            tauri::async_runtime::spawn_blocking(move || encode(&image))
+                .await
+                .map_err(|e| e.to_string())??
+        }
+        ClipboardContent::Html(html) => (html_document(&html).into_bytes(), "html"),
+        ClipboardContent::Text(text) => (text.into_bytes(), "txt"),
+    };
+
+    let file_name = format!(
+        "clipboard-{}.{}",
+        chrono::Local::now().format("%Y-%m-%d-%H%M%S"),
+        extension
+    );
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?
+        .join("clipboard");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+
+    let path = dir.join(&file_name);
+    tokio::fs::write(&path, bytes)
+        .await
+        .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+
+    println!("📋 Saved clipboard to {:?}", path);
+    upload::enqueue_temporary(
+        app,
+        path,
+        UploadTarget::Inbox,
+        Vec::new(),
+        UploadSource::Clipboard,
+    )
+    .ok_or_else(|| "Failed to queue clipboard".to_string())?;
+
+    Ok(Some(file_name))
+}
+
+/// Files come first since file managers also put their names on the
+/// clipboard as text, and HTML before text so copied email receipts keep
+/// their formatting.
+fn read_clipboard() -> Result<Option<ClipboardContent>, String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?;
+
+    if let Ok(paths) = clipboard.get().file_list() {
+        let files: Vec<PathBuf> = paths.into_iter().filter(|path| path.is_file()).collect();
+        if !files.is_empty() {
+            return Ok(Some(ClipboardContent::Files(files)));
+        }
+    }
+
+    if let Ok(data) = clipboard.get_image() {
+        let image = image::RgbaImage::from_raw(
+            data.width as u32,
+            data.height as u32,
+            data.bytes.into_owned(),
+        )
+        .ok_or_else(|| "Clipboard image has an unexpected size".to_string())?;
+        return Ok(Some(ClipboardContent::Image(
+            image::DynamicImage::ImageRgba8(image),
+        )));
+    }
+
+    if let Ok(html) = clipboard.get().html()
+        && !html.trim().is_empty()
+    {
+        return Ok(Some(ClipboardContent::Html(html)));
+    }
+
+    match clipboard.get_text() {
+        Ok(text) if !text.trim().is_empty() => Ok(Some(ClipboardContent::Text(text))),
+        _ => Ok(None),
+    }
+}
+
+/// Copied HTML is usually a fragment without a charset, which browsers and
+/// OCR would otherwise read as Latin-1.
+fn html_document(html: &str) -> String {
+    let start = html
+        .trim_start()
+        .chars()
+        .take(9)
+        .collect::<String>()
+        .to_lowercase();
+    if start.starts_with("<!doctype") || start.starts_with("<html") {
+        return html.to_string();
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"></head>\n<body>\n{}\n</body>\n</html>\n",
+        html
+    )
+}
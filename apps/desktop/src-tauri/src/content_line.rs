@@ -0,0 +1,279 @@
+/// A property of a vCard or iCalendar file, which share the line format, e.g.
+/// `item1.EMAIL;TYPE=INTERNET,pref:jane@example.com` or
+/// `DTSTART;TZID=Europe/Berlin:20240415T090000`.
+#[derive(Debug, Clone)]
+pub struct ContentLine {
+    /// Apple's `item1.` prefix, which ties a custom label to a value
+    pub group: Option<String>,
+    /// Uppercase
+    pub name: String,
+    /// Uppercase names, values without quotes
+    pub params: Vec<(String, Vec<String>)>,
+    /// Still escaped, see `unescape`
+    pub value: String,
+}
+
+impl ContentLine {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, values)| values.first())
+            .map(String::as_str)
+    }
+
+    /// Whether `TYPE` has `value`, also for vCard 2.1 where it is written
+    /// bare, e.g. `TEL;WORK;VOICE:`.
+    pub fn has_type(&self, value: &str) -> bool {
+        self.params
+            .iter()
+            .filter(|(key, _)| key == "TYPE")
+            .flat_map(|(_, values)| values)
+            .any(|type_value| type_value.eq_ignore_ascii_case(value))
+    }
+
+    /// Whether the property is marked preferred, `TYPE=pref` in vCard 3 and
+    /// `PREF=1` in vCard 4.
+    pub fn is_preferred(&self) -> bool {
+        self.has_type("pref") || self.param("PREF").is_some()
+    }
+
+    /// The value as text.
+    pub fn text(&self) -> String {
+        unescape(&self.decoded())
+    }
+
+    /// Components of structured values like `N` and `ADR`, split on `;`.
+    pub fn components(&self) -> Vec<String> {
+        split_escaped(&self.decoded(), ';')
+    }
+
+    /// vCard 2.1 writes non-ASCII values quoted-printable.
+    fn decoded(&self) -> String {
+        match self.param("ENCODING") {
+            Some(encoding) if encoding.eq_ignore_ascii_case("QUOTED-PRINTABLE") => {
+                decode_quoted_printable(&self.value, self.param("CHARSET"))
+            }
+            _ => self.value.clone(),
+        }
+    }
+}
+
+/// Unfold and split the lines of a file. Lines that aren't properties are
+/// skipped.
+pub fn parse(text: &str) -> Vec<ContentLine> {
+    let mut logical: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match logical.last_mut() {
+            // Folded lines continue after one space or tab
+            Some(previous) if line.starts_with([' ', '\t']) => previous.push_str(&line[1..]),
+            // Quoted-printable soft line breaks end in `=`
+            Some(previous) if previous.ends_with('=') && is_quoted_printable(previous) => {
+                previous.pop();
+                previous.push_str(line);
+            }
+            _ if line.is_empty() => {}
+            _ => logical.push(line.to_string()),
+        }
+    }
+
+    logical.iter().filter_map(|line| parse_line(line)).collect()
+}
+
+fn is_quoted_printable(line: &str) -> bool {
+    line.split(':')
+        .next()
+        .is_some_and(|head| head.to_ascii_uppercase().contains("QUOTED-PRINTABLE"))
+}
+
+fn parse_line(line: &str) -> Option<ContentLine> {
+    // The value starts at the first colon outside a quoted parameter
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(index, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(index),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = split_params(head).into_iter();
+    let full_name = parts.next()?;
+    let (group, name) = match full_name.rsplit_once('.') {
+        Some((group, name)) => (Some(group.to_string()), name),
+        None => (None, full_name.as_str()),
+    };
+    if name.is_empty() {
+        return None;
+    }
+
+    let params = parts
+        .map(|param| match param.split_once('=') {
+            Some((key, values)) => (
+                key.trim().to_ascii_uppercase(),
+                values
+                    .split(',')
+                    .map(|value| value.trim().trim_matches('"').to_string())
+                    .collect(),
+            ),
+            None => ("TYPE".to_string(), vec![param.trim().to_string()]),
+        })
+        .collect();
+
+    Some(ContentLine {
+        group,
+        name: name.to_ascii_uppercase(),
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// Split `NAME;KEY=value;KEY="a;b"` on semicolons outside quotes.
+fn split_params(head: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut in_quotes = false;
+    for c in head.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                parts.last_mut().unwrap().push(c);
+            }
+            ';' if !in_quotes => parts.push(String::new()),
+            _ => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// `\n`, `\,`, `\;` and `\\` in text values.
+pub fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+/// Split on `separator` where it isn't escaped, unescaping the parts.
+pub fn split_escaped(value: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if c == separator => {
+                parts.push(unescape(&value[start..index]));
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(unescape(&value[start..]));
+    parts
+}
+
+fn decode_quoted_printable(value: &str, charset: Option<&str>) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'='
+            && let Some(hex) = value.get(index + 1..index + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            index += 3;
+            continue;
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+
+    let encoding = charset
+        .and_then(|charset| encoding_rs::Encoding::for_label(charset.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(&decoded).0.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> ContentLine {
+        let mut lines = parse(text);
+        assert_eq!(lines.len(), 1, "{}", text);
+        lines.remove(0)
+    }
+
+    #[test]
+    fn unfolds_lines() {
+        let lines = parse(
+            "BEGIN:VEVENT\r\nDESCRIPTION:Line one\r\n  continues\r\n\tand more\r\n\r\nEND:VEVENT\r\n",
+        );
+        assert_eq!(lines.len(), 3);
+        // Only the first whitespace character is dropped
+        assert_eq!(lines[1].value, "Line one continuesand more");
+    }
+
+    #[test]
+    fn names_groups_and_params() {
+        let email = line("item1.email;TYPE=INTERNET,pref;x-custom=\"a;b\":jane@example.com");
+        assert_eq!(email.group.as_deref(), Some("item1"));
+        assert_eq!(email.name, "EMAIL");
+        assert!(email.has_type("internet"));
+        assert!(email.is_preferred());
+        assert_eq!(email.param("X-CUSTOM"), Some("a;b"));
+
+        // Colons in quoted params aren't the start of the value
+        let start = line("DTSTART;TZID=\"America/New_York:odd\":20240101T090000");
+        assert_eq!(start.param("TZID"), Some("America/New_York:odd"));
+        assert_eq!(start.value, "20240101T090000");
+
+        let phone = line("TEL;WORK;VOICE:+1 555 0100");
+        assert!(phone.has_type("work") && phone.has_type("voice"));
+        assert!(!phone.is_preferred());
+        assert!(line("EMAIL;PREF=1:jane@example.com").is_preferred());
+
+        assert!(parse("no colon here\r\n:no name\r\n").is_empty());
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            unescape(r"Meeting\, notes\; more\nnext \\ end\N"),
+            "Meeting, notes; more\nnext \\ end\n"
+        );
+        assert_eq!(
+            line(r"N:Doe;John\;Jr;;;").components(),
+            ["Doe", "John;Jr", "", "", ""]
+        );
+        assert_eq!(split_escaped(r"a\,b,c", ','), ["a,b", "c"]);
+    }
+
+    #[test]
+    fn quoted_printable() {
+        let note =
+            line("NOTE;ENCODING=QUOTED-PRINTABLE;CHARSET=UTF-8:Caf=C3=A9 =\r\nM=C3=BCller\r\n");
+        assert_eq!(note.text(), "Café Müller");
+
+        let latin = line("NOTE;CHARSET=ISO-8859-1;ENCODING=QUOTED-PRINTABLE:Cr=E8me");
+        assert_eq!(latin.text(), "Crème");
+
+        // Other values ending in `=` aren't joined
+        assert_eq!(parse("NOTE:a=\r\nFN:b\r\n").len(), 2);
+    }
+}
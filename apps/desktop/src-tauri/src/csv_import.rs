@@ -0,0 +1,843 @@
+use chrono::{Datelike, NaiveDate};
+use encoding_rs::Encoding;
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::drag_drop;
+
+/// Bytes read to detect the encoding, delimiter and header.
+const SAMPLE_SIZE: usize = 64 * 1024;
+/// Bank exports often start with account details before the header.
+const MAX_PREAMBLE_ROWS: usize = 20;
+/// Rows looked at to guess the column types.
+const TYPE_SAMPLE_ROWS: usize = 200;
+const PREVIEW_ROWS: usize = 10;
+const MAX_CHUNK_ROWS: usize = 5_000;
+/// Files kept open for `read_csv_chunk`.
+const MAX_OPEN_FILES: usize = 4;
+
+const DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// Extensions offered in the import dialog, CSV and the bank statements
+/// `parse_statement_file` reads.
+const IMPORT_EXTENSIONS: [&str; 9] = [
+    "csv", "tsv", "txt", "ofx", "qfx", "xml", "sta", "mt940", "940",
+];
+
+/// Date formats as (chrono, date-fns). Day first comes before month first,
+/// columns with a day over 12 settle it.
+const DATE_FORMATS: &[(&str, &str)] = &[
+    ("%Y-%m-%d", "yyyy-MM-dd"),
+    ("%d.%m.%Y", "dd.MM.yyyy"),
+    ("%d/%m/%Y", "dd/MM/yyyy"),
+    ("%m/%d/%Y", "MM/dd/yyyy"),
+    ("%d-%m-%Y", "dd-MM-yyyy"),
+    ("%m-%d-%Y", "MM-dd-yyyy"),
+    ("%Y/%m/%d", "yyyy/MM/dd"),
+    ("%Y.%m.%d", "yyyy.MM.dd"),
+    ("%d.%m.%y", "dd.MM.yy"),
+    ("%d/%m/%y", "dd/MM/yy"),
+    ("%m/%d/%y", "MM/dd/yy"),
+    ("%d-%m-%y", "dd-MM-yy"),
+    ("%d %b %Y", "dd MMM yyyy"),
+    ("%d-%b-%Y", "dd-MMM-yyyy"),
+    ("%b %d, %Y", "MMM dd, yyyy"),
+    ("%d %B %Y", "dd MMMM yyyy"),
+    ("%B %d, %Y", "MMMM dd, yyyy"),
+];
+
+/// `-1.234,56`, `(12.50)`, `1'234.50`, `12,50-`, after currency symbols and
+/// codes are stripped.
+static NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[-+−(]?\s?\d[\d.,'\u{a0} ]*\)?-?$").unwrap());
+
+/// The separator before the last one or two digits of a number.
+static DECIMAL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d([.,])\d{1,2}\)?-?$").unwrap());
+
+static CURRENCY_CODE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Z]{3}$").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColumnKind {
+    Date,
+    Number,
+    Currency,
+    Text,
+    Empty,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvColumn {
+    /// From the header row, or `Column 1`, `Column 2`, ...
+    pub name: String,
+    pub kind: ColumnKind,
+    /// date-fns format of date columns
+    pub date_format: Option<String>,
+}
+
+/// What `inspect_csv` found out about a file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvInspection {
+    /// `UTF-8`, `UTF-16LE`, `UTF-16BE` or `windows-1252`
+    pub encoding: String,
+    pub delimiter: String,
+    /// Rows before the header, or before the data without one
+    pub skip_rows: usize,
+    pub has_header: bool,
+    pub decimal_separator: String,
+    /// date-fns format of the first date column
+    pub date_format: Option<String>,
+    pub columns: Vec<CsvColumn>,
+    /// First data rows
+    pub preview: Vec<Vec<String>>,
+    /// Data rows in the whole file
+    pub rows: usize,
+}
+
+/// A file chosen for the import. The dashboard only gets the id, so it can't
+/// have arbitrary paths read.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFile {
+    pub id: u64,
+    pub file_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvChunk {
+    pub offset: usize,
+    pub rows: Vec<Vec<String>>,
+    /// No rows after this chunk
+    pub done: bool,
+}
+
+/// How to read a file, from `sniff`.
+#[derive(Clone, Copy)]
+struct CsvSettings {
+    encoding: &'static Encoding,
+    delimiter: u8,
+    /// Rows before the first data row, including the header
+    data_start: usize,
+}
+
+type DecodedReader = csv::Reader<DecodeReaderBytes<File, Vec<u8>>>;
+
+/// A reader positioned at data row `next_row`, so paging through a file in
+/// order doesn't read it from the start every time.
+struct Cursor {
+    reader: DecodedReader,
+    next_row: usize,
+}
+
+struct OpenCsv {
+    settings: CsvSettings,
+    cursor: Option<Cursor>,
+}
+
+#[derive(Default)]
+pub struct CsvState {
+    next_id: AtomicU64,
+    /// Files picked in the dialog or dropped onto the import, by id
+    picked: Mutex<HashMap<u64, PathBuf>>,
+    files: Mutex<Vec<(u64, OpenCsv)>>,
+}
+
+impl CsvState {
+    fn add(&self, path: PathBuf) -> ImportFile {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.picked.lock().unwrap().insert(id, path);
+        ImportFile { id, file_name }
+    }
+
+    fn path(&self, id: u64) -> Result<PathBuf, String> {
+        self.picked
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown import file: {}", id))
+    }
+
+    fn take(&self, id: u64) -> Option<OpenCsv> {
+        let mut files = self.files.lock().unwrap();
+        let position = files.iter().position(|(open, _)| *open == id)?;
+        Some(files.remove(position).1)
+    }
+
+    fn put(&self, id: u64, file: OpenCsv) {
+        let mut files = self.files.lock().unwrap();
+        files.retain(|(open, _)| *open != id);
+        if files.len() >= MAX_OPEN_FILES {
+            files.remove(0);
+        }
+        files.push((id, file));
+    }
+}
+
+/// Path of a file picked for the import.
+pub fn picked_path(app: &tauri::AppHandle, id: u64) -> Result<PathBuf, String> {
+    app.state::<CsvState>().path(id)
+}
+
+/// BOM, then NUL bytes for UTF-16 without one, then UTF-8 if the sample is
+/// valid, otherwise Windows-1252 which Excel and most banks write.
+fn detect_encoding(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+
+    let head = &sample[..sample.len().min(4096)];
+    let even_nuls = head.iter().step_by(2).filter(|&&byte| byte == 0).count();
+    let odd_nuls = head
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&byte| byte == 0)
+        .count();
+    let half = head.len() / 2;
+    if half > 0 && odd_nuls > half / 2 && even_nuls < odd_nuls / 4 {
+        return encoding_rs::UTF_16LE;
+    }
+    if half > 0 && even_nuls > half / 2 && odd_nuls < even_nuls / 4 {
+        return encoding_rs::UTF_16BE;
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => encoding_rs::UTF_8,
+        // The sample may end in the middle of a character
+        Err(e) if e.error_len().is_none() => encoding_rs::UTF_8,
+        Err(_) => encoding_rs::WINDOWS_1252,
+    }
+}
+
+fn open_decoded(
+    path: &Path,
+    encoding: &'static Encoding,
+) -> Result<DecodeReaderBytes<File, Vec<u8>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    Ok(DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .build(file))
+}
+
+fn csv_reader<R: Read>(input: R, delimiter: u8) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(input)
+}
+
+fn is_blank(record: &csv::StringRecord) -> bool {
+    record.iter().all(str::is_empty)
+}
+
+/// Records of the sample, without a last line that may be cut off.
+fn sample_records(sample: &str, delimiter: u8) -> Vec<csv::StringRecord> {
+    let sample = match sample.rfind('\n') {
+        Some(end) if end + 1 < sample.len() => &sample[..end + 1],
+        _ => sample,
+    };
+    csv_reader(sample.as_bytes(), delimiter)
+        .records()
+        .filter_map(Result::ok)
+        .filter(|record| !is_blank(record))
+        .collect()
+}
+
+/// The most common field count and how many records have it.
+fn field_count_mode(records: &[csv::StringRecord]) -> (usize, usize) {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for record in records.iter().take(MAX_PREAMBLE_ROWS + TYPE_SAMPLE_ROWS) {
+        *counts.entry(record.len()).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|&(fields, records)| (records, fields))
+        .unwrap_or_default()
+}
+
+/// The delimiter that splits most rows into the same number of fields.
+fn detect_delimiter(sample: &str) -> u8 {
+    DELIMITERS
+        .iter()
+        .copied()
+        .max_by_key(|&delimiter| {
+            let records = sample_records(sample, delimiter);
+            let (fields, matching) = field_count_mode(&records);
+            (fields > 1, matching, fields)
+        })
+        .unwrap_or(b',')
+}
+
+fn parse_date(value: &str, format: &str) -> bool {
+    // Timestamps like `2024-01-05 12:30` or `2024-01-05T12:30:00Z` count too.
+    // `%Y` takes any number of digits, the year check keeps `05-01-24` from
+    // being the 24th of January in the year 5.
+    NaiveDate::parse_and_remainder(value, format).is_ok_and(|(date, rest)| {
+        (1900..=2100).contains(&date.year()) && (rest.is_empty() || rest.starts_with([' ', 'T']))
+    })
+}
+
+fn strip_currency(value: &str) -> &str {
+    let value = value.trim_matches(|c: char| "€$£¥₣".contains(c) || c.is_whitespace());
+    let value = match value.len() {
+        len if len > 4
+            && value.is_char_boundary(len - 3)
+            && CURRENCY_CODE.is_match(&value[len - 3..]) =>
+        {
+            &value[..len - 3]
+        }
+        _ => value,
+    };
+    let value = match value.get(..3) {
+        Some(code) if value.len() > 4 && CURRENCY_CODE.is_match(code) => &value[3..],
+        _ => value,
+    };
+    value.trim()
+}
+
+fn is_number(value: &str) -> bool {
+    NUMBER.is_match(strip_currency(value))
+}
+
+fn is_date(value: &str) -> bool {
+    DATE_FORMATS
+        .iter()
+        .any(|(format, _)| parse_date(value, format))
+}
+
+/// The kind of a column from its values, and the date format for dates.
+fn guess_column(values: &[&str]) -> (ColumnKind, Option<&'static str>) {
+    let values: Vec<&str> = values
+        .iter()
+        .copied()
+        .filter(|value| !value.is_empty())
+        .collect();
+    if values.is_empty() {
+        return (ColumnKind::Empty, None);
+    }
+
+    if let Some((_, date_format)) = DATE_FORMATS
+        .iter()
+        .find(|(format, _)| values.iter().all(|value| parse_date(value, format)))
+    {
+        return (ColumnKind::Date, Some(date_format));
+    }
+    if values.iter().all(|value| is_number(value)) {
+        return (ColumnKind::Number, None);
+    }
+    if values.iter().all(|value| CURRENCY_CODE.is_match(value)) {
+        return (ColumnKind::Currency, None);
+    }
+    (ColumnKind::Text, None)
+}
+
+/// `.` unless amounts end in a comma and one or two digits more often.
+fn detect_decimal_separator(values: &[&str]) -> char {
+    let (mut dots, mut commas) = (0, 0);
+    for value in values {
+        match DECIMAL
+            .captures(strip_currency(value))
+            .map(|captures| captures[1].to_string())
+        {
+            Some(separator) if separator == "." => dots += 1,
+            Some(_) => commas += 1,
+            None => {}
+        }
+    }
+    if commas > dots { ',' } else { '.' }
+}
+
+/// A header has text in its cells, no dates or amounts.
+fn looks_like_header(record: &csv::StringRecord) -> bool {
+    record
+        .iter()
+        .any(|cell| cell.chars().any(char::is_alphabetic))
+        && record
+            .iter()
+            .all(|cell| cell.is_empty() || (!is_number(cell) && !is_date(cell)))
+}
+
+fn sniff(path: &Path) -> Result<(CsvSettings, CsvInspection), String> {
+    let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+    File::open(path)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?
+        .take(SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    if sample.is_empty() {
+        return Err("The file is empty".to_string());
+    }
+
+    let encoding = detect_encoding(&sample);
+    let mut text = String::new();
+    open_decoded(path, encoding)?
+        .take(SAMPLE_SIZE as u64)
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Failed to decode {:?} as {}: {}", path, encoding.name(), e))?;
+
+    let delimiter = detect_delimiter(&text);
+    let records = sample_records(&text, delimiter);
+    let (fields, _) = field_count_mode(&records);
+    if fields < 2 {
+        return Err("Couldn't find columns in the file".to_string());
+    }
+
+    // Preamble rows have fewer fields than the table
+    let start = records
+        .iter()
+        .take(MAX_PREAMBLE_ROWS + 1)
+        .position(|record| record.len() == fields)
+        .unwrap_or(0);
+    let has_header = records.len() > start + 1 && looks_like_header(&records[start]);
+    let data: Vec<&csv::StringRecord> = records
+        .iter()
+        .skip(start + usize::from(has_header))
+        .filter(|record| record.len() == fields)
+        .take(TYPE_SAMPLE_ROWS)
+        .collect();
+
+    let mut columns = Vec::with_capacity(fields);
+    let mut number_values = Vec::new();
+    for index in 0..fields {
+        let values: Vec<&str> = data
+            .iter()
+            .map(|record| record.get(index).unwrap_or_default())
+            .collect();
+        let (kind, date_format) = guess_column(&values);
+        if kind == ColumnKind::Number {
+            number_values.extend(values);
+        }
+
+        let name = if has_header {
+            records[start].get(index).unwrap_or_default().to_string()
+        } else {
+            String::new()
+        };
+        columns.push(CsvColumn {
+            name: if name.is_empty() {
+                format!("Column {}", index + 1)
+            } else {
+                name
+            },
+            kind,
+            date_format: date_format.map(str::to_string),
+        });
+    }
+
+    let settings = CsvSettings {
+        encoding,
+        delimiter,
+        data_start: start + usize::from(has_header),
+    };
+    let inspection = CsvInspection {
+        encoding: encoding.name().to_string(),
+        delimiter: char::from(delimiter).to_string(),
+        skip_rows: start,
+        has_header,
+        decimal_separator: detect_decimal_separator(&number_values).to_string(),
+        date_format: columns.iter().find_map(|column| column.date_format.clone()),
+        columns,
+        preview: data
+            .iter()
+            .take(PREVIEW_ROWS)
+            .map(|record| record.iter().map(str::to_string).collect())
+            .collect(),
+        rows: 0,
+    };
+    Ok((settings, inspection))
+}
+
+/// A reader past the preamble and header.
+fn open_reader(path: &Path, settings: &CsvSettings) -> Result<DecodedReader, String> {
+    let mut reader = csv_reader(open_decoded(path, settings.encoding)?, settings.delimiter);
+    let mut record = csv::StringRecord::new();
+    let mut skipped = 0;
+    while skipped < settings.data_start {
+        let more = reader
+            .read_record(&mut record)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        if !more {
+            break;
+        }
+        // Blank lines weren't counted while sniffing
+        if !is_blank(&record) {
+            skipped += 1;
+        }
+    }
+    Ok(reader)
+}
+
+fn count_rows(path: &Path, settings: &CsvSettings) -> Result<usize, String> {
+    let mut reader = open_reader(path, settings)?;
+    let mut record = csv::ByteRecord::new();
+    let mut rows = 0;
+    while reader
+        .read_byte_record(&mut record)
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?
+    {
+        if record.iter().any(|field| !field.is_empty()) {
+            rows += 1;
+        }
+    }
+    Ok(rows)
+}
+
+fn read_chunk(
+    path: &Path,
+    file: &mut OpenCsv,
+    offset: usize,
+    limit: usize,
+) -> Result<CsvChunk, String> {
+    let mut cursor = match file.cursor.take() {
+        Some(cursor) if cursor.next_row <= offset => cursor,
+        _ => Cursor {
+            reader: open_reader(path, &file.settings)?,
+            next_row: 0,
+        },
+    };
+
+    let mut rows = Vec::new();
+    let mut record = csv::StringRecord::new();
+    let mut done = false;
+    while rows.len() < limit {
+        let more = cursor
+            .reader
+            .read_record(&mut record)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        if !more {
+            done = true;
+            break;
+        }
+        if is_blank(&record) {
+            continue;
+        }
+
+        if cursor.next_row >= offset {
+            rows.push(record.iter().map(str::to_string).collect());
+        }
+        cursor.next_row += 1;
+    }
+
+    if !done {
+        file.cursor = Some(cursor);
+    }
+    Ok(CsvChunk { offset, rows, done })
+}
+
+/// Let the user choose a CSV or bank statement to import. Returns `None`
+/// when the dialog was cancelled.
+#[tauri::command]
+pub async fn pick_import_file(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<Option<ImportFile>, String> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("Import Transactions")
+        .set_parent(&window)
+        .add_filter("Transactions", &IMPORT_EXTENSIONS)
+        .pick_file(move |path| {
+            let _ = sender.send(path);
+        });
+
+    let Some(path) = receiver.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path
+        .into_path()
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+    Ok(Some(app.state::<CsvState>().add(path)))
+}
+
+/// Take a native drop onto the import as the file to import.
+#[tauri::command]
+pub fn import_dropped_file(app: tauri::AppHandle, drop_id: u64) -> Result<ImportFile, String> {
+    let mut paths = drag_drop::take_drop(&app, drop_id)?;
+    if paths.len() != 1 {
+        return Err("Drop a single file to import".to_string());
+    }
+    Ok(app.state::<CsvState>().add(paths.remove(0)))
+}
+
+/// Forget a file picked for the import.
+#[tauri::command]
+pub fn close_import_file(state: tauri::State<'_, CsvState>, id: u64) {
+    state.picked.lock().unwrap().remove(&id);
+    state.take(id);
+}
+
+/// Detect the encoding, delimiter, header row, decimal separator and date
+/// format of a picked CSV file, with a preview and a guess of each column's
+/// type. Reads the file once to count the rows, without loading it into
+/// memory.
+#[tauri::command]
+pub async fn inspect_csv(app: tauri::AppHandle, id: u64) -> Result<CsvInspection, String> {
+    let path = picked_path(&app, id)?;
+    let (settings, inspection, path) = tauri::async_runtime::spawn_blocking(move || {
+        let (settings, mut inspection) = sniff(&path)?;
+        inspection.rows = count_rows(&path, &settings)?;
+        Ok::<_, String>((settings, inspection, path))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    println!(
+        "📑 Inspected {:?}: {}, {:?} delimited, {} row(s)",
+        path, inspection.encoding, inspection.delimiter, inspection.rows
+    );
+    app.state::<CsvState>().put(
+        id,
+        OpenCsv {
+            settings,
+            cursor: None,
+        },
+    );
+    Ok(inspection)
+}
+
+/// Read `limit` data rows starting at row `offset`, with the settings
+/// `inspect_csv` detected. Reading chunks in order continues where the last
+/// one stopped.
+#[tauri::command]
+pub async fn read_csv_chunk(
+    app: tauri::AppHandle,
+    id: u64,
+    offset: usize,
+    limit: usize,
+) -> Result<CsvChunk, String> {
+    let limit = limit.clamp(1, MAX_CHUNK_ROWS);
+    let path = picked_path(&app, id)?;
+    // Taken out while reading so other files can be read meanwhile
+    let file = app.state::<CsvState>().take(id);
+
+    let state_app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut file = match file {
+            Some(file) => file,
+            None => OpenCsv {
+                settings: sniff(&path)?.0,
+                cursor: None,
+            },
+        };
+        let chunk = read_chunk(&path, &mut file, offset, limit);
+        state_app.state::<CsvState>().put(id, file);
+        chunk
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    /// Write `bytes` to a file of its own in the temp folder.
+    fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "midday-csv-test-{}-{}.csv",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn detects_encodings() {
+        let text = "Date,Description,Amount\n2024-01-05,Coffee,-3.50\n";
+
+        let mut with_bom = b"\xEF\xBB\xBF".to_vec();
+        with_bom.extend_from_slice(text.as_bytes());
+        assert_eq!(detect_encoding(&with_bom), encoding_rs::UTF_8);
+        assert_eq!(detect_encoding(text.as_bytes()), encoding_rs::UTF_8);
+
+        let mut le_with_bom = vec![0xFF, 0xFE];
+        le_with_bom.extend(utf16(text, false));
+        assert_eq!(detect_encoding(&le_with_bom), encoding_rs::UTF_16LE);
+        assert_eq!(detect_encoding(&utf16(text, false)), encoding_rs::UTF_16LE);
+        assert_eq!(detect_encoding(&utf16(text, true)), encoding_rs::UTF_16BE);
+
+        let (latin, _, _) = encoding_rs::WINDOWS_1252.encode("Café Müller;-4,50\n");
+        assert_eq!(detect_encoding(&latin), encoding_rs::WINDOWS_1252);
+    }
+
+    #[test]
+    fn utf8_cut_off_in_a_character_stays_utf8() {
+        let text = "Description\nCafé Müller\n".as_bytes();
+        let end = text.len() - "ller\n".len() - 1;
+        assert_eq!(detect_encoding(&text[..end]), encoding_rs::UTF_8);
+    }
+
+    #[test]
+    fn detects_delimiters() {
+        let semicolon = "Datum;Betrag;Text\n05.01.2024;-12,50;Miete\n06.01.2024;1.200,00;Gehalt\n";
+        assert_eq!(detect_delimiter(semicolon), b';');
+
+        let comma = "Date,Amount,Description\n2024-01-05,-12.50,\"Rent, January\"\n2024-01-06,1200.00,Salary\n";
+        assert_eq!(detect_delimiter(comma), b',');
+
+        let tab = "Date\tAmount\tDescription\n2024-01-05\t-12,50\tRent\n";
+        assert_eq!(detect_delimiter(tab), b'\t');
+
+        let pipe = "Date|Amount|Description\n2024-01-05|-12.50|Rent\n";
+        assert_eq!(detect_delimiter(pipe), b'|');
+    }
+
+    #[test]
+    fn detects_decimal_separators() {
+        assert_eq!(
+            detect_decimal_separator(&["-1.234,56", "12,50", "€ 3,00"]),
+            ','
+        );
+        assert_eq!(
+            detect_decimal_separator(&["-1,234.56", "12.50", "USD 3.00"]),
+            '.'
+        );
+        assert_eq!(detect_decimal_separator(&["12,50-", "(1.000,5)"]), ',');
+        // Thousands only, nothing to go by
+        assert_eq!(detect_decimal_separator(&["1,000", "12"]), '.');
+    }
+
+    #[test]
+    fn guesses_date_formats() {
+        assert_eq!(
+            guess_column(&["05.01.2024", "31.12.2023"]),
+            (ColumnKind::Date, Some("dd.MM.yyyy"))
+        );
+        assert_eq!(
+            guess_column(&["2024-01-05", "2024-01-06T12:30:00Z", ""]),
+            (ColumnKind::Date, Some("yyyy-MM-dd"))
+        );
+        // Ambiguous dates are read day first, a day over 12 settles it
+        assert_eq!(
+            guess_column(&["01/02/2024", "02/03/2024"]),
+            (ColumnKind::Date, Some("dd/MM/yyyy"))
+        );
+        assert_eq!(
+            guess_column(&["01/02/2024", "01/13/2024"]),
+            (ColumnKind::Date, Some("MM/dd/yyyy"))
+        );
+        assert_eq!(
+            guess_column(&["05-01-24", "31-12-23"]),
+            (ColumnKind::Date, Some("dd-MM-yy"))
+        );
+        assert_eq!(
+            guess_column(&["5 Jan 2024", "31 Dec 2023"]),
+            (ColumnKind::Date, Some("dd MMM yyyy"))
+        );
+        assert_eq!(
+            guess_column(&["January 5, 2024"]),
+            (ColumnKind::Date, Some("MMMM dd, yyyy"))
+        );
+    }
+
+    #[test]
+    fn guesses_other_column_kinds() {
+        assert_eq!(
+            guess_column(&["-1.234,56", "€ 12,50", "USD 3.00", "(4.20)"]),
+            (ColumnKind::Number, None)
+        );
+        assert_eq!(guess_column(&["EUR", "USD"]), (ColumnKind::Currency, None));
+        assert_eq!(guess_column(&["Rent", "12.50"]), (ColumnKind::Text, None));
+        assert_eq!(guess_column(&["", ""]), (ColumnKind::Empty, None));
+    }
+
+    #[test]
+    fn sniffs_a_bank_export_with_a_preamble() {
+        let text = "Kontonummer;DE89370400440532013000\n\
+                    Zeitraum;01.01.2024 - 31.01.2024\n\
+                    \n\
+                    Buchungstag;Verwendungszweck;Betrag;Währung\n\
+                    02.01.2024;Café Müller;-4,50;EUR\n\
+                    15.01.2024;Gehalt Januar;2.500,00;EUR\n\
+                    31.01.2024;Miete;-1.200,00;EUR\n";
+        let (bytes, _, _) = encoding_rs::WINDOWS_1252.encode(text);
+        let path = temp_file("preamble", &bytes);
+
+        let (settings, inspection) = sniff(&path).unwrap();
+        assert_eq!(inspection.encoding, "windows-1252");
+        assert_eq!(inspection.delimiter, ";");
+        assert_eq!(inspection.skip_rows, 2);
+        assert!(inspection.has_header);
+        assert_eq!(inspection.decimal_separator, ",");
+        assert_eq!(inspection.date_format.as_deref(), Some("dd.MM.yyyy"));
+        let columns: Vec<(&str, ColumnKind)> = inspection
+            .columns
+            .iter()
+            .map(|column| (column.name.as_str(), column.kind))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("Buchungstag", ColumnKind::Date),
+                ("Verwendungszweck", ColumnKind::Text),
+                ("Betrag", ColumnKind::Number),
+                ("Währung", ColumnKind::Currency),
+            ]
+        );
+        assert_eq!(inspection.preview[0][1], "Café Müller");
+        assert_eq!(count_rows(&path, &settings).unwrap(), 3);
+
+        let mut file = OpenCsv {
+            settings,
+            cursor: None,
+        };
+        let first = read_chunk(&path, &mut file, 0, 2).unwrap();
+        assert_eq!(first.rows.len(), 2);
+        assert!(!first.done);
+        let rest = read_chunk(&path, &mut file, 2, 2).unwrap();
+        assert_eq!(rest.rows, [["31.01.2024", "Miete", "-1.200,00", "EUR"]]);
+        assert!(rest.done);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sniffs_utf16_without_a_header() {
+        let text = "2024-01-05\tCoffee\t-3.50\n2024-01-06\tSalary\t2500.00\n";
+        let path = temp_file("utf16", &utf16(text, false));
+
+        let (_, inspection) = sniff(&path).unwrap();
+        assert_eq!(inspection.encoding, "UTF-16LE");
+        assert_eq!(inspection.delimiter, "\t");
+        assert!(!inspection.has_header);
+        assert_eq!(inspection.skip_rows, 0);
+        assert_eq!(inspection.decimal_separator, ".");
+        assert_eq!(inspection.columns[0].name, "Column 1");
+        assert_eq!(inspection.preview.len(), 2);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
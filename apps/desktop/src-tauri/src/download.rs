@@ -0,0 +1,670 @@
+use chrono::Datelike;
+use futures_util::StreamExt;
+use globset::Glob;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::webview::DownloadEvent;
+use tauri::{Emitter, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+
+use crate::store;
+use crate::upload::next_upload_id;
+
+const SETTINGS_FILE: &str = "download-settings.json";
+const HISTORY_FILE: &str = "download-history.json";
+
+/// Oldest entries are dropped from the history beyond this.
+const MAX_HISTORY: usize = 200;
+
+/// Progress events are throttled to roughly this interval per download.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Where files of a given type end up, e.g. PDFs named `invoice*` go to
+/// `~/Documents/Midday/Invoices/{year}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRule {
+    /// Lowercase extensions without the dot. Empty matches every type.
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    /// Glob matched against the file name, e.g. `invoice*`
+    #[serde(default)]
+    pub name_pattern: Option<String>,
+    /// Directory template. Supports `~`, `{downloads}`, `{documents}`,
+    /// `{year}`, `{month}`, `{day}` and `{type}`. Relative paths are resolved
+    /// against the Downloads folder.
+    pub destination: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadSettings {
+    /// Used when no rule matches, defaults to the Downloads folder
+    #[serde(default)]
+    pub default_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub rules: Vec<DownloadRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadStatus {
+    InProgress,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRecord {
+    pub id: String,
+    pub url: String,
+    pub file_name: String,
+    pub path: PathBuf,
+    pub status: DownloadStatus,
+    pub bytes_received: u64,
+    pub total_bytes: Option<u64>,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgress<'a> {
+    id: &'a str,
+    bytes_received: u64,
+    total_bytes: Option<u64>,
+}
+
+#[derive(Default)]
+struct DownloadsInner {
+    settings: DownloadSettings,
+    history: Vec<DownloadRecord>,
+    running: HashMap<String, CancellationToken>,
+}
+
+/// Downloads started from the main and search windows.
+#[derive(Default)]
+pub struct Downloads {
+    inner: Mutex<DownloadsInner>,
+}
+
+pub fn setup(app: &tauri::AppHandle) {
+    let settings: DownloadSettings = store::config_file(app, SETTINGS_FILE)
+        .map(|path| store::load(&path))
+        .unwrap_or_default();
+    let mut history: Vec<DownloadRecord> = store::data_file(app, HISTORY_FILE)
+        .map(|path| store::load(&path))
+        .unwrap_or_default();
+
+    // Downloads interrupted by a quit don't resume
+    for record in history.iter_mut() {
+        if record.status == DownloadStatus::InProgress {
+            record.status = DownloadStatus::Failed;
+            record.error = Some("Interrupted".to_string());
+        }
+    }
+
+    app.manage(Downloads {
+        inner: Mutex::new(DownloadsInner {
+            settings,
+            history,
+            running: HashMap::new(),
+        }),
+    });
+}
+
+/// `on_download` handler for the main and search windows.
+///
+/// http(s) downloads are cancelled in the webview and fetched natively with
+/// the webview's cookies, so we can report progress. Blob and data URLs only
+/// exist inside the webview, those are left to it and only redirected to the
+/// destination picked by the rules.
+pub fn handle_download(webview: tauri::Webview, event: DownloadEvent<'_>) -> bool {
+    let app = webview.app_handle().clone();
+
+    match event {
+        DownloadEvent::Requested { url, destination } => {
+            let suggested_name = destination
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .filter(|name| !name.is_empty())
+                .or_else(|| file_name_from_url(&url));
+
+            if matches!(url.scheme(), "http" | "https") {
+                println!("📥 Downloading {} natively", url);
+                let app_handle = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    download(&app_handle, &webview, url, suggested_name).await;
+                });
+                return false;
+            }
+
+            let file_name = suggested_name.unwrap_or_else(|| "download".to_string());
+            let path = match resolve_destination(&app, &file_name) {
+                Ok(path) => path,
+                Err(e) => {
+                    eprintln!("📥 Failed to resolve download destination: {}", e);
+                    return true;
+                }
+            };
+
+            println!("📥 Webview download to {:?}", path);
+            *destination = path.clone();
+            start_record(&app, url.to_string(), path, None);
+            true
+        }
+        DownloadEvent::Finished { url, path, success } => {
+            let url = url.to_string();
+            let Some(downloads) = app.try_state::<Downloads>() else {
+                return true;
+            };
+
+            let finished = {
+                let mut inner = downloads.inner.lock().unwrap();
+                let record = inner.history.iter_mut().rev().find(|record| {
+                    record.url == url && record.status == DownloadStatus::InProgress
+                });
+                record.map(|record| {
+                    // macOS never reports the path, keep the one we picked
+                    if let Some(path) = path {
+                        record.path = path;
+                    }
+                    record.status = if success {
+                        DownloadStatus::Completed
+                    } else {
+                        DownloadStatus::Failed
+                    };
+                    if let Ok(metadata) = std::fs::metadata(&record.path) {
+                        record.bytes_received = metadata.len();
+                        record.total_bytes = Some(metadata.len());
+                    }
+                    record.finished_at = Some(chrono::Utc::now().timestamp_millis());
+                    record.clone()
+                })
+            };
+
+            if let Some(record) = finished {
+                println!("📥 Download finished ({}): {:?}", success, record.path);
+                finish(&app, record);
+            }
+            true
+        }
+        _ => true,
+    }
+}
+
+/// GET request carrying the webview's cookies for `url`, so it is authenticated
+/// like a request made by the dashboard. Must not run on the main thread,
+/// reading cookies deadlocks there on Windows.
+pub fn authenticated_get(webview: &tauri::Webview, url: &tauri::Url) -> reqwest::RequestBuilder {
+    let cookie_header = webview
+        .cookies_for_url(url.clone())
+        .map(|cookies| {
+            cookies
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .unwrap_or_default();
+
+    let request = reqwest::Client::new().get(url.clone());
+    if cookie_header.is_empty() {
+        request
+    } else {
+        request.header(reqwest::header::COOKIE, cookie_header)
+    }
+}
+
+async fn download(
+    app: &tauri::AppHandle,
+    webview: &tauri::Webview,
+    url: tauri::Url,
+    suggested_name: Option<String>,
+) {
+    let request = authenticated_get(webview, &url);
+
+    let response = match request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("📥 Download of {} failed: {}", url, e);
+            let _ = app.emit(
+                "download-failed",
+                serde_json::json!({ "url": url.to_string(), "error": e.to_string() }),
+            );
+            return;
+        }
+    };
+
+    let file_name = response
+        .headers()
+        .get(reqwest::header::CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(file_name_from_disposition)
+        .or(suggested_name)
+        .unwrap_or_else(|| "download".to_string());
+
+    let path = match resolve_destination(app, &file_name) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("📥 Failed to resolve download destination: {}", e);
+            return;
+        }
+    };
+
+    let total_bytes = response.content_length();
+    let token = CancellationToken::new();
+    let id = start_record(app, url.to_string(), path.clone(), Some(token.clone()));
+
+    let result = tokio::select! {
+        result = write_response(app, &id, response, &path, total_bytes) => result,
+        _ = token.cancelled() => Err("Cancelled".to_string()),
+    };
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+
+    let Some(downloads) = app.try_state::<Downloads>() else {
+        return;
+    };
+    let finished = {
+        let mut inner = downloads.inner.lock().unwrap();
+        inner.running.remove(&id);
+        inner
+            .history
+            .iter_mut()
+            .find(|record| record.id == id)
+            .map(|record| {
+                match &result {
+                    Ok(bytes_received) => {
+                        record.status = DownloadStatus::Completed;
+                        record.bytes_received = *bytes_received;
+                    }
+                    Err(_) if token.is_cancelled() => record.status = DownloadStatus::Cancelled,
+                    Err(e) => {
+                        record.status = DownloadStatus::Failed;
+                        record.error = Some(e.clone());
+                    }
+                }
+                record.finished_at = Some(chrono::Utc::now().timestamp_millis());
+                record.clone()
+            })
+    };
+
+    if let Some(record) = finished {
+        println!("📥 Download {:?}: {:?}", record.status, record.path);
+        finish(app, record);
+    }
+}
+
+async fn write_response(
+    app: &tauri::AppHandle,
+    id: &str,
+    response: reqwest::Response,
+    path: &Path,
+    total_bytes: Option<u64>,
+) -> Result<u64, String> {
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+
+    let mut stream = response.bytes_stream();
+    let mut bytes_received = 0u64;
+    let mut last_progress = Instant::now();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        bytes_received += chunk.len() as u64;
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let _ = app.emit(
+                "download-progress",
+                DownloadProgress {
+                    id,
+                    bytes_received,
+                    total_bytes,
+                },
+            );
+        }
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+
+    Ok(bytes_received)
+}
+
+/// Add an in-progress entry to the history and announce it.
+fn start_record(
+    app: &tauri::AppHandle,
+    url: String,
+    path: PathBuf,
+    token: Option<CancellationToken>,
+) -> String {
+    let record = DownloadRecord {
+        id: next_upload_id(),
+        url,
+        file_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path,
+        status: DownloadStatus::InProgress,
+        bytes_received: 0,
+        total_bytes: None,
+        error: None,
+        started_at: chrono::Utc::now().timestamp_millis(),
+        finished_at: None,
+    };
+    let id = record.id.clone();
+
+    if let Some(downloads) = app.try_state::<Downloads>() {
+        let mut inner = downloads.inner.lock().unwrap();
+        if let Some(token) = token {
+            inner.running.insert(id.clone(), token);
+        }
+        inner.history.push(record.clone());
+        let overflow = inner.history.len().saturating_sub(MAX_HISTORY);
+        inner.history.drain(..overflow);
+        save_history(app, &inner.history);
+    }
+
+    let _ = app.emit("download-started", record);
+    id
+}
+
+fn finish(app: &tauri::AppHandle, record: DownloadRecord) {
+    if let Some(downloads) = app.try_state::<Downloads>() {
+        save_history(app, &downloads.inner.lock().unwrap().history);
+    }
+    let _ = app.emit("download-finished", record);
+}
+
+fn save_history(app: &tauri::AppHandle, history: &[DownloadRecord]) {
+    let result = store::data_file(app, HISTORY_FILE).and_then(|path| store::save(&path, &history));
+    if let Err(e) = result {
+        eprintln!("📥 Failed to save download history: {}", e);
+    }
+}
+
+fn file_name_from_url(url: &tauri::Url) -> Option<String> {
+    url.path_segments()?
+        .next_back()
+        .filter(|segment| !segment.is_empty())
+        .map(percent_decode)
+}
+
+/// Minimal percent decoding for file names taken from URLs and headers.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// `filename*=UTF-8''...` wins over `filename="..."`, like browsers do.
+pub fn file_name_from_disposition(value: &str) -> Option<String> {
+    let mut plain = None;
+
+    for part in value.split(';').map(str::trim) {
+        if let Some(encoded) = part.strip_prefix("filename*=") {
+            let encoded = encoded.rsplit("''").next().unwrap_or(encoded);
+            return Some(percent_decode(encoded.trim_matches('"')));
+        }
+        if let Some(name) = part.strip_prefix("filename=") {
+            plain = Some(name.trim_matches('"').to_string());
+        }
+    }
+
+    plain.filter(|name| !name.is_empty())
+}
+
+/// Strip directories and characters file systems don't accept from a
+/// server-provided name.
+pub fn safe_file_name(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace());
+
+    if cleaned.is_empty() {
+        "download".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// Expand a destination template into a directory.
+fn expand_template(
+    app: &tauri::AppHandle,
+    template: &str,
+    extension: &str,
+) -> Result<PathBuf, String> {
+    let path = app.path();
+    let downloads = path
+        .download_dir()
+        .map_err(|e| format!("Failed to resolve Downloads folder: {}", e))?;
+    let now = chrono::Local::now();
+
+    let mut expanded = template
+        .replace("{year}", &now.year().to_string())
+        .replace("{month}", &format!("{:02}", now.month()))
+        .replace("{day}", &format!("{:02}", now.day()))
+        .replace("{type}", extension)
+        .replace("{downloads}", &downloads.to_string_lossy());
+
+    if expanded.contains("{documents}") {
+        let documents = path
+            .document_dir()
+            .map_err(|e| format!("Failed to resolve Documents folder: {}", e))?;
+        expanded = expanded.replace("{documents}", &documents.to_string_lossy());
+    }
+
+    if let Some(rest) = expanded.strip_prefix('~') {
+        let home = path
+            .home_dir()
+            .map_err(|e| format!("Failed to resolve home folder: {}", e))?;
+        expanded = format!("{}{}", home.to_string_lossy(), rest);
+    }
+
+    let expanded = PathBuf::from(expanded);
+    Ok(if expanded.is_absolute() {
+        expanded
+    } else {
+        downloads.join(expanded)
+    })
+}
+
+/// Pick the destination for `file_name` from the rules, create the directory
+/// and avoid overwriting existing files.
+fn resolve_destination(app: &tauri::AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    let file_name = safe_file_name(file_name);
+    let extension = Path::new(&file_name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let settings = app
+        .try_state::<Downloads>()
+        .map(|downloads| downloads.inner.lock().unwrap().settings.clone())
+        .unwrap_or_default();
+
+    let rule = settings.rules.iter().find(|rule| {
+        let type_matches = rule.file_types.is_empty()
+            || rule
+                .file_types
+                .iter()
+                .any(|file_type| file_type.eq_ignore_ascii_case(&extension));
+        let name_matches = match &rule.name_pattern {
+            Some(pattern) => Glob::new(pattern)
+                .map(|glob| glob.compile_matcher().is_match(file_name.to_lowercase()))
+                .unwrap_or(false),
+            None => true,
+        };
+        type_matches && name_matches
+    });
+
+    let directory = match (rule, settings.default_directory) {
+        (Some(rule), _) => expand_template(app, &rule.destination, &extension)?,
+        (None, Some(directory)) => directory,
+        (None, None) => app
+            .path()
+            .download_dir()
+            .map_err(|e| format!("Failed to resolve Downloads folder: {}", e))?,
+    };
+
+    std::fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create {:?}: {}", directory, e))?;
+
+    Ok(unique_path(&directory, &file_name))
+}
+
+/// `report.pdf`, `report (1).pdf`, `report (2).pdf`, ...
+pub fn unique_path(directory: &Path, file_name: &str) -> PathBuf {
+    let candidate = directory.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let path = Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| directory.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+fn find_record(downloads: &Downloads, id: &str) -> Result<DownloadRecord, String> {
+    downloads
+        .inner
+        .lock()
+        .unwrap()
+        .history
+        .iter()
+        .find(|record| record.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown download: {}", id))
+}
+
+#[tauri::command]
+pub fn list_downloads(downloads: tauri::State<'_, Downloads>) -> Vec<DownloadRecord> {
+    let mut history = downloads.inner.lock().unwrap().history.clone();
+    history.reverse();
+    history
+}
+
+#[tauri::command]
+pub fn open_download(
+    app: tauri::AppHandle,
+    downloads: tauri::State<'_, Downloads>,
+    id: String,
+) -> Result<(), String> {
+    let record = find_record(&downloads, &id)?;
+    app.opener()
+        .open_path(record.path.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open {:?}: {}", record.path, e))
+}
+
+#[tauri::command]
+pub fn show_download_in_folder(
+    app: tauri::AppHandle,
+    downloads: tauri::State<'_, Downloads>,
+    id: String,
+) -> Result<(), String> {
+    let record = find_record(&downloads, &id)?;
+    app.opener()
+        .reveal_item_in_dir(&record.path)
+        .map_err(|e| format!("Failed to show {:?}: {}", record.path, e))
+}
+
+#[tauri::command]
+pub fn cancel_download(downloads: tauri::State<'_, Downloads>, id: String) -> Result<(), String> {
+    let token = downloads
+        .inner
+        .lock()
+        .unwrap()
+        .running
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| format!("Download {} is not running", id))?;
+    token.cancel();
+    Ok(())
+}
+
+/// Forget finished downloads. Files on disk are left alone.
+#[tauri::command]
+pub fn clear_download_history(app: tauri::AppHandle, downloads: tauri::State<'_, Downloads>) {
+    let mut inner = downloads.inner.lock().unwrap();
+    inner
+        .history
+        .retain(|record| record.status == DownloadStatus::InProgress);
+    save_history(&app, &inner.history);
+}
+
+#[tauri::command]
+pub fn get_download_settings(downloads: tauri::State<'_, Downloads>) -> DownloadSettings {
+    downloads.inner.lock().unwrap().settings.clone()
+}
+
+#[tauri::command]
+pub fn set_download_settings(
+    app: tauri::AppHandle,
+    downloads: tauri::State<'_, Downloads>,
+    settings: DownloadSettings,
+) -> Result<(), String> {
+    for rule in &settings.rules {
+        if let Some(pattern) = &rule.name_pattern {
+            Glob::new(pattern).map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+        }
+    }
+
+    store::save(&store::config_file(&app, SETTINGS_FILE)?, &settings)?;
+    downloads.inner.lock().unwrap().settings = settings;
+    Ok(())
+}
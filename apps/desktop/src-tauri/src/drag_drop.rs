@@ -0,0 +1,251 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{DragDropEvent, Emitter, Manager, WindowEvent};
+
+use crate::archive;
+use crate::email;
+use crate::ics;
+use crate::merge;
+use crate::upload::{self, UploadSource, UploadTarget};
+use crate::vcard;
+
+/// Paths of drops the dashboard hasn't picked a target for yet, keyed by drop id.
+#[derive(Default)]
+pub struct DropState {
+    next_id: u64,
+    drops: HashMap<u64, Vec<PathBuf>>,
+}
+
+pub type DropStateHandle = Mutex<DropState>;
+
+/// Drop position in logical pixels, matching DOM client coordinates.
+#[derive(Clone, Copy, Serialize)]
+struct DropPosition {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DragEnterPayload {
+    paths: Vec<String>,
+    position: DropPosition,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DropPayload {
+    drop_id: u64,
+    paths: Vec<String>,
+    position: DropPosition,
+    /// Shift was held when the files were dropped. The webview gets no key
+    /// events during an OS drag, so this is read from the system.
+    shift_key: bool,
+}
+
+/// Forward native drag and drop on `window` to the dashboard. The dashboard
+/// hit-tests the position to decide between Inbox and Vault and answers with
+/// `upload_dropped_files`.
+pub fn attach(window: &tauri::WebviewWindow) {
+    let window_clone = window.clone();
+    window.on_window_event(move |event| {
+        let WindowEvent::DragDrop(drag_event) = event else {
+            return;
+        };
+
+        let scale_factor = window_clone.scale_factor().unwrap_or(1.0);
+        let to_logical = |position: &tauri::PhysicalPosition<f64>| {
+            let logical = position.to_logical::<f64>(scale_factor);
+            DropPosition {
+                x: logical.x,
+                y: logical.y,
+            }
+        };
+
+        match drag_event {
+            DragDropEvent::Enter { paths, position } => {
+                let _ = window_clone.emit(
+                    "native-drag-enter",
+                    DragEnterPayload {
+                        paths: paths_to_strings(paths),
+                        position: to_logical(position),
+                    },
+                );
+            }
+            DragDropEvent::Over { position } => {
+                let _ = window_clone.emit("native-drag-over", to_logical(position));
+            }
+            DragDropEvent::Drop { paths, position } => {
+                let Some(state) = window_clone.try_state::<DropStateHandle>() else {
+                    return;
+                };
+
+                let drop_id = {
+                    let mut state = state.lock().unwrap();
+                    state.next_id += 1;
+                    let drop_id = state.next_id;
+                    state.drops.insert(drop_id, paths.clone());
+                    drop_id
+                };
+
+                println!("📥 Files dropped ({}): {:?}", drop_id, paths);
+                let _ = window_clone.emit(
+                    "native-drop",
+                    DropPayload {
+                        drop_id,
+                        paths: paths_to_strings(paths),
+                        position: to_logical(position),
+                        shift_key: modifiers::shift_held(),
+                    },
+                );
+            }
+            DragDropEvent::Leave => {
+                let _ = window_clone.emit("native-drag-leave", ());
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Keyboard state at the time of the drop, read on the main thread where
+/// window events are delivered.
+mod modifiers {
+    #[cfg(target_os = "linux")]
+    pub fn shift_held() -> bool {
+        use gtk::gdk;
+
+        gdk::Display::default()
+            .and_then(|display| gdk::Keymap::for_display(&display))
+            .is_some_and(|keymap| {
+                keymap.modifier_state() & gdk::ModifierType::SHIFT_MASK.bits() != 0
+            })
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn shift_held() -> bool {
+        const COMBINED_SESSION_STATE: i32 = 0;
+        const SHIFT_MASK: u64 = 0x0002_0000;
+
+        #[link(name = "CoreGraphics", kind = "framework")]
+        unsafe extern "C" {
+            fn CGEventSourceFlagsState(state_id: i32) -> u64;
+        }
+
+        unsafe { CGEventSourceFlagsState(COMBINED_SESSION_STATE) & SHIFT_MASK != 0 }
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn shift_held() -> bool {
+        const VK_SHIFT: i32 = 0x10;
+
+        #[link(name = "user32")]
+        unsafe extern "system" {
+            fn GetAsyncKeyState(key: i32) -> i16;
+        }
+
+        unsafe { GetAsyncKeyState(VK_SHIFT) < 0 }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    pub fn shift_held() -> bool {
+        false
+    }
+}
+
+fn paths_to_strings(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Take the paths of a drop the dashboard found a handler for.
+pub fn take_drop(app: &tauri::AppHandle, drop_id: u64) -> Result<Vec<PathBuf>, String> {
+    app.state::<DropStateHandle>()
+        .lock()
+        .unwrap()
+        .drops
+        .remove(&drop_id)
+        .ok_or_else(|| format!("Unknown drop: {}", drop_id))
+}
+
+/// Queue the files of a native drop for upload. Returns one upload id per file, matching
+/// the ids in the `upload-*` events. `folder` is the Vault folder path, if any. With `merge`
+/// (the dashboard sets it from `shiftKey` of the drop) the dropped images become a single PDF.
+#[tauri::command]
+pub async fn upload_dropped_files(
+    app: tauri::AppHandle,
+    drop_id: u64,
+    target: UploadTarget,
+    folder: Option<Vec<String>>,
+    merge: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let paths = take_drop(&app, drop_id)?;
+    let folder = folder.unwrap_or_default();
+
+    // Contacts and calendars are imported after confirming in the dashboard
+    let (contacts, paths): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.into_iter().partition(|path| vcard::is_vcard(path));
+    let (calendars, paths): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.into_iter().partition(|path| ics::is_calendar(path));
+    vcard::import(&app, contacts).await;
+    ics::import(&app, calendars).await;
+
+    // Emails are uploaded as their attachments
+    let (emails, paths): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.into_iter().partition(|path| email::is_email(path));
+    let mut ids =
+        email::enqueue_emails(&app, emails, target, folder.clone(), UploadSource::Drop).await;
+
+    // Archives are uploaded as the documents inside them
+    let (archives, paths): (Vec<PathBuf>, Vec<PathBuf>) = paths
+        .into_iter()
+        .partition(|path| archive::is_archive(path));
+    ids.extend(
+        archive::enqueue_archives(&app, archives, target, folder.clone(), UploadSource::Drop).await,
+    );
+
+    let (images, others): (Vec<PathBuf>, Vec<PathBuf>) = paths
+        .iter()
+        .cloned()
+        .partition(|path| merge::is_image(path));
+    if !merge.unwrap_or(false) || images.len() < 2 {
+        ids.extend(upload::enqueue(
+            &app,
+            paths,
+            target,
+            folder,
+            UploadSource::Drop,
+        ));
+        return Ok(ids);
+    }
+
+    // Anything that isn't an image is uploaded on its own next to the PDF
+    ids.push(
+        merge::merge_and_enqueue(
+            &app,
+            images,
+            target,
+            folder.clone(),
+            None,
+            UploadSource::Drop,
+        )
+        .await?,
+    );
+    ids.extend(upload::enqueue(
+        &app,
+        others,
+        target,
+        folder,
+        UploadSource::Drop,
+    ));
+    Ok(ids)
+}
+
+/// Forget a drop the dashboard decided not to handle (dropped outside a target).
+#[tauri::command]
+pub fn discard_drop(state: tauri::State<'_, DropStateHandle>, drop_id: u64) {
+    state.lock().unwrap().drops.remove(&drop_id);
+}
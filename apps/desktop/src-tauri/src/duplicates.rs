@@ -0,0 +1,503 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::merge;
+use crate::preprocess;
+use crate::store;
+use crate::upload::{self, UploadTarget};
+
+const SETTINGS_FILE: &str = "duplicate-settings.json";
+const INDEX_FILE: &str = "upload-index.json";
+
+/// Oldest entries are dropped from the index beyond this.
+const MAX_ENTRIES: usize = 20_000;
+
+/// Images whose difference hashes differ in at most this many of the 64 bits
+/// are treated as the same receipt, e.g. a re-saved or slightly cropped photo.
+const SIMILAR_DISTANCE: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateAction {
+    /// Don't upload the file
+    Skip,
+    /// Upload it, but emit `upload-duplicate` so the dashboard can tell the user
+    Warn,
+    Allow,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DuplicateSettings {
+    /// Files with exactly the same content
+    pub exact: DuplicateAction,
+    /// Images that look the same
+    pub similar: DuplicateAction,
+}
+
+impl Default for DuplicateSettings {
+    fn default() -> Self {
+        DuplicateSettings {
+            exact: DuplicateAction::Skip,
+            similar: DuplicateAction::Warn,
+        }
+    }
+}
+
+/// Content hashes of a file.
+#[derive(Debug, Clone)]
+pub struct Fingerprint {
+    pub sha256: String,
+    /// Difference hash, only for images
+    pub dhash: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexEntry {
+    sha256: String,
+    #[serde(default)]
+    dhash: Option<u64>,
+    file_name: String,
+    /// Uploads only match within the team they went to
+    team_id: String,
+    target: UploadTarget,
+    uploaded_at: u64,
+}
+
+/// A file known to be on its way, by upload id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueuedEntry {
+    sha256: String,
+    target: UploadTarget,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DuplicateIndex {
+    entries: Vec<IndexEntry>,
+    /// Queued files whose hash is known before uploading, e.g. from watched
+    /// folders, so they aren't queued twice
+    #[serde(default)]
+    queued: HashMap<String, QueuedEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    Exact,
+    Similar,
+}
+
+/// An earlier upload a file matches.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateMatch {
+    pub kind: DuplicateKind,
+    pub file_name: String,
+    /// Unix seconds
+    pub uploaded_at: u64,
+    /// Differing bits between the image hashes, 0 for exact duplicates
+    pub distance: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCheck {
+    pub path: PathBuf,
+    pub duplicate: Option<DuplicateMatch>,
+    /// What the upload queue would do with the file
+    pub action: DuplicateAction,
+}
+
+struct DuplicatesInner {
+    settings: DuplicateSettings,
+    index: DuplicateIndex,
+}
+
+/// Hashes of everything uploaded through the shell, from any source.
+pub struct Duplicates {
+    inner: Mutex<DuplicatesInner>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn setup(app: &tauri::AppHandle) {
+    let settings: DuplicateSettings = store::config_file(app, SETTINGS_FILE)
+        .map(|path| store::load(&path))
+        .unwrap_or_default();
+    let index: DuplicateIndex = store::data_file(app, INDEX_FILE)
+        .map(|path| store::load(&path))
+        .unwrap_or_default();
+
+    app.manage(Duplicates {
+        inner: Mutex::new(DuplicatesInner { settings, index }),
+    });
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 64-bit difference hash: shrink to 9x8 grayscale and compare each pixel
+/// with its right neighbour. Survives resizing, recompression and small
+/// colour changes.
+fn dhash(path: &Path) -> Result<u64, String> {
+    let image = preprocess::open_image(path)?;
+    let small = image
+        .grayscale()
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    Ok(hash)
+}
+
+/// Differing bits between two difference hashes, if few enough for the
+/// images to count as the same.
+fn similar_distance(a: u64, b: u64) -> Option<u32> {
+    let distance = (a ^ b).count_ones();
+    (distance <= SIMILAR_DISTANCE).then_some(distance)
+}
+
+/// Hash a file on a blocking thread. Returns `None` when it can't be read,
+/// the upload then reports the actual error.
+pub async fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let path = path.to_path_buf();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let sha256 = sha256_file(&path)?;
+        // Images we can't decode are still compared by content
+        let dhash = if merge::is_image(&path) {
+            dhash(&path).ok()
+        } else {
+            None
+        };
+        Ok::<_, String>(Fingerprint { sha256, dhash })
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+
+    match result {
+        Ok(fingerprint) => Some(fingerprint),
+        Err(e) => {
+            eprintln!("🔁 Failed to hash file: {}", e);
+            None
+        }
+    }
+}
+
+/// The closest earlier upload to the same team and target, exact matches first.
+pub fn find(
+    app: &tauri::AppHandle,
+    fingerprint: &Fingerprint,
+    team_id: &str,
+    target: UploadTarget,
+) -> Option<DuplicateMatch> {
+    let duplicates = app.try_state::<Duplicates>()?;
+    let inner = duplicates.inner.lock().unwrap();
+    let entries = inner
+        .index
+        .entries
+        .iter()
+        .filter(|entry| entry.target == target && entry.team_id == team_id);
+
+    if let Some(entry) = entries
+        .clone()
+        .find(|entry| entry.sha256 == fingerprint.sha256)
+    {
+        return Some(DuplicateMatch {
+            kind: DuplicateKind::Exact,
+            file_name: entry.file_name.clone(),
+            uploaded_at: entry.uploaded_at,
+            distance: 0,
+        });
+    }
+
+    let dhash = fingerprint.dhash?;
+    entries
+        .filter_map(|entry| Some((entry, similar_distance(entry.dhash?, dhash)?)))
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(entry, distance)| DuplicateMatch {
+            kind: DuplicateKind::Similar,
+            file_name: entry.file_name.clone(),
+            uploaded_at: entry.uploaded_at,
+            distance,
+        })
+}
+
+/// What to do with a file matching an earlier upload.
+pub fn action_for(app: &tauri::AppHandle, duplicate: &DuplicateMatch) -> DuplicateAction {
+    let Some(duplicates) = app.try_state::<Duplicates>() else {
+        return DuplicateAction::Allow;
+    };
+    let settings = duplicates.inner.lock().unwrap().settings.clone();
+    match duplicate.kind {
+        DuplicateKind::Exact => settings.exact,
+        DuplicateKind::Similar => settings.similar,
+    }
+}
+
+/// Whether a file with this content was uploaded to `target` or is queued for
+/// it. Without a team, uploads to any team count.
+pub fn is_known(
+    app: &tauri::AppHandle,
+    sha256: &str,
+    team_id: Option<&str>,
+    target: UploadTarget,
+) -> bool {
+    let Some(duplicates) = app.try_state::<Duplicates>() else {
+        return false;
+    };
+    let inner = duplicates.inner.lock().unwrap();
+
+    inner.index.entries.iter().any(|entry| {
+        entry.sha256 == sha256
+            && entry.target == target
+            && team_id.is_none_or(|team_id| entry.team_id == team_id)
+    }) || inner
+        .index
+        .queued
+        .values()
+        .any(|queued| queued.sha256 == sha256 && queued.target == target)
+}
+
+/// Remember that upload `id` carries a file with this content until it is
+/// recorded or forgotten.
+pub fn track(app: &tauri::AppHandle, id: &str, sha256: &str, target: UploadTarget) {
+    let Some(duplicates) = app.try_state::<Duplicates>() else {
+        return;
+    };
+    let mut inner = duplicates.inner.lock().unwrap();
+    inner.index.queued.insert(
+        id.to_string(),
+        QueuedEntry {
+            sha256: sha256.to_string(),
+            target,
+        },
+    );
+    save_index(app, &inner.index);
+}
+
+/// Forget a queued upload that was cancelled or skipped.
+pub fn untrack(app: &tauri::AppHandle, id: &str) {
+    let Some(duplicates) = app.try_state::<Duplicates>() else {
+        return;
+    };
+    let mut inner = duplicates.inner.lock().unwrap();
+    if inner.index.queued.remove(id).is_some() {
+        save_index(app, &inner.index);
+    }
+}
+
+/// Remember a finished upload.
+pub fn record(
+    app: &tauri::AppHandle,
+    id: &str,
+    fingerprint: Fingerprint,
+    file_name: &str,
+    team_id: &str,
+    target: UploadTarget,
+) {
+    let Some(duplicates) = app.try_state::<Duplicates>() else {
+        return;
+    };
+    let mut inner = duplicates.inner.lock().unwrap();
+    inner.index.queued.remove(id);
+
+    let entries = &mut inner.index.entries;
+    entries.retain(|entry| {
+        !(entry.sha256 == fingerprint.sha256 && entry.target == target && entry.team_id == team_id)
+    });
+    entries.push(IndexEntry {
+        sha256: fingerprint.sha256,
+        dhash: fingerprint.dhash,
+        file_name: file_name.to_string(),
+        team_id: team_id.to_string(),
+        target,
+        uploaded_at: unix_now(),
+    });
+    if entries.len() > MAX_ENTRIES {
+        let excess = entries.len() - MAX_ENTRIES;
+        entries.drain(..excess);
+    }
+
+    save_index(app, &inner.index);
+}
+
+fn save_index(app: &tauri::AppHandle, index: &DuplicateIndex) {
+    if let Ok(path) = store::data_file(app, INDEX_FILE)
+        && let Err(e) = store::save(&path, index)
+    {
+        eprintln!("🔁 Failed to save upload index: {}", e);
+    }
+}
+
+/// Check files against everything uploaded before, e.g. before the dashboard
+/// uploads them itself.
+#[tauri::command]
+pub async fn check_duplicates(
+    app: tauri::AppHandle,
+    paths: Vec<PathBuf>,
+    target: Option<UploadTarget>,
+) -> Result<Vec<DuplicateCheck>, String> {
+    let target = target.unwrap_or(UploadTarget::Inbox);
+    let team_id = upload::session_team(&app).ok_or("Not signed in")?;
+    let mut checks = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        let fingerprint = fingerprint(&path)
+            .await
+            .ok_or_else(|| format!("Failed to read {:?}", path))?;
+        let duplicate = find(&app, &fingerprint, &team_id, target);
+        let action = duplicate
+            .as_ref()
+            .map(|duplicate| action_for(&app, duplicate))
+            .unwrap_or(DuplicateAction::Allow);
+        checks.push(DuplicateCheck {
+            path,
+            duplicate,
+            action,
+        });
+    }
+
+    Ok(checks)
+}
+
+#[tauri::command]
+pub fn get_duplicate_settings(duplicates: tauri::State<'_, Duplicates>) -> DuplicateSettings {
+    duplicates.inner.lock().unwrap().settings.clone()
+}
+
+#[tauri::command]
+pub fn set_duplicate_settings(
+    app: tauri::AppHandle,
+    duplicates: tauri::State<'_, Duplicates>,
+    settings: DuplicateSettings,
+) -> Result<(), String> {
+    store::save(&store::config_file(&app, SETTINGS_FILE)?, &settings)?;
+    duplicates.inner.lock().unwrap().settings = settings;
+    Ok(())
+}
+
+/// Forget all uploads, e.g. after deleting Inbox items on purpose. Queued
+/// files are kept, they are still on their way.
+#[tauri::command]
+pub fn clear_upload_index(
+    app: tauri::AppHandle,
+    duplicates: tauri::State<'_, Duplicates>,
+) -> Result<(), String> {
+    let mut inner = duplicates.inner.lock().unwrap();
+    inner.index.entries.clear();
+    store::save(&store::data_file(&app, INDEX_FILE)?, &inner.index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `image` to a PNG of its own in the temp folder.
+    fn temp_image(name: &str, image: &image::GrayImage) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "midday-duplicates-test-{}-{}.png",
+            std::process::id(),
+            name
+        ));
+        image.save(&path).unwrap();
+        path
+    }
+
+    /// A receipt-like test image: dark bands of varying width on white.
+    fn bands(width: u32, height: u32) -> image::GrayImage {
+        image::GrayImage::from_fn(width, height, |x, y| {
+            let band = (y * 12 / height + x * 5 / width) % 3;
+            image::Luma([[250, 40, 140][band as usize]])
+        })
+    }
+
+    #[test]
+    fn hashes_content() {
+        let path = std::env::temp_dir().join(format!(
+            "midday-duplicates-test-{}-abc.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(sha256_file(&path.with_extension("missing")).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn resized_images_are_similar() {
+        let original = temp_image("original", &bands(900, 1200));
+        let resized = temp_image("resized", &bands(300, 400));
+        let original_hash = dhash(&original).unwrap();
+        let resized_hash = dhash(&resized).unwrap();
+
+        assert_eq!(dhash(&original).unwrap(), original_hash);
+        assert!(similar_distance(original_hash, resized_hash).is_some());
+
+        let _ = std::fs::remove_file(&original);
+        let _ = std::fs::remove_file(&resized);
+    }
+
+    #[test]
+    fn different_images_are_not_similar() {
+        let original = temp_image("bands", &bands(600, 800));
+        let inverted = {
+            let mut image = bands(600, 800);
+            image::imageops::invert(&mut image);
+            temp_image("inverted", &image)
+        };
+
+        assert_eq!(
+            similar_distance(dhash(&original).unwrap(), dhash(&inverted).unwrap()),
+            None
+        );
+
+        let _ = std::fs::remove_file(&original);
+        let _ = std::fs::remove_file(&inverted);
+    }
+
+    #[test]
+    fn similarity_threshold() {
+        assert_eq!(similar_distance(0, 0), Some(0));
+        assert_eq!(similar_distance(0, 0b11_1111), Some(SIMILAR_DISTANCE));
+        assert_eq!(similar_distance(0, 0b111_1111), None);
+        assert_eq!(similar_distance(u64::MAX, 0), None);
+    }
+}
@@ -0,0 +1,516 @@
+use mail_parser::{Message, MessageParser, MimeHeaders};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tauri::Manager;
+
+use crate::capture;
+use crate::download;
+use crate::merge;
+use crate::print;
+use crate::upload::{self, UploadSource, UploadTarget};
+
+/// Extracted attachments and rendered bodies live here until uploaded.
+const CACHE_DIR: &str = "email";
+
+/// Rendered bodies can't run scripts or load anything from the network, so
+/// opening one doesn't tell the sender and can't reach the app.
+const BODY_CSP: &str = "default-src 'none'; img-src data:; style-src 'unsafe-inline'";
+
+/// What zod's `.email()` accepts, which the Inbox API checks the sender with
+static EMAIL_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z0-9_'+\-.]*[A-Za-z0-9_+\-]@(?:[A-Za-z0-9][A-Za-z0-9\-]*\.)+[A-Za-z]{2,}$")
+        .unwrap()
+});
+
+/// Scripts and embedded documents, removed with their contents
+static UNSAFE_ELEMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<(?:script|iframe|object|embed|frame|frameset|applet|noscript|template)\b.*?</(?:script|iframe|object|embed|frame|frameset|applet|noscript|template)\s*>",
+    )
+    .unwrap()
+});
+
+/// Tags left over from unclosed unsafe elements, and tags that redirect or
+/// load other documents
+static UNSAFE_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?is)</?(?:script|iframe|object|embed|frame|frameset|applet|noscript|template|meta|link|base)\b(?:[^>"']|"[^"]*"|'[^']*')*>"#,
+    )
+    .unwrap()
+});
+
+static TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<[A-Za-z][^\s/>]*(?:[^>"']|"[^"]*"|'[^']*')*>"#).unwrap());
+
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([\s/]+)([^\s/>="']+)(?:\s*=\s*("[^"]*"|'[^']*'|[^\s>]+))?"#).unwrap()
+});
+
+static CHARACTER_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)&#(x[0-9a-f]+|[0-9]+);?|&(colon|tab|newline);").unwrap());
+
+/// Sender, subject and date of the email an upload was extracted from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailMetadata {
+    pub sender_name: Option<String>,
+    pub sender_email: Option<String>,
+    pub subject: Option<String>,
+    /// RFC 3339
+    pub date: Option<String>,
+}
+
+/// Files extracted from one email.
+struct Extracted {
+    metadata: EmailMetadata,
+    files: Vec<PathBuf>,
+    /// The rendered body as HTML, for emails without attachments
+    body: Option<PathBuf>,
+}
+
+pub fn is_email(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("eml"))
+}
+
+/// Queue the PDF and image attachments of `.eml` files, or the body of emails
+/// without any as a PDF. Returns the upload ids.
+pub async fn enqueue_emails(
+    app: &tauri::AppHandle,
+    paths: Vec<PathBuf>,
+    target: UploadTarget,
+    folder: Vec<String>,
+    source: UploadSource,
+) -> Vec<String> {
+    let dir = match app.path().app_cache_dir() {
+        Ok(dir) => dir.join(CACHE_DIR),
+        Err(e) => {
+            eprintln!("📧 Failed to resolve cache directory: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut ids = Vec::new();
+    for path in paths {
+        let extract_dir = dir.clone();
+        let email_path = path.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            std::fs::create_dir_all(&extract_dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", extract_dir, e))?;
+            extract(&email_path, &extract_dir)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+        let mut extracted = match result {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                eprintln!("📧 Failed to read {:?}: {}", path, e);
+                capture::notify(app, "Couldn't read email", &e);
+                continue;
+            }
+        };
+
+        if let Some(body) = extracted.body {
+            extracted.files.push(body_to_pdf(app, body).await);
+        }
+
+        println!(
+            "📧 Extracted {} file(s) from {:?}",
+            extracted.files.len(),
+            path
+        );
+        for file in extracted.files {
+            ids.extend(upload::enqueue_from_email(
+                app,
+                file,
+                target,
+                folder.clone(),
+                source,
+                extracted.metadata.clone(),
+            ));
+        }
+    }
+    ids
+}
+
+/// The PDF rendered from an email body, or the HTML itself when it can't be
+/// rendered.
+async fn body_to_pdf(app: &tauri::AppHandle, html_path: PathBuf) -> PathBuf {
+    let pdf_path = download::unique_path(
+        html_path.parent().unwrap_or(Path::new(".")),
+        &format!(
+            "{}.pdf",
+            html_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "email".to_string())
+        ),
+    );
+
+    let result = match tokio::fs::read_to_string(&html_path).await {
+        Ok(html) => print::html_to_pdf(app, &html, &pdf_path).await,
+        Err(e) => Err(format!("Failed to read {:?}: {}", html_path, e)),
+    };
+    match result {
+        Ok(()) => {
+            let _ = tokio::fs::remove_file(&html_path).await;
+            pdf_path
+        }
+        Err(e) => {
+            eprintln!("📧 Uploading the email body as HTML: {}", e);
+            let _ = tokio::fs::remove_file(&pdf_path).await;
+            html_path
+        }
+    }
+}
+
+fn extract(path: &Path, dir: &Path) -> Result<Extracted, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let message = MessageParser::default()
+        .parse(&bytes)
+        .ok_or_else(|| format!("{:?} is not an email", path))?;
+
+    let metadata = metadata(&message);
+    let mut files = Vec::new();
+    let mut body = None;
+    let result = write_attachments(&message, dir, &mut files).and_then(|()| {
+        if !files.is_empty() {
+            return Ok(());
+        }
+
+        // Order confirmations and e-receipts often are the email itself
+        let stem = metadata
+            .subject
+            .clone()
+            .or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            })
+            .unwrap_or_else(|| "email".to_string());
+        let output =
+            download::unique_path(dir, &download::safe_file_name(&format!("{}.html", stem)));
+        std::fs::write(&output, render_body(&message, &metadata))
+            .map_err(|e| format!("Failed to write {:?}: {}", output, e))?;
+        body = Some(output);
+        Ok(())
+    });
+
+    // Don't leave half an email behind
+    if let Err(e) = result {
+        for file in files {
+            let _ = std::fs::remove_file(file);
+        }
+        return Err(e);
+    }
+
+    Ok(Extracted {
+        metadata,
+        files,
+        body,
+    })
+}
+
+fn metadata(message: &Message) -> EmailMetadata {
+    let sender = message.from().and_then(|from| from.first());
+    EmailMetadata {
+        sender_name: sender.and_then(|sender| sender.name()).map(str::to_string),
+        // Kept only when the Inbox API accepts them, it rejects the whole
+        // upload otherwise
+        sender_email: sender
+            .and_then(|sender| sender.address())
+            .map(str::trim)
+            .filter(|address| is_email_address(address))
+            .map(str::to_string),
+        subject: message
+            .subject()
+            .map(|subject| subject.trim().to_string())
+            .filter(|subject| !subject.is_empty()),
+        date: message.date().and_then(rfc3339_date),
+    }
+}
+
+fn is_email_address(address: &str) -> bool {
+    !address.starts_with('.') && !address.contains("..") && EMAIL_ADDRESS.is_match(address)
+}
+
+/// The `Date` header as RFC 3339, if it is an actual date.
+fn rfc3339_date(date: &mail_parser::DateTime) -> Option<String> {
+    use chrono::Datelike;
+
+    if date.second > 59 {
+        return None;
+    }
+    let parsed = chrono::DateTime::parse_from_rfc3339(&date.to_rfc3339()).ok()?;
+    (parsed.year() <= 9999).then(|| parsed.to_rfc3339())
+}
+
+/// Write PDF and image attachments to `dir`, including those of forwarded
+/// emails attached as `message/rfc822`.
+fn write_attachments(
+    message: &Message,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    let html: String = message
+        .html_bodies()
+        .filter_map(|part| part.text_contents())
+        .collect();
+
+    for (index, part) in message.attachments().enumerate() {
+        if let Some(nested) = part.message() {
+            write_attachments(nested, dir, files)?;
+            continue;
+        }
+
+        let content_type = part
+            .content_type()
+            .map(|content_type| {
+                format!(
+                    "{}/{}",
+                    content_type.ctype(),
+                    content_type.subtype().unwrap_or_default()
+                )
+                .to_lowercase()
+            })
+            .unwrap_or_default();
+        let name = match part.attachment_name() {
+            Some(name) => name.to_string(),
+            None => match extension_for(&content_type) {
+                Some(extension) => format!("attachment-{}.{}", index + 1, extension),
+                None => continue,
+            },
+        };
+        // The upload's content type comes from the extension
+        let name = match extension_for(&content_type) {
+            Some(extension) if Path::new(&name).extension().is_none() => {
+                format!("{}.{}", name, extension)
+            }
+            _ => name,
+        };
+
+        let is_pdf = content_type == "application/pdf" || name.to_lowercase().ends_with(".pdf");
+        let is_image = content_type.starts_with("image/") || merge::is_image(Path::new(&name));
+        if !is_pdf && !is_image {
+            continue;
+        }
+
+        // Logos and signatures shown inside the body aren't receipts
+        if is_image
+            && let Some(id) = part.content_id()
+            && html.contains(&format!("cid:{}", id.trim_matches(['<', '>'])))
+        {
+            continue;
+        }
+
+        let output = download::unique_path(dir, &download::safe_file_name(&name));
+        std::fs::write(&output, part.contents())
+            .map_err(|e| format!("Failed to write {:?}: {}", output, e))?;
+        files.push(output);
+    }
+
+    Ok(())
+}
+
+fn extension_for(content_type: &str) -> Option<&'static str> {
+    match content_type {
+        "application/pdf" => Some("pdf"),
+        "image/jpeg" | "image/jpg" | "image/pjpeg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/heic" => Some("heic"),
+        "image/heif" => Some("heif"),
+        "image/tiff" => Some("tiff"),
+        "image/bmp" => Some("bmp"),
+        _ => None,
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// What is between `<body>` and `</body>`, or everything for fragments.
+fn body_contents(html: &str) -> &str {
+    let lower = html.to_ascii_lowercase();
+    let start = lower
+        .find("<body")
+        .and_then(|start| lower[start..].find('>').map(|end| start + end + 1))
+        .unwrap_or(0);
+    let end = lower[start..]
+        .rfind("</body")
+        .map(|end| start + end)
+        .unwrap_or(html.len());
+    &html[start..end]
+}
+
+/// Remove scripts, embedded documents, event handlers and `javascript:` URLs
+/// from an email's HTML. The CSP of the rendered document backs this up.
+fn sanitize_html(html: &str) -> String {
+    let html = UNSAFE_ELEMENT.replace_all(html, "");
+    let html = UNSAFE_TAG.replace_all(&html, "");
+    TAG.replace_all(&html, |tag: &regex::Captures| {
+        ATTRIBUTE
+            .replace_all(&tag[0], |attribute: &regex::Captures| {
+                let name = attribute[2].to_ascii_lowercase();
+                let value = attribute.get(3).map_or("", |value| value.as_str());
+                if name.starts_with("on") || name == "srcdoc" || is_script_url(value) {
+                    String::new()
+                } else {
+                    attribute[0].to_string()
+                }
+            })
+            .into_owned()
+    })
+    .into_owned()
+}
+
+/// Whether an attribute value is a URL that runs code, however it is
+/// obfuscated with character references, whitespace or case.
+fn is_script_url(value: &str) -> bool {
+    let value = value.trim_matches(['"', '\'']);
+    let decoded = CHARACTER_REFERENCE.replace_all(value, |reference: &regex::Captures| {
+        let character = match (reference.get(1), reference.get(2)) {
+            (Some(number), _) => {
+                let number = number.as_str();
+                match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => number.parse().ok(),
+                }
+                .and_then(char::from_u32)
+            }
+            (None, Some(name)) => match name.as_str().to_ascii_lowercase().as_str() {
+                "colon" => Some(':'),
+                "tab" => Some('\t'),
+                _ => Some('\n'),
+            },
+            (None, None) => None,
+        };
+        character.map(String::from).unwrap_or_default()
+    });
+    let url: String = decoded
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    ["javascript:", "vbscript:", "data:text/html"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+/// A standalone UTF-8 document with the sender, subject and date on top and
+/// the HTML body, or the plain text one, below.
+fn render_body(message: &Message, metadata: &EmailMetadata) -> String {
+    let sender = match (&metadata.sender_name, &metadata.sender_email) {
+        (Some(name), Some(email)) => format!("{} <{}>", name, email),
+        (Some(name), None) => name.clone(),
+        (None, Some(email)) => email.clone(),
+        (None, None) => String::new(),
+    };
+    let date = message
+        .date()
+        .map(|date| date.to_rfc822())
+        .unwrap_or_default();
+    let subject = metadata.subject.clone().unwrap_or_default();
+
+    let body = match message.body_html(0) {
+        Some(html) => sanitize_html(body_contents(&html)),
+        None => format!(
+            "<pre style=\"white-space: pre-wrap; font-family: inherit\">{}</pre>",
+            escape_html(&message.body_text(0).unwrap_or_default())
+        ),
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\">\
+         <meta http-equiv=\"Content-Security-Policy\" content=\"{csp}\">\
+         <title>{subject}</title></head>\n<body>\n\
+         <table style=\"font-family: sans-serif; font-size: 13px; margin-bottom: 16px\">\n\
+         <tr><td><b>From</b></td><td>{sender}</td></tr>\n\
+         <tr><td><b>Subject</b></td><td>{subject}</td></tr>\n\
+         <tr><td><b>Date</b></td><td>{date}</td></tr>\n\
+         </table>\n<hr>\n{body}\n</body>\n</html>\n",
+        csp = BODY_CSP,
+        subject = escape_html(&subject),
+        sender = escape_html(&sender),
+        date = escape_html(&date),
+        body = body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(headers: &str) -> EmailMetadata {
+        let email = format!("{}\r\nContent-Type: text/plain\r\n\r\nThanks!\r\n", headers);
+        metadata(&MessageParser::default().parse(email.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn removes_scripts_and_embeds() {
+        let html = sanitize_html(
+            "<p>Total</p><script>window.__TAURI__.core.invoke('save_url')</script>\
+             <SCRIPT src=https://tracker.example/t.js></SCRIPT>\
+             <iframe src=\"https://example.com\"></iframe><object data=x></object>\
+             <embed src=x><meta http-equiv=refresh content=\"0;url=https://example.com\">\
+             <script>unclosed",
+        );
+        assert_eq!(html, "<p>Total</p>unclosed");
+    }
+
+    #[test]
+    fn removes_event_handlers_and_script_urls() {
+        assert_eq!(
+            sanitize_html(r#"<img src="data:image/png;base64,AA" onerror="alert(1)" alt=x>"#),
+            r#"<img src="data:image/png;base64,AA" alt=x>"#
+        );
+        assert_eq!(
+            sanitize_html(
+                "<svg/onload=alert(1)><a href='https://midday.ai' title=\"a onclick=b\">"
+            ),
+            "<svg><a href='https://midday.ai' title=\"a onclick=b\">"
+        );
+        assert_eq!(
+            sanitize_html(r#"<a href=" java&#x73;cript&colon;alert(1)">Pay</a>"#),
+            "<a>Pay</a>"
+        );
+        assert_eq!(
+            sanitize_html("<a href='JaVaScRiPt:alert(1)'>Pay</a> 2 < 3 onclick=fine"),
+            "<a>Pay</a> 2 < 3 onclick=fine"
+        );
+    }
+
+    #[test]
+    fn keeps_valid_metadata() {
+        assert_eq!(
+            parse(
+                "From: Acme Billing <billing@acme.com>\r\nSubject: Receipt\r\nDate: Tue, 5 Mar 2024 10:30:00 +0100"
+            ),
+            EmailMetadata {
+                sender_name: Some("Acme Billing".to_string()),
+                sender_email: Some("billing@acme.com".to_string()),
+                subject: Some("Receipt".to_string()),
+                date: Some("2024-03-05T10:30:00+01:00".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn drops_metadata_the_api_rejects() {
+        let metadata = parse("From: billing@localhost\r\nDate: Tue, 45 Mar 2024 10:30:00 +0100");
+        assert_eq!(metadata.sender_email, None);
+        assert_eq!(metadata.date, None);
+
+        assert_eq!(parse("From: a..b@acme.com").sender_email, None);
+        assert_eq!(parse("Date: Tue, 5 Mar 2024 10:30:61 +0000").date, None);
+    }
+}
@@ -0,0 +1,378 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+use zip::write::SimpleFileOptions;
+
+use crate::download;
+use crate::save;
+use crate::upload::next_upload_id;
+
+/// How many documents are downloaded at the same time.
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+const MANIFEST_NAME: &str = "manifest.csv";
+
+/// A document to include in the archive.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportItem {
+    /// Transaction, inbox or invoice id, written to the manifest
+    pub id: String,
+    pub url: String,
+    /// Name inside the archive
+    pub file_name: String,
+    #[serde(default)]
+    pub original_name: Option<String>,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub amount: Option<f64>,
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportProgress<'a> {
+    id: &'a str,
+    completed: usize,
+    failed: usize,
+    total: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportFailure {
+    id: String,
+    file_name: String,
+    error: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportFinished {
+    id: String,
+    path: Option<PathBuf>,
+    exported: usize,
+    failed: Vec<ExportFailure>,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// Running exports, so they can be cancelled.
+#[derive(Default)]
+pub struct ExportState {
+    running: Mutex<HashMap<String, CancellationToken>>,
+}
+
+/// A downloaded document waiting to be added to the archive.
+struct Downloaded {
+    name: String,
+    temp_path: PathBuf,
+}
+
+/// Give every entry a unique name inside the archive.
+fn unique_entry_names(items: &[ExportItem]) -> Vec<String> {
+    let mut used = HashSet::new();
+    used.insert(MANIFEST_NAME.to_string());
+
+    items
+        .iter()
+        .map(|item| {
+            let name = download::safe_file_name(&item.file_name);
+            let path = Path::new(&name);
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let extension = path
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+
+            let mut candidate = name.clone();
+            let mut n = 1;
+            while !used.insert(candidate.to_lowercase()) {
+                candidate = format!("{} ({}){}", stem, n, extension);
+                n += 1;
+            }
+            candidate
+        })
+        .collect()
+}
+
+/// Export documents into a single ZIP chosen through the save dialog.
+/// Returns the export id, or `None` when the dialog was cancelled. Progress is
+/// reported through `export-progress` and the result through `export-finished`.
+#[tauri::command]
+pub async fn bulk_export(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    webview: tauri::Webview,
+    state: tauri::State<'_, ExportState>,
+    items: Vec<ExportItem>,
+    archive_name: String,
+) -> Result<Option<String>, String> {
+    if items.is_empty() {
+        return Err("Nothing to export".to_string());
+    }
+
+    let archive_name = if archive_name.to_lowercase().ends_with(".zip") {
+        archive_name
+    } else {
+        format!("{}.zip", archive_name)
+    };
+
+    let Some(path) = save::choose_path(&app, &window, &archive_name, true).await? else {
+        println!("🗜️ Export cancelled");
+        return Ok(None);
+    };
+
+    let id = next_upload_id();
+    let token = CancellationToken::new();
+    state
+        .running
+        .lock()
+        .unwrap()
+        .insert(id.clone(), token.clone());
+
+    println!("🗜️ Exporting {} documents to {:?}", items.len(), path);
+
+    let export_id = id.clone();
+    tauri::async_runtime::spawn(async move {
+        let finished = run_export(&app, &webview, &export_id, items, &path, &token).await;
+        if let Some(state) = app.try_state::<ExportState>() {
+            state.running.lock().unwrap().remove(&export_id);
+        }
+        let _ = app.emit("export-finished", finished);
+    });
+
+    Ok(Some(id))
+}
+
+async fn run_export(
+    app: &tauri::AppHandle,
+    webview: &tauri::Webview,
+    id: &str,
+    items: Vec<ExportItem>,
+    path: &Path,
+    token: &CancellationToken,
+) -> ExportFinished {
+    let total = items.len();
+    let names = unique_entry_names(&items);
+    let temp_dir = std::env::temp_dir().join(format!("midday-export-{}", id));
+    let archive_temp_path = save::temp_path_for(path);
+
+    let mut finished = ExportFinished {
+        id: id.to_string(),
+        path: None,
+        exported: 0,
+        failed: Vec::new(),
+        cancelled: false,
+        error: None,
+    };
+
+    if let Err(e) = tokio::fs::create_dir_all(&temp_dir).await {
+        finished.error = Some(format!("Failed to create {:?}: {}", temp_dir, e));
+        return finished;
+    }
+
+    // The zip writer is synchronous, it runs on a blocking thread and
+    // receives documents as they finish downloading
+    let (sender, receiver) = std::sync::mpsc::channel::<Downloaded>();
+    let manifest = build_manifest(&items, &names);
+    let writer_path = archive_temp_path.clone();
+    let writer = tauri::async_runtime::spawn_blocking(move || {
+        write_archive(&writer_path, receiver, manifest)
+    });
+
+    let downloads = futures_util::stream::iter(items.iter().zip(names.iter()).enumerate())
+        .map(|(index, (item, name))| {
+            let temp_path = temp_dir.join(format!("{}", index));
+            async move {
+                let result = fetch_to_file(webview, &item.url, &temp_path).await;
+                (item, name.clone(), temp_path, result)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_DOWNLOADS);
+    tokio::pin!(downloads);
+
+    loop {
+        let next = tokio::select! {
+            next = downloads.next() => next,
+            _ = token.cancelled() => {
+                finished.cancelled = true;
+                break;
+            }
+        };
+        let Some((item, name, temp_path, result)) = next else {
+            break;
+        };
+
+        match result {
+            Ok(()) => {
+                finished.exported += 1;
+                let _ = sender.send(Downloaded { name, temp_path });
+            }
+            Err(e) => {
+                eprintln!("🗜️ Failed to export {}: {}", item.file_name, e);
+                finished.failed.push(ExportFailure {
+                    id: item.id.clone(),
+                    file_name: name,
+                    error: e,
+                });
+            }
+        }
+
+        let _ = app.emit(
+            "export-progress",
+            ExportProgress {
+                id,
+                completed: finished.exported,
+                failed: finished.failed.len(),
+                total,
+            },
+        );
+    }
+
+    // Closing the channel lets the writer finish the archive
+    drop(sender);
+    let write_result = writer
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+    let _ = tokio::fs::remove_dir_all(&temp_dir).await;
+
+    if finished.cancelled {
+        println!("🗜️ Export {} cancelled", id);
+        let _ = tokio::fs::remove_file(&archive_temp_path).await;
+        return finished;
+    }
+
+    let result = match write_result {
+        Ok(()) => tokio::fs::rename(&archive_temp_path, path)
+            .await
+            .map_err(|e| format!("Failed to save {:?}: {}", path, e)),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => {
+            println!(
+                "🗜️ Exported {} of {} documents to {:?}",
+                finished.exported, total, path
+            );
+            finished.path = Some(path.to_path_buf());
+        }
+        Err(e) => {
+            eprintln!("🗜️ Export failed: {}", e);
+            let _ = tokio::fs::remove_file(&archive_temp_path).await;
+            finished.error = Some(e);
+        }
+    }
+
+    finished
+}
+
+async fn fetch_to_file(webview: &tauri::Webview, url: &str, path: &Path) -> Result<(), String> {
+    let url = tauri::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let response = download::authenticated_get(webview, &url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Download failed: {}", e))?;
+
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write: {}", e))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write: {}", e))
+}
+
+/// Manifest listing every requested document, so failed ones are visible too.
+fn build_manifest(items: &[ExportItem], names: &[String]) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let _ = writer.write_record([
+        "id",
+        "date",
+        "amount",
+        "currency",
+        "original_name",
+        "file_name",
+    ]);
+
+    for (item, name) in items.iter().zip(names) {
+        let amount = item
+            .amount
+            .map(|amount| amount.to_string())
+            .unwrap_or_default();
+        let _ = writer.write_record([
+            item.id.as_str(),
+            item.date.as_deref().unwrap_or_default(),
+            amount.as_str(),
+            item.currency.as_deref().unwrap_or_default(),
+            item.original_name.as_deref().unwrap_or(&item.file_name),
+            name.as_str(),
+        ]);
+    }
+
+    writer.into_inner().unwrap_or_default()
+}
+
+fn write_archive(
+    path: &Path,
+    receiver: std::sync::mpsc::Receiver<Downloaded>,
+    manifest: Vec<u8>,
+) -> Result<(), String> {
+    let file =
+        std::fs::File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for downloaded in receiver {
+        zip.start_file(downloaded.name.as_str(), options)
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        let mut source = std::fs::File::open(&downloaded.temp_path)
+            .map_err(|e| format!("Failed to read {:?}: {}", downloaded.temp_path, e))?;
+        std::io::copy(&mut source, &mut zip)
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        let _ = std::fs::remove_file(&downloaded.temp_path);
+    }
+
+    zip.start_file(MANIFEST_NAME, options)
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    zip.write_all(&manifest)
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    zip.finish()
+        .map_err(|e| format!("Failed to write archive: {}", e))?
+        .flush()
+        .map_err(|e| format!("Failed to write archive: {}", e))
+}
+
+#[tauri::command]
+pub fn cancel_export(state: tauri::State<'_, ExportState>, id: String) -> Result<(), String> {
+    let token = state
+        .running
+        .lock()
+        .unwrap()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| format!("Export {} is not running", id))?;
+    token.cancel();
+    Ok(())
+}
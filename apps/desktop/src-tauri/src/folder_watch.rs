@@ -0,0 +1,600 @@
+use globset::{Glob, GlobMatcher};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::menu::MenuItem;
+use tauri::{Emitter, Listener, Manager};
+use tokio::sync::mpsc;
+
+use crate::duplicates::{self, sha256_file};
+use crate::store;
+use crate::upload::{self, UploadSource, UploadTarget};
+
+const CONFIG_FILE: &str = "watched-folders.json";
+const STATE_FILE: &str = "watch-state.json";
+
+/// How long a file's size and modification time must stay unchanged before it
+/// is considered completely written.
+const SETTLE_TIME: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Used when a folder doesn't restrict file types.
+const DEFAULT_FILE_TYPES: &[&str] = &["pdf", "jpg", "jpeg", "png", "heic", "webp"];
+
+/// Suffixes browsers and editors use while a file is still being written.
+const PARTIAL_SUFFIXES: &[&str] = &[".crdownload", ".part", ".partial", ".download", ".tmp"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedFolder {
+    pub id: String,
+    pub path: PathBuf,
+    /// Glob matched against the path relative to the folder, e.g. `*.pdf` or `**/*`
+    pub pattern: String,
+    /// Lowercase extensions without the dot. Empty means the default receipt types.
+    pub file_types: Vec<String>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedFolderInput {
+    pub path: PathBuf,
+    pub pattern: Option<String>,
+    pub file_types: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+/// Uploaded and queued files are in the duplicate index, which is keyed by
+/// content so renamed or copied files are not uploaded twice.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchState {
+    /// Files modified after this (unix seconds) are picked up on startup,
+    /// so receipts saved while Midday was closed are not missed.
+    last_seen_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchStatus {
+    pub folders: usize,
+    /// Files waiting in the upload queue, from any source
+    pub pending_uploads: usize,
+    pub uploaded_count: usize,
+    pub last_error: Option<String>,
+}
+
+impl WatchStatus {
+    fn tray_label(&self) -> String {
+        match self.pending_uploads {
+            0 => {}
+            1 => return "Uploading 1 file…".to_string(),
+            n => return format!("Uploading {} files…", n),
+        }
+        if let Some(error) = &self.last_error {
+            return format!("Folder watch: {}", error);
+        }
+        match self.folders {
+            0 => "Not watching any folders".to_string(),
+            1 => "Watching 1 folder".to_string(),
+            n => format!("Watching {} folders", n),
+        }
+    }
+}
+
+struct CompiledFolder {
+    path: PathBuf,
+    matcher: GlobMatcher,
+    file_types: Vec<String>,
+    /// Only descend into subfolders when the pattern can match them
+    recursive: bool,
+}
+
+struct WatchInner {
+    folders: Vec<WatchedFolder>,
+    compiled: Vec<CompiledFolder>,
+    // Dropping the watcher stops it, so it lives here
+    watcher: Option<RecommendedWatcher>,
+    state: WatchState,
+    /// Upload ids of files queued by the watcher, for the uploaded count
+    queued: HashSet<String>,
+    status: WatchStatus,
+}
+
+/// Watches local folders and uploads new receipts to Inbox. Runs entirely in
+/// Rust so it keeps working while all windows are hidden.
+pub struct FolderWatch {
+    inner: Mutex<WatchInner>,
+    events: mpsc::UnboundedSender<PathBuf>,
+}
+
+/// Tray menu item showing the watch status.
+pub struct WatchTrayItem(pub MenuItem<tauri::Wry>);
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn compile(folder: &WatchedFolder) -> Result<CompiledFolder, String> {
+    let matcher = Glob::new(&folder.pattern)
+        .map_err(|e| format!("Invalid pattern {}: {}", folder.pattern, e))?
+        .compile_matcher();
+
+    let file_types = if folder.file_types.is_empty() {
+        DEFAULT_FILE_TYPES.iter().map(|t| t.to_string()).collect()
+    } else {
+        folder
+            .file_types
+            .iter()
+            .map(|t| t.trim_start_matches('.').to_lowercase())
+            .collect()
+    };
+
+    Ok(CompiledFolder {
+        path: folder
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| folder.path.clone()),
+        matcher,
+        file_types,
+        recursive: folder.pattern.contains('/'),
+    })
+}
+
+fn is_partial_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return true;
+    };
+    let name = name.to_lowercase();
+
+    name.starts_with('.')
+        || name.starts_with("~$")
+        || PARTIAL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+impl WatchInner {
+    /// Whether `path` belongs to a watched folder and matches its pattern and types.
+    fn matches(&self, path: &Path) -> bool {
+        if is_partial_file(path) {
+            return false;
+        }
+
+        // Watched folders are stored canonicalized (e.g. /private/var on macOS)
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+
+        self.compiled.iter().any(|folder| {
+            let Ok(relative) = path.strip_prefix(&folder.path) else {
+                return false;
+            };
+            folder.file_types.contains(&extension) && folder.matcher.is_match(relative)
+        })
+    }
+}
+
+/// Size and modification time, used to tell when a file stopped changing.
+fn file_signature(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+pub fn setup(app: &tauri::AppHandle) -> Result<(), String> {
+    let folders: Vec<WatchedFolder> = store::load(&store::config_file(app, CONFIG_FILE)?);
+    let state: WatchState = store::load(&store::data_file(app, STATE_FILE)?);
+    let last_seen_at = state.last_seen_at;
+
+    let (events, receiver) = mpsc::unbounded_channel();
+    app.manage(FolderWatch {
+        inner: Mutex::new(WatchInner {
+            folders,
+            compiled: Vec::new(),
+            watcher: None,
+            state,
+            queued: HashSet::new(),
+            status: WatchStatus::default(),
+        }),
+        events,
+    });
+
+    rewatch(app)?;
+
+    // Keep the tray label in sync with the upload queue
+    let app_handle = app.clone();
+    app.listen_any("uploads-changed", move |_| publish_status(&app_handle));
+
+    let app_handle = app.clone();
+    app.listen_any("upload-finished", move |event| {
+        if let Some(id) = event_upload_id(event.payload()) {
+            upload_done(&app_handle, &id, true);
+        }
+    });
+    // Skipped duplicates are in storage already
+    let app_handle = app.clone();
+    app.listen_any("upload-duplicate", move |event| {
+        let payload: serde_json::Value = serde_json::from_str(event.payload()).unwrap_or_default();
+        if payload["skipped"].as_bool() == Some(true)
+            && let Some(id) = payload["id"].as_str()
+        {
+            upload_done(&app_handle, id, true);
+        }
+    });
+    let app_handle = app.clone();
+    app.listen_any("upload-cancelled", move |event| {
+        if let Ok(id) = serde_json::from_str::<String>(event.payload()) {
+            upload_done(&app_handle, &id, false);
+        }
+    });
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        catch_up(&app_handle, last_seen_at);
+        settle_loop(app_handle, receiver).await;
+    });
+
+    Ok(())
+}
+
+/// The watch state, taken from the index older versions kept on first start.
+fn event_upload_id(payload: &str) -> Option<String> {
+    let payload: serde_json::Value = serde_json::from_str(payload).ok()?;
+    payload["id"].as_str().map(str::to_string)
+}
+
+/// Recreate the file system watcher from the current folder list.
+fn rewatch(app: &tauri::AppHandle) -> Result<(), String> {
+    let watch = app.state::<FolderWatch>();
+    let mut inner = watch.inner.lock().unwrap();
+
+    inner.watcher = None;
+    inner.compiled.clear();
+
+    let events = watch.events.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result {
+            for path in event.paths {
+                let _ = events.send(path);
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to start folder watcher: {}", e))?;
+
+    let mut errors = Vec::new();
+    let folders = inner.folders.clone();
+    for folder in folders.iter().filter(|folder| folder.enabled) {
+        let compiled = match compile(folder) {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        let mode = if compiled.recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+
+        match watcher.watch(&folder.path, mode) {
+            Ok(_) => {
+                println!("👀 Watching {:?} for {}", folder.path, folder.pattern);
+                inner.compiled.push(compiled);
+            }
+            Err(e) => errors.push(format!("Can't watch {}: {}", folder.path.display(), e)),
+        }
+    }
+
+    inner.watcher = Some(watcher);
+    inner.status.folders = inner.compiled.len();
+    inner.status.last_error = errors.into_iter().next();
+    drop(inner);
+
+    publish_status(app);
+    Ok(())
+}
+
+/// Queue files modified since the app last looked at the folders.
+fn catch_up(app: &tauri::AppHandle, last_seen_at: u64) {
+    if last_seen_at == 0 {
+        return;
+    }
+
+    let watch = app.state::<FolderWatch>();
+    let folders: Vec<(PathBuf, bool)> = {
+        let inner = watch.inner.lock().unwrap();
+        inner
+            .compiled
+            .iter()
+            .map(|folder| (folder.path.clone(), folder.recursive))
+            .collect()
+    };
+
+    let since = UNIX_EPOCH + Duration::from_secs(last_seen_at);
+    for (folder, recursive) in folders {
+        for path in walk(&folder, recursive) {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified());
+            if matches!(modified, Ok(modified) if modified > since) {
+                let _ = watch.events.send(path);
+            }
+        }
+    }
+}
+
+fn walk(dir: &Path, recursive: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() && recursive => dirs.push(path),
+                Ok(file_type) if file_type.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+    }
+
+    files
+}
+
+struct PendingFile {
+    signature: Option<(u64, SystemTime)>,
+    changed_at: Instant,
+}
+
+/// Wait for files to stop changing before uploading them, so half-written
+/// downloads and scans are never sent.
+async fn settle_loop(app: tauri::AppHandle, mut receiver: mpsc::UnboundedReceiver<PathBuf>) {
+    let mut pending: HashMap<PathBuf, PendingFile> = HashMap::new();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            Some(path) = receiver.recv() => {
+                let matches = app.state::<FolderWatch>().inner.lock().unwrap().matches(&path);
+                if matches {
+                    pending.insert(path.clone(), PendingFile {
+                        signature: file_signature(&path),
+                        changed_at: Instant::now(),
+                    });
+                }
+            }
+            _ = interval.tick() => {
+                let mut ready = Vec::new();
+                pending.retain(|path, file| {
+                    let signature = file_signature(path);
+                    if signature.is_none() {
+                        // Deleted or renamed away
+                        return false;
+                    }
+                    if signature != file.signature {
+                        file.signature = signature;
+                        file.changed_at = Instant::now();
+                        return true;
+                    }
+                    if file.changed_at.elapsed() >= SETTLE_TIME {
+                        ready.push(path.clone());
+                        return false;
+                    }
+                    true
+                });
+
+                for path in ready {
+                    process_file(&app, &path).await;
+                }
+
+                if pending.is_empty() {
+                    mark_seen(&app);
+                }
+            }
+        }
+    }
+}
+
+fn mark_seen(app: &tauri::AppHandle) {
+    let watch = app.state::<FolderWatch>();
+    let mut inner = watch.inner.lock().unwrap();
+    let now = unix_now();
+
+    // Only touch the file every minute or so
+    if now.saturating_sub(inner.state.last_seen_at) >= 60 {
+        inner.state.last_seen_at = now;
+        if let Ok(path) = store::data_file(app, STATE_FILE) {
+            let _ = store::save(&path, &inner.state);
+        }
+    }
+}
+
+async fn process_file(app: &tauri::AppHandle, path: &Path) {
+    let hash_path = path.to_path_buf();
+    let hash = match tauri::async_runtime::spawn_blocking(move || sha256_file(&hash_path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result)
+    {
+        Ok(hash) => hash,
+        Err(e) => {
+            eprintln!("👀 Failed to hash {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let team_id = upload::session_team(app);
+    if duplicates::is_known(app, &hash, team_id.as_deref(), UploadTarget::Inbox) {
+        println!("👀 Already uploaded or queued {}, skipping", file_name);
+        return;
+    }
+
+    // The queue emits events our own listeners handle, so the lock can't be
+    // held while enqueueing
+    let ids = upload::enqueue(
+        app,
+        vec![path.to_path_buf()],
+        UploadTarget::Inbox,
+        Vec::new(),
+        UploadSource::Watch,
+    );
+    for id in &ids {
+        duplicates::track(app, id, &hash, UploadTarget::Inbox);
+    }
+    app.state::<FolderWatch>()
+        .inner
+        .lock()
+        .unwrap()
+        .queued
+        .extend(ids);
+}
+
+/// Count a queued file once it is in storage, or forget it when the upload
+/// was cancelled.
+fn upload_done(app: &tauri::AppHandle, id: &str, uploaded: bool) {
+    let Some(watch) = app.try_state::<FolderWatch>() else {
+        return;
+    };
+    let mut inner = watch.inner.lock().unwrap();
+    if inner.queued.remove(id) && uploaded {
+        inner.status.uploaded_count += 1;
+    }
+}
+
+fn publish_status(app: &tauri::AppHandle) {
+    let Some(watch) = app.try_state::<FolderWatch>() else {
+        return;
+    };
+    // Read before locking, the queue emits events our listeners handle while
+    // holding its own lock
+    let pending_uploads = upload::summary(app).pending;
+    let status = {
+        let mut inner = watch.inner.lock().unwrap();
+        inner.status.pending_uploads = pending_uploads;
+        inner.status.clone()
+    };
+
+    if let Some(item) = app.try_state::<WatchTrayItem>() {
+        let _ = item.0.set_text(status.tray_label());
+    }
+    let _ = app.emit("watch-status", status);
+}
+
+fn save_folders(app: &tauri::AppHandle, folders: &[WatchedFolder]) -> Result<(), String> {
+    store::save(&store::config_file(app, CONFIG_FILE)?, &folders)
+}
+
+#[tauri::command]
+pub fn list_watched_folders(watch: tauri::State<'_, FolderWatch>) -> Vec<WatchedFolder> {
+    watch.inner.lock().unwrap().folders.clone()
+}
+
+#[tauri::command]
+pub fn watch_status(watch: tauri::State<'_, FolderWatch>) -> WatchStatus {
+    watch.inner.lock().unwrap().status.clone()
+}
+
+/// Start watching a folder. Files already in it are left alone, only new ones are uploaded.
+#[tauri::command]
+pub fn add_watched_folder(
+    app: tauri::AppHandle,
+    watch: tauri::State<'_, FolderWatch>,
+    folder: WatchedFolderInput,
+) -> Result<WatchedFolder, String> {
+    if !folder.path.is_dir() {
+        return Err(format!("{} is not a folder", folder.path.display()));
+    }
+
+    let folder = WatchedFolder {
+        id: upload::next_upload_id(),
+        path: folder.path,
+        pattern: folder.pattern.unwrap_or_else(|| "*".to_string()),
+        file_types: folder.file_types.unwrap_or_default(),
+        enabled: folder.enabled.unwrap_or(true),
+    };
+    compile(&folder)?;
+
+    let folders = {
+        let mut inner = watch.inner.lock().unwrap();
+        inner.folders.push(folder.clone());
+        inner.folders.clone()
+    };
+    save_folders(&app, &folders)?;
+    rewatch(&app)?;
+
+    Ok(folder)
+}
+
+#[tauri::command]
+pub fn update_watched_folder(
+    app: tauri::AppHandle,
+    watch: tauri::State<'_, FolderWatch>,
+    id: String,
+    folder: WatchedFolderInput,
+) -> Result<WatchedFolder, String> {
+    let (updated, folders) = {
+        let mut inner = watch.inner.lock().unwrap();
+        let existing = inner
+            .folders
+            .iter_mut()
+            .find(|existing| existing.id == id)
+            .ok_or_else(|| format!("Unknown watched folder: {}", id))?;
+
+        let mut updated = existing.clone();
+        updated.path = folder.path;
+        if let Some(pattern) = folder.pattern {
+            updated.pattern = pattern;
+        }
+        if let Some(file_types) = folder.file_types {
+            updated.file_types = file_types;
+        }
+        if let Some(enabled) = folder.enabled {
+            updated.enabled = enabled;
+        }
+        compile(&updated)?;
+
+        *existing = updated.clone();
+        (updated, inner.folders.clone())
+    };
+
+    save_folders(&app, &folders)?;
+    rewatch(&app)?;
+
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn remove_watched_folder(
+    app: tauri::AppHandle,
+    watch: tauri::State<'_, FolderWatch>,
+    id: String,
+) -> Result<(), String> {
+    let folders = {
+        let mut inner = watch.inner.lock().unwrap();
+        inner.folders.retain(|folder| folder.id != id);
+        inner.folders.clone()
+    };
+
+    save_folders(&app, &folders)?;
+    rewatch(&app)
+}
@@ -0,0 +1,180 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+/// Events older than this are dropped instead of being delivered once the
+/// frontend comes up (e.g. a link clicked while the login page was stuck).
+const STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// Events for dashboard components are kept longer, signing in first is fine.
+pub const LISTENER_STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+struct PendingEvent {
+    event: &'static str,
+    payload: serde_json::Value,
+    received_at: Instant,
+    stale_after: Duration,
+    /// Only delivered once the dashboard reported listening to `event`
+    needs_listener: bool,
+}
+
+#[derive(Default)]
+struct QueueState {
+    frontend_ready: bool,
+    /// Listeners dashboard components reported, by event. Counted, since a
+    /// remounting component may report the new listener before removing the
+    /// old one.
+    listening: HashMap<String, usize>,
+    pending: VecDeque<PendingEvent>,
+}
+
+impl QueueState {
+    fn can_deliver(&self, event: &str, needs_listener: bool) -> bool {
+        self.frontend_ready && (!needs_listener || self.listening.contains_key(event))
+    }
+
+    /// Take the pending events that can be delivered now, in order.
+    fn take_deliverable(&mut self) -> Vec<PendingEvent> {
+        let (deliverable, waiting): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|item| self.can_deliver(item.event, item.needs_listener));
+        self.pending = waiting;
+        deliverable.into()
+    }
+}
+
+/// Buffers events for the main window until the dashboard has registered its
+/// listeners and emitted `frontend-ready`.
+///
+/// Deep links that launch the app arrive before the webview has even loaded,
+/// so emitting them right away would lose them. Components like the calendar
+/// import only mount after signing in, their events also wait until the
+/// component reports listening through `frontend_listening`.
+#[derive(Default)]
+pub struct FrontendQueue {
+    state: Mutex<QueueState>,
+}
+
+impl FrontendQueue {
+    /// Emit `event` to the main window now if the frontend is listening,
+    /// otherwise keep it until `mark_ready` is called.
+    pub fn push(&self, app: &tauri::AppHandle, event: &'static str, payload: serde_json::Value) {
+        self.enqueue(app, event, payload, STALE_AFTER, false);
+    }
+
+    /// Like `push`, for an event a dashboard component listens to. It is kept
+    /// until the component reports listening, or dropped after `stale_after`.
+    pub fn push_to_listener(
+        &self,
+        app: &tauri::AppHandle,
+        event: &'static str,
+        payload: serde_json::Value,
+        stale_after: Duration,
+    ) {
+        self.enqueue(app, event, payload, stale_after, true);
+    }
+
+    fn enqueue(
+        &self,
+        app: &tauri::AppHandle,
+        event: &'static str,
+        payload: serde_json::Value,
+        stale_after: Duration,
+        needs_listener: bool,
+    ) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.can_deliver(event, needs_listener) {
+            drop(state);
+            deliver(app, event, payload);
+            return;
+        }
+
+        println!("📥 Frontend not listening, queueing {} event", event);
+        state.pending.push_back(PendingEvent {
+            event,
+            payload,
+            received_at: Instant::now(),
+            stale_after,
+            needs_listener,
+        });
+    }
+
+    /// Whether a dashboard component reported listening to `event`.
+    pub fn is_listening(&self, event: &str) -> bool {
+        self.state.lock().unwrap().can_deliver(event, true)
+    }
+
+    /// Called when the main window emits `frontend-ready`. Delivers everything
+    /// that is still fresh and not waiting for a component, in the order it
+    /// was received.
+    pub fn mark_ready(&self, app: &tauri::AppHandle) {
+        let pending = {
+            let mut state = self.state.lock().unwrap();
+            state.frontend_ready = true;
+            state.take_deliverable()
+        };
+        deliver_fresh(app, pending);
+    }
+
+    /// Called when the main window starts loading a new page, since the
+    /// listeners registered by the previous page are gone.
+    pub fn mark_not_ready(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.frontend_ready = false;
+        state.listening.clear();
+    }
+}
+
+fn deliver_fresh(app: &tauri::AppHandle, pending: Vec<PendingEvent>) {
+    for item in pending {
+        if item.received_at.elapsed() > item.stale_after {
+            println!("📥 Dropping stale {} event", item.event);
+            continue;
+        }
+        deliver(app, item.event, item.payload);
+    }
+}
+
+fn deliver(app: &tauri::AppHandle, event: &str, payload: serde_json::Value) {
+    if let Some(window) = app.get_webview_window("main") {
+        if let Ok(_) = window.emit(event, payload) {
+            // Always show the window first, then bring it to front
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
+
+/// A dashboard component registered its listeners for `events`. Delivers the
+/// events that waited for it.
+#[tauri::command]
+pub fn frontend_listening(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FrontendQueue>,
+    events: Vec<String>,
+) {
+    let pending = {
+        let mut state = state.state.lock().unwrap();
+        for event in events {
+            *state.listening.entry(event).or_default() += 1;
+        }
+        state.take_deliverable()
+    };
+    deliver_fresh(&app, pending);
+}
+
+/// A dashboard component removed its listeners for `events`.
+#[tauri::command]
+pub fn frontend_not_listening(state: tauri::State<'_, FrontendQueue>, events: Vec<String>) {
+    let mut state = state.state.lock().unwrap();
+    for event in events {
+        if let Some(count) = state.listening.get_mut(&event) {
+            *count -= 1;
+            if *count == 0 {
+                state.listening.remove(&event);
+            }
+        }
+    }
+}
@@ -0,0 +1,999 @@
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::capture;
+use crate::content_line::{self, ContentLine};
+use crate::frontend_queue::{FrontendQueue, LISTENER_STALE_AFTER};
+use crate::recurrence::Rule;
+use crate::store;
+
+/// Events are proposed for this far back.
+const WINDOW_DAYS: i64 = 366;
+const MAX_ENTRIES: usize = 1000;
+const IMPORTED_FILE: &str = "calendar-imports.json";
+
+/// Zones Outlook and Exchange name after Windows instead of IANA.
+const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("UTC", "UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("FLE Standard Time", "Europe/Kyiv"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+];
+
+/// A calendar event as a tracker entry, for the dashboard to assign to a
+/// project by its summary.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedEntry {
+    pub uid: Option<String>,
+    /// Original start of an occurrence of a recurring event, RFC 3339 in UTC.
+    /// With `uid` it identifies the entry.
+    pub recurrence_id: Option<String>,
+    pub summary: String,
+    pub description: Option<String>,
+    /// RFC 3339 in UTC
+    pub start: String,
+    pub stop: String,
+    /// The local day of the start
+    pub date: String,
+    /// Seconds
+    pub duration: i64,
+    pub recurring: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CalendarImported {
+    file_name: String,
+    entries: Vec<ProposedEntry>,
+    /// All-day, cancelled and unreadable events, those outside the window
+    /// and those past the limit
+    skipped: usize,
+}
+
+/// An entry the dashboard asks about or imported, see `ProposedEntry`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryId {
+    uid: Option<String>,
+    recurrence_id: Option<String>,
+}
+
+impl EntryId {
+    /// Entries without a UID can't be recognized again.
+    fn key(&self) -> Option<String> {
+        let uid = self.uid.as_deref()?;
+        Some(match &self.recurrence_id {
+            Some(recurrence_id) => format!("{}/{}", uid, recurrence_id),
+            None => uid.to_string(),
+        })
+    }
+}
+
+/// Keys of imported entries by team, with when they were imported.
+#[derive(Default, Serialize, Deserialize)]
+struct ImportedEntries {
+    teams: HashMap<String, HashMap<String, i64>>,
+}
+
+#[derive(Debug, Default)]
+struct Component {
+    name: String,
+    properties: Vec<ContentLine>,
+    children: Vec<Component>,
+}
+
+impl Component {
+    fn property(&self, name: &str) -> Option<&ContentLine> {
+        self.properties.iter().find(|line| line.name == name)
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.property(name)
+            .map(|line| line.text().trim().to_string())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// A `VTIMEZONE` defined in the file.
+#[derive(Debug)]
+struct Observance {
+    start: NaiveDateTime,
+    offset_from: i32,
+    offset_to: i32,
+    rule: Option<Rule>,
+    dates: Vec<NaiveDateTime>,
+}
+
+#[derive(Debug)]
+enum Zone<'a> {
+    Utc,
+    /// No zone, the time is the same wherever the user is
+    Floating,
+    Named(chrono_tz::Tz),
+    Defined(&'a [Observance]),
+}
+
+pub fn is_calendar(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ics") || ext.eq_ignore_ascii_case("ical"))
+}
+
+/// Parse `.ics` files and send the events to the dashboard as
+/// `calendar-imported`, which asks before creating tracker entries.
+pub async fn import(app: &tauri::AppHandle, paths: Vec<PathBuf>) {
+    for path in paths {
+        let file = path.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || parse_file(&file, Utc::now()))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+
+        let (entries, skipped) = match result {
+            Ok((entries, skipped)) if !entries.is_empty() => (entries, skipped),
+            Ok(_) => {
+                capture::notify(app, "No events found", &file_name(&path));
+                continue;
+            }
+            Err(e) => {
+                eprintln!("📅 Failed to read {:?}: {}", path, e);
+                capture::notify(app, "Couldn't read calendar", &e);
+                continue;
+            }
+        };
+
+        println!(
+            "📅 Read {} event(s) from {:?}, skipped {}",
+            entries.len(),
+            path,
+            skipped
+        );
+        let payload = CalendarImported {
+            file_name: file_name(&path),
+            entries,
+            skipped,
+        };
+        if let (Some(queue), Ok(payload)) = (
+            app.try_state::<FrontendQueue>(),
+            serde_json::to_value(payload),
+        ) {
+            queue.push_to_listener(app, "calendar-imported", payload, LISTENER_STALE_AFTER);
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn load_imported(app: &tauri::AppHandle) -> Result<(PathBuf, ImportedEntries), String> {
+    let path = store::data_file(app, IMPORTED_FILE)?;
+    let mut imported: ImportedEntries = store::load(&path);
+    // Entries imported before the window can't be proposed again
+    let cutoff = (Utc::now() - Duration::days(WINDOW_DAYS)).timestamp();
+    for keys in imported.teams.values_mut() {
+        keys.retain(|_, imported_at| *imported_at >= cutoff);
+    }
+    imported.teams.retain(|_, keys| !keys.is_empty());
+    Ok((path, imported))
+}
+
+/// Which of `entries` were already imported for the team, in order.
+#[tauri::command]
+pub fn imported_calendar_entries(
+    app: tauri::AppHandle,
+    team_id: String,
+    entries: Vec<EntryId>,
+) -> Result<Vec<bool>, String> {
+    let (_, imported) = load_imported(&app)?;
+    let keys = imported.teams.get(&team_id);
+    Ok(entries
+        .iter()
+        .map(|entry| {
+            entry
+                .key()
+                .is_some_and(|key| keys.is_some_and(|keys| keys.contains_key(&key)))
+        })
+        .collect())
+}
+
+/// Remember entries the dashboard imported for the team, so importing the
+/// same calendar again skips them.
+#[tauri::command]
+pub fn mark_calendar_imported(
+    app: tauri::AppHandle,
+    team_id: String,
+    entries: Vec<EntryId>,
+) -> Result<(), String> {
+    let (path, mut imported) = load_imported(&app)?;
+    let now = Utc::now().timestamp();
+    let keys = imported.teams.entry(team_id).or_default();
+    for key in entries.iter().filter_map(EntryId::key) {
+        keys.insert(key, now);
+    }
+    store::save(&path, &imported)
+}
+
+fn parse_file(path: &Path, now: DateTime<Utc>) -> Result<(Vec<ProposedEntry>, usize), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => encoding_rs::WINDOWS_1252
+            .decode(e.as_bytes())
+            .0
+            .into_owned(),
+    };
+    parse(&text, now)
+}
+
+/// The events of the last year up to `now` as tracker entries, with
+/// recurring events expanded, and the number skipped.
+pub fn parse(text: &str, now: DateTime<Utc>) -> Result<(Vec<ProposedEntry>, usize), String> {
+    let root = components(content_line::parse(text));
+    let calendars: Vec<&Component> = root
+        .children
+        .iter()
+        .filter(|component| component.name == "VCALENDAR")
+        .collect();
+    if calendars.is_empty() {
+        return Err("Not an iCalendar file".to_string());
+    }
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    // Each calendar has its own zones
+    for component in calendars {
+        let zones: Vec<(String, Option<String>, Vec<Observance>)> = component
+            .children
+            .iter()
+            .filter(|child| child.name == "VTIMEZONE")
+            .filter_map(|zone| {
+                Some((
+                    zone.text("TZID")?,
+                    zone.text("X-LIC-LOCATION"),
+                    observances(zone),
+                ))
+            })
+            .collect();
+        let calendar = Calendar {
+            zones: &zones,
+            default_zone: component.text("X-WR-TIMEZONE"),
+        };
+        skipped += calendar.events(component, now, &mut entries);
+    }
+
+    entries.sort_by(|a: &ProposedEntry, b| a.start.cmp(&b.start));
+    if entries.len() > MAX_ENTRIES {
+        let extra = entries.len() - MAX_ENTRIES;
+        skipped += extra;
+        entries.drain(..extra);
+    }
+    Ok((entries, skipped))
+}
+
+/// Nest the lines between `BEGIN` and `END` into components.
+fn components(lines: Vec<ContentLine>) -> Component {
+    let mut stack = vec![Component::default()];
+    for line in lines {
+        match line.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: line.value.trim().to_ascii_uppercase(),
+                ..Default::default()
+            }),
+            "END" if stack.len() > 1 => {
+                let component = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(component);
+            }
+            "END" => {}
+            _ => stack.last_mut().unwrap().properties.push(line),
+        }
+    }
+    // Close components a truncated file left open
+    while stack.len() > 1 {
+        let component = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(component);
+    }
+    stack.pop().unwrap()
+}
+
+fn observances(zone: &Component) -> Vec<Observance> {
+    zone.children
+        .iter()
+        .filter(|child| child.name == "STANDARD" || child.name == "DAYLIGHT")
+        .filter_map(|child| {
+            let (start, _) = parse_date_time(&child.property("DTSTART")?.value)?;
+            let offset_from = parse_offset(&child.property("TZOFFSETFROM")?.value)?;
+            let offset_to = parse_offset(&child.property("TZOFFSETTO")?.value)?;
+            // `UNTIL` is in UTC, before the change
+            let until = |value: &str| {
+                let (until, utc) = parse_date_time(value)?;
+                Some(match utc {
+                    true => until + Duration::seconds(offset_from as i64),
+                    false => until,
+                })
+            };
+            let rule = child
+                .property("RRULE")
+                .and_then(|line| Rule::parse(&line.value, until).ok());
+            let dates = child
+                .properties
+                .iter()
+                .filter(|line| line.name == "RDATE")
+                .flat_map(|line| line.value.split(','))
+                .filter_map(|value| parse_date_time(value).map(|(date, _)| date))
+                .collect();
+            Some(Observance {
+                start,
+                offset_from,
+                offset_to,
+                rule,
+                dates,
+            })
+        })
+        .collect()
+}
+
+/// The UTC offset in seconds of a defined zone at a local time.
+fn offset_at(observances: &[Observance], local: NaiveDateTime) -> i32 {
+    let latest = observances
+        .iter()
+        .filter_map(|observance| {
+            let from_rule = observance.rule.as_ref().and_then(|rule| {
+                rule.between(observance.start, observance.start, local)
+                    .last()
+                    .copied()
+            });
+            let from_dates = observance
+                .dates
+                .iter()
+                .filter(|date| **date <= local)
+                .max()
+                .copied();
+            let onset = [
+                Some(observance.start).filter(|start| *start <= local),
+                from_rule,
+                from_dates,
+            ]
+            .into_iter()
+            .flatten()
+            .max()?;
+            Some((onset, observance.offset_to))
+        })
+        .max_by_key(|(onset, _)| *onset);
+
+    match latest {
+        Some((_, offset)) => offset,
+        // Before the first change
+        None => observances
+            .iter()
+            .min_by_key(|observance| observance.start)
+            .map(|observance| observance.offset_from)
+            .unwrap_or(0),
+    }
+}
+
+/// A `DTSTART`, `DTEND`, `EXDATE`, ... value in its zone.
+struct Moment<'a> {
+    local: NaiveDateTime,
+    zone: Zone<'a>,
+    all_day: bool,
+}
+
+struct Calendar<'a> {
+    /// TZID, X-LIC-LOCATION and the definition of each `VTIMEZONE`
+    zones: &'a [(String, Option<String>, Vec<Observance>)],
+    /// Google's calendar wide zone for times without one
+    default_zone: Option<String>,
+}
+
+impl<'a> Calendar<'a> {
+    /// An IANA name, a path ending in one, the location or definition in the
+    /// file, a Windows name, or floating when nothing matches.
+    fn zone(&self, tzid: Option<&str>) -> Zone<'a> {
+        let Some(tzid) = tzid
+            .or(self.default_zone.as_deref())
+            .map(|tzid| tzid.trim().trim_matches('"'))
+            .filter(|tzid| !tzid.is_empty())
+        else {
+            return Zone::Floating;
+        };
+
+        if let Some(zone) = named_zone(tzid) {
+            return zone;
+        }
+        // e.g. `/mozilla.org/20050126_1/Europe/Berlin`
+        let segments: Vec<&str> = tzid.split('/').collect();
+        if let Some(zone) =
+            (1..segments.len()).find_map(|index| named_zone(&segments[index..].join("/")))
+        {
+            return zone;
+        }
+
+        if let Some((_, location, observances)) = self.zones.iter().find(|(id, _, _)| id == tzid) {
+            if let Some(zone) = location.as_deref().and_then(named_zone) {
+                return zone;
+            }
+            if !observances.is_empty() {
+                return Zone::Defined(observances);
+            }
+        }
+
+        WINDOWS_ZONES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(tzid))
+            .and_then(|(_, iana)| named_zone(iana))
+            .unwrap_or_else(|| {
+                println!("📅 Unknown time zone {}, using local time", tzid);
+                Zone::Floating
+            })
+    }
+
+    fn moment(&self, line: &ContentLine, value: &str) -> Option<Moment<'a>> {
+        let value = value.trim();
+        let is_date = line
+            .param("VALUE")
+            .is_some_and(|kind| kind.eq_ignore_ascii_case("DATE"))
+            || value.len() == 8;
+        if is_date {
+            let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+            return Some(Moment {
+                local: date.and_hms_opt(0, 0, 0)?,
+                zone: Zone::Floating,
+                all_day: true,
+            });
+        }
+
+        let (local, utc) = parse_date_time(value)?;
+        Some(Moment {
+            local,
+            zone: match utc {
+                true => Zone::Utc,
+                false => self.zone(line.param("TZID")),
+            },
+            all_day: false,
+        })
+    }
+
+    /// Every value of properties like `EXDATE` that can repeat and list many.
+    fn moments(&self, event: &Component, name: &str) -> Vec<DateTime<Utc>> {
+        event
+            .properties
+            .iter()
+            .filter(|line| line.name == name)
+            .flat_map(|line| {
+                line.value
+                    .split(',')
+                    // `RDATE` periods are start/end or start/duration
+                    .filter_map(|value| self.moment(line, value.split('/').next()?))
+                    .filter_map(|moment| to_utc(moment.local, &moment.zone))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Add the entries of a calendar's events and return how many were skipped.
+    fn events(
+        &self,
+        calendar: &Component,
+        now: DateTime<Utc>,
+        entries: &mut Vec<ProposedEntry>,
+    ) -> usize {
+        let events: Vec<&Component> = calendar
+            .children
+            .iter()
+            .filter(|component| component.name == "VEVENT")
+            .collect();
+
+        // Modified or cancelled occurrences of recurring events
+        let overrides: HashSet<(String, DateTime<Utc>)> = events
+            .iter()
+            .filter_map(|event| {
+                let line = event.property("RECURRENCE-ID")?;
+                let moment = self.moment(line, &line.value)?;
+                Some((event.text("UID")?, to_utc(moment.local, &moment.zone)?))
+            })
+            .collect();
+
+        let from = now - Duration::days(WINDOW_DAYS);
+        let mut skipped = 0;
+        for event in events {
+            let cancelled = event
+                .text("STATUS")
+                .is_some_and(|status| status.eq_ignore_ascii_case("CANCELLED"));
+            let start = event
+                .property("DTSTART")
+                .and_then(|line| self.moment(line, &line.value));
+            let Some(start) = start.filter(|start| !start.all_day && !cancelled) else {
+                skipped += 1;
+                continue;
+            };
+            let Some(first) = to_utc(start.local, &start.zone) else {
+                skipped += 1;
+                continue;
+            };
+
+            let end = event
+                .property("DTEND")
+                .and_then(|line| self.moment(line, &line.value))
+                .and_then(|end| to_utc(end.local, &end.zone));
+            let duration = match end {
+                Some(end) => end - first,
+                None => event
+                    .text("DURATION")
+                    .and_then(|value| parse_duration(&value))
+                    .unwrap_or_default(),
+            };
+            if duration <= Duration::zero() {
+                skipped += 1;
+                continue;
+            }
+
+            let uid = event.text("UID");
+            let recurrence_id = event.property("RECURRENCE-ID");
+            let is_override = recurrence_id.is_some();
+            let overridden = recurrence_id
+                .and_then(|line| self.moment(line, &line.value))
+                .and_then(|moment| to_utc(moment.local, &moment.zone));
+            let rule = match event.property("RRULE") {
+                Some(_) if is_override => None,
+                Some(line) => {
+                    let until = |value: &str| self.until(value, &start.zone);
+                    Rule::parse(&line.value, until)
+                        .inspect_err(|e| {
+                            println!("📅 {}, importing {:?} once", e, event.text("SUMMARY"))
+                        })
+                        .ok()
+                }
+                None => None,
+            };
+
+            let starts: Vec<DateTime<Utc>> = match &rule {
+                Some(rule) => {
+                    // A day of margin for the shift from local time
+                    let local_from = to_local(from, &start.zone) - Duration::days(1);
+                    let local_to = to_local(now, &start.zone) + Duration::days(1);
+                    let excluded = self.moments(event, "EXDATE");
+                    let mut starts: Vec<DateTime<Utc>> = rule
+                        .between(start.local, local_from, local_to)
+                        .into_iter()
+                        .filter_map(|local| to_utc(local, &start.zone))
+                        .chain(self.moments(event, "RDATE"))
+                        .filter(|occurrence| *occurrence >= from && *occurrence <= now)
+                        .filter(|occurrence| !excluded.contains(occurrence))
+                        .filter(|occurrence| match &uid {
+                            Some(uid) => !overrides.contains(&(uid.clone(), *occurrence)),
+                            None => true,
+                        })
+                        .collect();
+                    starts.sort();
+                    starts.dedup();
+                    starts
+                }
+                None if first < from || first > now => {
+                    // Moved occurrences follow the window of their series
+                    if !is_override {
+                        skipped += 1;
+                    }
+                    continue;
+                }
+                None => vec![first],
+            };
+
+            let summary = event.text("SUMMARY").unwrap_or_default();
+            let description = event.text("DESCRIPTION");
+            for start_time in starts {
+                let stop = start_time + duration;
+                let occurrence = match &rule {
+                    Some(_) => Some(start_time),
+                    None => overridden,
+                };
+                entries.push(ProposedEntry {
+                    uid: uid.clone(),
+                    recurrence_id: occurrence
+                        .map(|time| time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+                    summary: summary.clone(),
+                    description: description.clone(),
+                    start: start_time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                    stop: stop.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                    date: start_time
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d")
+                        .to_string(),
+                    duration: duration.num_seconds(),
+                    recurring: rule.is_some() || is_override,
+                });
+            }
+        }
+        skipped
+    }
+
+    /// `UNTIL` in the local time of the event; a date includes the whole day.
+    fn until(&self, value: &str, zone: &Zone) -> Option<NaiveDateTime> {
+        let value = value.trim();
+        if value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+            return date.and_hms_opt(23, 59, 59);
+        }
+        let (until, utc) = parse_date_time(value)?;
+        match utc {
+            true => Some(to_local(Utc.from_utc_datetime(&until), zone)),
+            false => Some(until),
+        }
+    }
+}
+
+fn named_zone(name: &str) -> Option<Zone<'static>> {
+    match name {
+        "UTC" | "GMT" | "Etc/UTC" | "Etc/GMT" | "Z" => Some(Zone::Utc),
+        _ => name.parse::<chrono_tz::Tz>().ok().map(Zone::Named),
+    }
+}
+
+/// Times in a DST gap move forward an hour, repeated ones take the first.
+fn resolve<Z: TimeZone>(zone: &Z, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    let time = match zone.from_local_datetime(&local) {
+        LocalResult::Single(time) => time,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => zone
+            .from_local_datetime(&(local + Duration::hours(1)))
+            .earliest()?,
+    };
+    Some(time.with_timezone(&Utc))
+}
+
+fn to_utc(local: NaiveDateTime, zone: &Zone) -> Option<DateTime<Utc>> {
+    match zone {
+        Zone::Utc => Some(Utc.from_utc_datetime(&local)),
+        Zone::Floating => resolve(&Local, local),
+        Zone::Named(tz) => resolve(tz, local),
+        Zone::Defined(observances) => {
+            Some(Utc.from_utc_datetime(
+                &(local - Duration::seconds(offset_at(observances, local) as i64)),
+            ))
+        }
+    }
+}
+
+fn to_local(time: DateTime<Utc>, zone: &Zone) -> NaiveDateTime {
+    match zone {
+        Zone::Utc => time.naive_utc(),
+        Zone::Floating => time.with_timezone(&Local).naive_local(),
+        Zone::Named(tz) => time.with_timezone(tz).naive_local(),
+        Zone::Defined(observances) => {
+            let utc = time.naive_utc();
+            // Close enough: the offset at the UTC time read as local
+            utc + Duration::seconds(offset_at(observances, utc) as i64)
+        }
+    }
+}
+
+/// `20240415T090000` or `20240415T070000Z`, and whether it's UTC.
+fn parse_date_time(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    let (value, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M"))
+        .ok()?;
+    Some((time, utc))
+}
+
+/// `+0200`, `-0430` or `+053000`.
+fn parse_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let sign = match value.chars().next()? {
+        '-' => -1,
+        '+' => 1,
+        _ => return None,
+    };
+    let digits = &value[1..];
+    let part =
+        |range: std::ops::Range<usize>| digits.get(range).and_then(|part| part.parse::<i32>().ok());
+    let seconds = part(0..2)? * 3600 + part(2..4)? * 60 + part(4..6).unwrap_or(0);
+    Some(sign * seconds)
+}
+
+/// `PT1H30M`, `P1D` or `P2W`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix(['P', 'p'])?;
+
+    let mut seconds = 0i64;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c.to_ascii_uppercase() {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let amount: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += amount
+                    * match unit {
+                        'W' => 7 * 86_400,
+                        'D' => 86_400,
+                        'H' => 3600,
+                        'M' => 60,
+                        'S' => 1,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    number.is_empty().then(|| Duration::seconds(sign * seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap()
+    }
+
+    fn calendar(body: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}\r\nEND:VCALENDAR\r\n",
+            body.trim().replace('\n', "\r\n")
+        )
+    }
+
+    fn starts(entries: &[ProposedEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.start.as_str()).collect()
+    }
+
+    #[test]
+    fn utc_and_named_zones() {
+        let text = calendar(
+            "BEGIN:VEVENT
+UID:a
+SUMMARY:Standup
+DTSTART:20240610T090000Z
+DTEND:20240610T100000Z
+END:VEVENT
+BEGIN:VEVENT
+UID:b
+DTSTART;TZID=Europe/Berlin:20240611T090000
+DTEND;TZID=Europe/Berlin:20240611T093000
+END:VEVENT
+BEGIN:VEVENT
+UID:c
+DTSTART;TZID=\"W. Europe Standard Time\":20240612T090000
+DURATION:PT1H30M
+END:VEVENT
+BEGIN:VEVENT
+UID:d
+DTSTART;TZID=/mozilla.org/20050126_1/America/New_York:20240613T090000
+DTEND;TZID=/mozilla.org/20050126_1/America/New_York:20240613T100000
+END:VEVENT",
+        );
+        let (entries, skipped) = parse(&text, now()).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(
+            starts(&entries),
+            [
+                "2024-06-10T09:00:00.000Z",
+                "2024-06-11T07:00:00.000Z",
+                "2024-06-12T07:00:00.000Z",
+                "2024-06-13T13:00:00.000Z"
+            ]
+        );
+        assert_eq!(entries[0].summary, "Standup");
+        assert_eq!(entries[0].stop, "2024-06-10T10:00:00.000Z");
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.duration)
+                .collect::<Vec<_>>(),
+            [3600, 1800, 5400, 3600]
+        );
+        assert!(entries.iter().all(|entry| entry.recurrence_id.is_none()));
+    }
+
+    #[test]
+    fn defined_zones() {
+        let text = calendar(
+            "BEGIN:VTIMEZONE
+TZID:Custom Eastern
+BEGIN:STANDARD
+DTSTART:19701101T020000
+RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU
+TZOFFSETFROM:-0400
+TZOFFSETTO:-0500
+END:STANDARD
+BEGIN:DAYLIGHT
+DTSTART:19700308T020000
+RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU
+TZOFFSETFROM:-0500
+TZOFFSETTO:-0400
+END:DAYLIGHT
+END:VTIMEZONE
+BEGIN:VEVENT
+UID:winter
+DTSTART;TZID=Custom Eastern:20240110T090000
+DTEND;TZID=Custom Eastern:20240110T100000
+END:VEVENT
+BEGIN:VEVENT
+UID:summer
+DTSTART;TZID=Custom Eastern:20240610T090000
+DTEND;TZID=Custom Eastern:20240610T100000
+END:VEVENT",
+        );
+        let (entries, _) = parse(&text, now()).unwrap();
+        assert_eq!(
+            starts(&entries),
+            ["2024-01-10T14:00:00.000Z", "2024-06-10T13:00:00.000Z"]
+        );
+    }
+
+    #[test]
+    fn recurring_events_keep_local_time_across_daylight_saving() {
+        let text = calendar(
+            "BEGIN:VEVENT
+UID:weekly
+DTSTART;TZID=Europe/Berlin:20240318T090000
+DTEND;TZID=Europe/Berlin:20240318T100000
+RRULE:FREQ=WEEKLY;COUNT=3
+END:VEVENT",
+        );
+        let (entries, _) = parse(&text, now()).unwrap();
+        assert_eq!(
+            starts(&entries),
+            [
+                "2024-03-18T08:00:00.000Z",
+                "2024-03-25T08:00:00.000Z",
+                "2024-04-01T07:00:00.000Z"
+            ]
+        );
+        assert!(entries.iter().all(|entry| entry.recurring));
+        assert_eq!(
+            entries[2].recurrence_id.as_deref(),
+            Some("2024-04-01T07:00:00Z")
+        );
+    }
+
+    #[test]
+    fn exceptions_additions_and_overrides() {
+        let text = calendar(
+            "BEGIN:VEVENT
+UID:series
+SUMMARY:Daily
+DTSTART;TZID=Europe/Berlin:20240603T090000
+DTEND;TZID=Europe/Berlin:20240603T091500
+RRULE:FREQ=DAILY;COUNT=5
+EXDATE;TZID=Europe/Berlin:20240604T090000
+RDATE;TZID=Europe/Berlin:20240610T090000
+END:VEVENT
+BEGIN:VEVENT
+UID:series
+SUMMARY:Moved
+RECURRENCE-ID;TZID=Europe/Berlin:20240605T090000
+DTSTART;TZID=Europe/Berlin:20240605T140000
+DTEND;TZID=Europe/Berlin:20240605T143000
+END:VEVENT",
+        );
+        let (entries, skipped) = parse(&text, now()).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(
+            starts(&entries),
+            [
+                "2024-06-03T07:00:00.000Z",
+                "2024-06-05T12:00:00.000Z",
+                "2024-06-06T07:00:00.000Z",
+                "2024-06-07T07:00:00.000Z",
+                "2024-06-10T07:00:00.000Z"
+            ]
+        );
+
+        let moved = &entries[1];
+        assert_eq!(moved.summary, "Moved");
+        assert_eq!(moved.duration, 1800);
+        assert!(moved.recurring);
+        assert_eq!(moved.recurrence_id.as_deref(), Some("2024-06-05T07:00:00Z"));
+        assert_eq!(
+            entries[0].recurrence_id.as_deref(),
+            Some("2024-06-03T07:00:00Z")
+        );
+    }
+
+    #[test]
+    fn only_the_last_year() {
+        let text = calendar(
+            "BEGIN:VEVENT
+UID:old
+DTSTART:20220610T090000Z
+DTEND:20220610T100000Z
+END:VEVENT
+BEGIN:VEVENT
+UID:future
+DTSTART:20240701T090000Z
+DTEND:20240701T100000Z
+END:VEVENT
+BEGIN:VEVENT
+UID:all-day
+DTSTART;VALUE=DATE:20240610
+DTEND;VALUE=DATE:20240611
+END:VEVENT
+BEGIN:VEVENT
+UID:cancelled
+STATUS:CANCELLED
+DTSTART:20240610T090000Z
+DTEND:20240610T100000Z
+END:VEVENT
+BEGIN:VEVENT
+UID:forever
+DTSTART:20200101T090000Z
+DTEND:20200101T091500Z
+RRULE:FREQ=DAILY
+END:VEVENT",
+        );
+        let (entries, skipped) = parse(&text, now()).unwrap();
+        assert_eq!(skipped, 4);
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.uid.as_deref() == Some("forever"))
+        );
+        assert_eq!(entries.len(), 366);
+        assert_eq!(entries[0].start, "2023-06-16T09:00:00.000Z");
+        assert_eq!(entries[365].start, "2024-06-15T09:00:00.000Z");
+    }
+
+    #[test]
+    fn not_a_calendar() {
+        assert!(parse("BEGIN:VCARD\r\nFN:Jane\r\nEND:VCARD\r\n", now()).is_err());
+    }
+
+    #[test]
+    fn offsets_and_durations() {
+        assert_eq!(parse_offset("+0200"), Some(7200));
+        assert_eq!(parse_offset("-0430"), Some(-16_200));
+        assert_eq!(parse_offset("+053000"), Some(19_800));
+        assert_eq!(parse_offset("0200"), None);
+
+        let seconds = |value| parse_duration(value).map(|duration| duration.num_seconds());
+        assert_eq!(seconds("PT1H30M"), Some(5400));
+        assert_eq!(seconds("P1D"), Some(86_400));
+        assert_eq!(seconds("P2W"), Some(1_209_600));
+        assert_eq!(seconds("-PT15M"), Some(-900));
+        assert_eq!(seconds("PT"), Some(0));
+        assert_eq!(seconds("1H"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use serde_json;
 use std::env;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use tauri::{
     Emitter, Listener, Manager, PhysicalPosition, Position, TitleBarStyle, WebviewUrl,
     WebviewWindowBuilder,
@@ -8,15 +8,50 @@ use tauri::{
 use tauri_plugin_deep_link::DeepLinkExt;
 use tauri_plugin_updater;
 use tauri_plugin_dialog;
-use tauri_plugin_process;
-use tauri::menu::{Menu, MenuItem};
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
 use tauri::image::Image;
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::webview::PageLoadEvent;
 use image;
 
+mod archive;
+mod capture;
+mod cli;
+mod clipboard;
+mod content_line;
+mod csv_import;
+mod download;
+mod drag_drop;
+mod duplicates;
+mod email;
+mod export;
+mod folder_watch;
+mod frontend_queue;
+mod ics;
+mod merge;
+mod payment_codes;
+mod preprocess;
+mod print;
+mod qr;
+mod receipt;
+mod recurrence;
+mod save;
+mod shortcuts;
+mod statements;
+mod store;
+mod updates;
+mod upload;
+mod vcard;
+
+use frontend_queue::FrontendQueue;
+
 // Global state for search window availability
 type SearchWindowState = Arc<Mutex<bool>>;
 
+// Set from `--env` / `--profile` before the app starts
+static ENV_OVERRIDE: OnceLock<String> = OnceLock::new();
+static PROFILE: OnceLock<String> = OnceLock::new();
+
 #[tauri::command]
 fn show_window(window: tauri::Window) -> Result<(), String> {
     // Always target the main window specifically, not the calling window
@@ -38,106 +73,6 @@ fn show_window(window: tauri::Window) -> Result<(), String> {
     Ok(())
 }
 
-/// Prompt the user to download and install an update.
-/// Shared by both manual and silent update flows.
-#[cfg(desktop)]
-async fn prompt_and_install_update(app: &tauri::AppHandle, update: tauri_plugin_updater::Update) {
-    use tauri_plugin_dialog::{DialogExt, MessageDialogKind, MessageDialogButtons};
-
-    let answer = app.dialog()
-        .message(format!("A new version {} is available. Would you like to update now?", update.version))
-        .title("Update Available")
-        .kind(MessageDialogKind::Info)
-        .buttons(MessageDialogButtons::OkCancel)
-        .blocking_show();
-
-    if answer {
-        let _ = update.download_and_install(
-            |_chunk_length, _content_length| {},
-            || {
-                println!("Update download finished");
-            }
-        ).await;
-    }
-}
-
-/// Silent update check — only shows a dialog when an update is available.
-/// Used on startup and by the periodic background timer.
-#[cfg(desktop)]
-async fn silent_update_check(app: tauri::AppHandle) {
-    use tauri_plugin_updater::UpdaterExt;
-
-    if let Ok(updater) = app.updater() {
-        match updater.check().await {
-            Ok(Some(update)) => {
-                println!("Update available: {}", update.version);
-                prompt_and_install_update(&app, update).await;
-            }
-            Ok(None) => {
-                println!("No updates available");
-            }
-            Err(e) => {
-                println!("Silent update check failed: {}", e);
-            }
-        }
-    }
-}
-
-/// Manual update check (triggered from tray menu).
-/// Shows dialogs for all outcomes: update available, up-to-date, and errors.
-#[tauri::command]
-async fn check_for_updates(app: tauri::AppHandle) -> Result<(), String> {
-    use tauri_plugin_dialog::{DialogExt, MessageDialogKind, MessageDialogButtons};
-    use tauri_plugin_updater::UpdaterExt;
-    
-    #[cfg(desktop)]
-    {
-        if let Ok(updater) = app.updater() {
-            match updater.check().await {
-                Ok(Some(update)) => {
-                    prompt_and_install_update(&app, update).await;
-                }
-                Ok(None) => {
-                    let version = app.package_info().version.to_string();
-                    app.dialog()
-                        .message(format!("Midday\nversion {}\n\nYou're up to date!", version))
-                        .title("No Updates Available")
-                        .kind(MessageDialogKind::Info)
-                        .buttons(MessageDialogButtons::Ok)
-                        .blocking_show();
-                }
-                Err(e) => {
-                    app.dialog()
-                        .message(format!("Failed to check for updates: {}", e))
-                        .title("Update Check Failed")
-                        .kind(MessageDialogKind::Error)
-                        .buttons(MessageDialogButtons::Ok)
-                        .blocking_show();
-                }
-            }
-        } else {
-            app.dialog()
-                .message("Update checking is not available in this build.")
-                .title("Updates Not Available")
-                .kind(MessageDialogKind::Warning)
-                .buttons(MessageDialogButtons::Ok)
-                .blocking_show();
-        }
-    }
-    
-    #[cfg(not(desktop))]
-    {
-        app.dialog()
-            .message("Updates are managed through your app store.")
-            .title("Check App Store")
-            .kind(MessageDialogKind::Info)
-            .buttons(MessageDialogButtons::Ok)
-            .blocking_show();
-    }
-    
-    Ok(())
-}
-
 fn toggle_search_window(
     app: &tauri::AppHandle,
     search_state: &SearchWindowState,
@@ -286,11 +221,11 @@ async fn create_preloaded_search_window(
     .transparent(true)
     .decorations(false)
     .visible(false) // Start hidden for preloading
-    .on_download(|_window, _event| {
-        println!("Search window download triggered!");
-        // Allow downloads from search window too
-        true
-    });
+    .on_download(download::handle_download);
+
+    if let Some(data_directory) = profile_data_directory(app) {
+        search_builder = search_builder.data_directory(data_directory);
+    }
 
     // Platform-specific styling
     search_builder = search_builder
@@ -333,9 +268,12 @@ async fn create_preloaded_search_window(
 }
 
 fn get_app_url() -> String {
-    // Try runtime environment variable first, then fall back to compile-time
-    let env = env::var("MIDDAY_ENV")
-        .unwrap_or_else(|_| {
+    // Try the --env flag, then the runtime environment variable, then fall back to compile-time
+    let env = ENV_OVERRIDE
+        .get()
+        .cloned()
+        .or_else(|| env::var("MIDDAY_ENV").ok())
+        .unwrap_or_else(|| {
             option_env!("MIDDAY_ENV")
                 .unwrap_or("development")
                 .to_string()
@@ -382,6 +320,51 @@ fn is_external_url(url: &str, app_url: &str) -> bool {
     false
 }
 
+/// Webview data directory for the `--profile` flag, so each profile keeps its
+/// own cookies and session. `None` uses the default directory.
+fn profile_data_directory(app: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+    let profile = PROFILE.get()?;
+    let dir = app.path().app_local_data_dir().ok()?;
+    Some(dir.join("profiles").join(profile))
+}
+
+/// The default app menu with its Quit item replaced by one that quits like the
+/// tray's, through `app.exit`, so a staged update still installs.
+fn app_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::default(app)?;
+    let quit_text = PredefinedMenuItem::quit(app, None)?.text()?;
+    for item in menu.items()? {
+        let Some(submenu) = item.as_submenu() else {
+            continue;
+        };
+        let position = submenu.items()?.iter().position(|item| {
+            item.as_predefined_menuitem()
+                .and_then(|item| item.text().ok())
+                .is_some_and(|text| text == quit_text)
+        });
+        if let Some(position) = position {
+            submenu.remove_at(position)?;
+            let quit = MenuItem::with_id(app, "quit", &quit_text, true, Some("CmdOrCtrl+Q"))?;
+            submenu.insert(&quit, position)?;
+        }
+    }
+    Ok(menu)
+}
+
+/// Reject commands from webviews rendering untrusted documents, such as email
+/// bodies. Plugin commands are already limited by the capabilities.
+fn isolate_commands(
+    commands: impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync + 'static,
+) -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        if print::is_isolated(invoke.message.webview_ref().label()) {
+            invoke.resolver.reject("Commands aren't available in this window");
+            return true;
+        }
+        commands(invoke)
+    }
+}
+
 fn handle_deep_link_event(app_handle: &tauri::AppHandle, urls: Vec<String>) {
     for url in &urls {
         // Only handle midday schemes (midday://, midday-dev://, midday-staging://)
@@ -395,14 +378,46 @@ fn handle_deep_link_event(app_handle: &tauri::AppHandle, urls: Vec<String>) {
             // Remove any leading slashes
             let clean_path = path.trim_start_matches('/');
 
-            // Get the main window and emit navigation event to frontend
-            if let Some(window) = app_handle.get_webview_window("main") {
-                // Emit event to frontend with just the path - frontend handles the full URL construction
-                if let Ok(_) = window.emit("deep-link-navigate", clean_path) {
-                    // Always show the window first, then bring it to front
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
+            // Emit navigation event to the main window with just the path - frontend
+            // handles the full URL construction. Queued until the frontend is listening.
+            if let Some(queue) = app_handle.try_state::<FrontendQueue>() {
+                queue.push(app_handle, "deep-link-navigate", serde_json::json!(clean_path));
+            }
+        }
+    }
+}
+
+/// Runs in the already-running instance when Midday is launched again. The
+/// second process exits right away and hands its arguments over to us.
+#[cfg(desktop)]
+fn handle_second_instance(app_handle: &tauri::AppHandle, argv: Vec<String>, cwd: String) {
+    println!("🪟 Second instance launched with {:?} (cwd: {})", argv, cwd);
+
+    // The second process already validated its arguments before exiting
+    let args = match cli::parse(argv, std::path::Path::new(&cwd)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("🪟 Ignoring invalid arguments from second instance: {}", e);
+            return;
+        }
+    };
+
+    if args.env.is_some() || args.profile.is_some() {
+        println!("🪟 --env and --profile only apply when Midday is not already running");
+    }
+
+    // Deep links on Linux/Windows are passed as plain arguments
+    if !args.urls.is_empty() {
+        handle_deep_link_event(app_handle, args.urls);
+    }
+
+    match args.command {
+        Some(command) => cli::run_command(app_handle, command),
+        None => {
+            if let Some(main_window) = app_handle.get_webview_window("main") {
+                let _ = main_window.unminimize();
+                let _ = main_window.show();
+                let _ = main_window.set_focus();
             }
         }
     }
@@ -410,16 +425,107 @@ fn handle_deep_link_event(app_handle: &tauri::AppHandle, urls: Vec<String>) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let cwd = env::current_dir().unwrap_or_default();
+    let cli_args = match cli::parse(env::args(), &cwd) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if cli_args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    if let Some(env) = cli_args.env.clone() {
+        let _ = ENV_OVERRIDE.set(env);
+    }
+    if let Some(profile) = cli_args.profile.clone() {
+        let _ = PROFILE.set(profile);
+    }
+
+    // Deep link URLs are picked up by the deep link plugin itself on launch
+    let startup_command = cli_args.command;
+
     let app_url = get_app_url();
 
-    tauri::Builder::default()
+    let mut builder = tauri::Builder::default();
+
+    // Must be registered first so a second launch exits before any other
+    // plugin (global shortcut, tray) initializes
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(handle_second_instance));
+    }
+
+    builder
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_upload::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![show_window, check_for_updates])
+        .plugin(tauri_plugin_notification::init())
+        .invoke_handler(isolate_commands(tauri::generate_handler![
+            show_window,
+            cli::timer_result,
+            frontend_queue::frontend_listening,
+            frontend_queue::frontend_not_listening,
+            updates::check_for_updates,
+            updates::pending_update,
+            updates::update_action,
+            shortcuts::shortcuts_status,
+            upload::set_upload_session,
+            upload::list_uploads,
+            upload::finished_uploads,
+            upload::ack_upload,
+            upload::retry_upload,
+            upload::cancel_upload,
+            upload::finish_plugin_upload,
+            preprocess::get_image_settings,
+            preprocess::set_image_settings,
+            duplicates::check_duplicates,
+            duplicates::get_duplicate_settings,
+            duplicates::set_duplicate_settings,
+            duplicates::clear_upload_index,
+            drag_drop::upload_dropped_files,
+            drag_drop::discard_drop,
+            merge::merge_to_pdf,
+            qr::scan_payment_codes,
+            receipt::preview_receipts,
+            statements::parse_statement,
+            statements::parse_statement_file,
+            csv_import::pick_import_file,
+            csv_import::import_dropped_file,
+            csv_import::close_import_file,
+            csv_import::inspect_csv,
+            csv_import::read_csv_chunk,
+            ics::imported_calendar_entries,
+            ics::mark_calendar_imported,
+            download::list_downloads,
+            download::open_download,
+            download::show_download_in_folder,
+            download::cancel_download,
+            download::clear_download_history,
+            download::get_download_settings,
+            download::set_download_settings,
+            save::begin_save,
+            save::write_save_chunk,
+            save::finish_save,
+            save::abort_save,
+            save::save_url,
+            save::reveal_in_folder,
+            export::bulk_export,
+            export::cancel_export,
+            print::print_url,
+            print::print_current_view,
+            folder_watch::list_watched_folders,
+            folder_watch::add_watched_folder,
+            folder_watch::update_watched_folder,
+            folder_watch::remove_watched_folder,
+            folder_watch::watch_status
+        ]))
         .setup(move |app| {
             // Add updater plugin conditionally for desktop
             #[cfg(desktop)]
@@ -433,7 +539,7 @@ pub fn run() {
                     // Wait 5 seconds after startup before first check
                     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                     println!("Running startup update check...");
-                    silent_update_check(app_handle_for_updates.clone()).await;
+                    updates::silent_update_check(app_handle_for_updates.clone()).await;
 
                     // Then check every 4 hours
                     let mut interval = tokio::time::interval(std::time::Duration::from_secs(4 * 60 * 60));
@@ -441,7 +547,7 @@ pub fn run() {
                     loop {
                         interval.tick().await;
                         println!("Running periodic update check...");
-                        silent_update_check(app_handle_for_updates.clone()).await;
+                        updates::silent_update_check(app_handle_for_updates.clone()).await;
                     }
                 });
             }
@@ -461,43 +567,8 @@ pub fn run() {
             
             // Auth state is now accessed via managed state for consistency
 
-            // Initialize global shortcuts
-            {
-                use tauri_plugin_global_shortcut::{
-                    Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState,
-                };
-
-                let search_shortcut =
-                    Shortcut::new(Some(Modifiers::SHIFT | Modifiers::ALT), Code::KeyK);
-
-                if let Ok(_) = app.handle().plugin(
-                    tauri_plugin_global_shortcut::Builder::new()
-                        .with_handler(move |app_handle, shortcut, event| {
-                            if shortcut == &search_shortcut
-                                && event.state() == ShortcutState::Pressed
-                            {
-                                println!("🔍 Global shortcut triggered - checking search state via managed state");
-                                // Get search state from managed state (same as commands use)
-                                if let Some(managed_search_state) = app_handle.try_state::<SearchWindowState>() {
-                                    let current_search_state = *managed_search_state.lock().unwrap();
-                                    println!("🔍 Shortcut: Search state from managed state: {}", current_search_state);
-                                    
-                                    // Use the same app_handle for both search state and toggle function
-                                    let result = toggle_search_window(app_handle, &managed_search_state);
-                                    match result {
-                                        Ok(_) => println!("🔍 Shortcut: toggle_search_window returned Ok"),
-                                        Err(e) => println!("🔍 Shortcut: toggle_search_window returned Err: {}", e)
-                                    }
-                                } else {
-                                    println!("❌ Failed to get managed search state for shortcut");
-                                }
-                            }
-                        })
-                        .build(),
-                ) {
-                    let _ = app.global_shortcut().register(search_shortcut);
-                }
-            }
+            // Initialize global shortcuts (with a portal fallback on Wayland)
+            shortcuts::setup(&app_handle);
 
             // Register deep links at runtime for development (Linux/Windows only).
             // macOS does not support runtime registration — the scheme is registered
@@ -510,9 +581,22 @@ pub fn run() {
                 }
             }
 
-            // Log deep link URLs if the app was launched via a deep link
-            if let Ok(urls) = app_handle.deep_link().get_current() {
+            // Deep links are held here until the dashboard emits `frontend-ready`
+            app.manage(FrontendQueue::default());
+            app.manage(cli::TimerRequests::default());
+
+            // Downloads from the main and search windows are routed and recorded here
+            download::setup(&app_handle);
+            app.manage(save::SaveState::default());
+            app.manage(export::ExportState::default());
+            app.manage(csv_import::CsvState::default());
+            app.manage(updates::UpdateState::default());
+
+            // Queue deep link URLs if the app was launched via a deep link
+            if let Ok(Some(urls)) = app_handle.deep_link().get_current() {
                 println!("🔗 Current deep link URLs on launch: {:?}", urls);
+                let url_strings: Vec<String> = urls.iter().map(|url| url.to_string()).collect();
+                handle_deep_link_event(&app_handle, url_strings);
             }
 
             // Handle deep link events
@@ -538,12 +622,16 @@ pub fn run() {
             .shadow(true)
             .hidden_title(true)
             .title_bar_style(TitleBarStyle::Overlay)
-            .disable_drag_drop_handler()
-            .on_download(|_window, _event| {
-                println!("Download triggered!");
-                // Allow all downloads - they will go to default Downloads folder
-                true
+            .on_page_load(|window, payload| {
+                // A new page means the previous page's listeners are gone
+                if payload.event() == PageLoadEvent::Started {
+                    if let Some(queue) = window.try_state::<FrontendQueue>() {
+                        queue.mark_not_ready();
+                    }
+                    upload::abandon_plugin_uploads(window.app_handle());
+                }
             })
+            .on_download(download::handle_download)
             .on_navigation(move |url| {
                 let url_str = url.as_str();
 
@@ -567,8 +655,26 @@ pub fn run() {
                 true
             });
 
+            let win_builder = match profile_data_directory(&app_handle) {
+                Some(data_directory) => win_builder.data_directory(data_directory),
+                None => win_builder,
+            };
+
             let window = win_builder.build().unwrap();
 
+            // Files dropped onto the main window are uploaded natively, the
+            // dashboard only picks the target (Inbox or Vault). Uploads go
+            // through a queue persisted across restarts. Duplicates of
+            // earlier uploads are skipped and images are preprocessed right
+            // before they are uploaded. Archives report progress per batch.
+            duplicates::setup(&app_handle);
+            preprocess::setup(&app_handle);
+            app.manage(upload::UploadSessionState::default());
+            upload::setup(&app_handle);
+            archive::setup(&app_handle);
+            app.manage(drag_drop::DropStateHandle::default());
+            drag_drop::attach(&window);
+
             // Listen for search window state events from the frontend
             let search_state_for_events = search_state.clone();
             let app_handle_for_events = app_handle.clone();
@@ -589,6 +695,15 @@ pub fn run() {
                 }
             });
 
+            // The dashboard emits this once its deep link listener is registered
+            let app_handle_for_ready = app_handle.clone();
+            window.listen("frontend-ready", move |_event| {
+                println!("📥 Event received: frontend-ready");
+                if let Some(queue) = app_handle_for_ready.try_state::<FrontendQueue>() {
+                    queue.mark_ready(&app_handle_for_ready);
+                }
+            });
+
             // Listen for search window close requests from the frontend
             let app_handle_for_close = app_handle.clone();
             window.listen("search-window-close-requested", move |_event| {
@@ -619,8 +734,7 @@ pub fn run() {
             // This prevents interference with the login flow
 
             // Set the default app menu to restore the Midday menu
-            let app_menu = Menu::default(app.handle())?;
-            app.set_menu(app_menu)?;
+            app.set_menu(app_menu(app.handle())?)?;
 
             // Setup simple system tray for search toggle only
             // Load custom tray icon
@@ -634,20 +748,28 @@ pub fn run() {
 
             // Create tray menu
             let check_updates_item = MenuItem::with_id(app, "check_updates", "Check for Updates...", true, None::<&str>)?;
-            let tray_menu = Menu::with_items(app, &[&check_updates_item])?;
+            let upload_clipboard_item = MenuItem::with_id(app, "upload_clipboard", "Upload Clipboard to Inbox", true, None::<&str>)?;
+            let watch_status_item = MenuItem::with_id(app, "watch_status", "Not watching any folders", false, None::<&str>)?;
+            let quit_item = MenuItem::with_id(app, "quit", "Quit Midday", true, None::<&str>)?;
+            let separator = PredefinedMenuItem::separator(app)?;
+            let tray_menu = Menu::with_items(app, &[&watch_status_item, &upload_clipboard_item, &separator, &check_updates_item, &quit_item])?;
+            app.manage(folder_watch::WatchTrayItem(watch_status_item));
+            app.manage(updates::UpdateTrayItem(check_updates_item));
 
             let _tray = TrayIconBuilder::new()
                 .icon(tray_icon)
                 .menu(&tray_menu)
                 .show_menu_on_left_click(false)
+                // Gets the events of the app menu too, like its Quit item
                 .on_menu_event(|app, event| {
                     println!("🔧 Tray menu event triggered: {:?}", event.id);
                     if event.id == "check_updates" {
-                        println!("🔧 Calling check_for_updates...");
-                        let app_handle = app.clone();
-                        tauri::async_runtime::spawn(async move {
-                            let _ = check_for_updates(app_handle).await;
-                        });
+                        updates::tray_clicked(app);
+                    } else if event.id == "upload_clipboard" {
+                        clipboard::upload_clipboard(app);
+                    } else if event.id == "quit" {
+                        // Closing windows only hides them, this really quits
+                        app.exit(0);
                     }
                 })
                 .on_tray_icon_event(move |tray, event| {
@@ -668,6 +790,16 @@ pub fn run() {
                 })
                 .build(app)?;
 
+            // Upload new receipts from watched folders, even while all windows are hidden
+            if let Err(e) = folder_watch::setup(&app_handle) {
+                eprintln!("👀 Failed to start folder watch: {}", e);
+            }
+
+            // Run the command this instance was launched with, e.g. `midday search`
+            if let Some(command) = startup_command {
+                cli::run_command(&app_handle, command);
+            }
+
             Ok(())
         })
         .build(tauri::generate_context!())
@@ -679,7 +811,9 @@ pub fn run() {
                     let _ = main_window.set_focus();
                 }
             }
-            tauri::RunEvent::ExitRequested { api, .. } => {
+            // The last window closed. Quitting, restarting into an update and
+            // the OS ending the app have a code or skip this, and go ahead
+            tauri::RunEvent::ExitRequested { api, code: None, .. } => {
                 // Prevent app from quitting to keep global shortcuts working
                 api.prevent_exit();
                 
@@ -691,6 +825,9 @@ pub fn run() {
                     let _ = search_window.hide();
                 }
             }
+            // Every real quit ends here; install an update the user chose to
+            // install on quit
+            tauri::RunEvent::Exit => updates::install_on_exit(app_handle),
             _ => {}
         });
 }
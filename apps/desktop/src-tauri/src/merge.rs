@@ -0,0 +1,266 @@
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref};
+use std::cmp::Ordering;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::download;
+use crate::preprocess;
+use crate::upload::{self, UploadSource, UploadTarget};
+
+/// Every page is scaled to this width, A4 at 200 dpi, which keeps receipts
+/// readable for OCR without making the PDF huge.
+const PAGE_WIDTH_PX: u32 = 1654;
+/// A4 width in points. Page heights follow each image's aspect ratio.
+const PAGE_WIDTH_PT: f32 = 595.0;
+const JPEG_QUALITY: u8 = 85;
+
+const IMAGE_TYPES: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "heic", "heif", "tif", "tiff", "bmp", "gif",
+];
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_TYPES.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Compare file names so that `IMG_2` comes before `IMG_10`, which is the
+/// order phones and scanners number pages in.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut x_digits = String::new();
+                while let Some(c) = a.next_if(|c| c.is_ascii_digit()) {
+                    x_digits.push(c);
+                }
+                let mut y_digits = String::new();
+                while let Some(c) = b.next_if(|c| c.is_ascii_digit()) {
+                    y_digits.push(c);
+                }
+
+                let x_trimmed = x_digits.trim_start_matches('0');
+                let y_trimmed = y_digits.trim_start_matches('0');
+                let ordering = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// Put pages in file name order.
+pub fn sort_pages(paths: &mut [PathBuf]) {
+    paths.sort_by(|a, b| {
+        let a = a.file_name().unwrap_or_default().to_string_lossy();
+        let b = b.file_name().unwrap_or_default().to_string_lossy();
+        natural_cmp(&a, &b)
+    });
+}
+
+/// Merge images into a single PDF, one page per image in file name order,
+/// and queue it for upload. Returns the upload id.
+pub async fn merge_and_enqueue(
+    app: &tauri::AppHandle,
+    mut paths: Vec<PathBuf>,
+    target: UploadTarget,
+    folder: Vec<String>,
+    file_name: Option<String>,
+    source: UploadSource,
+) -> Result<String, String> {
+    if paths.is_empty() {
+        return Err("No images to merge".to_string());
+    }
+    if let Some(path) = paths.iter().find(|path| !is_image(path)) {
+        return Err(format!("{:?} is not an image", path));
+    }
+    sort_pages(&mut paths);
+
+    let file_name = file_name
+        .map(|name| {
+            if name.to_lowercase().ends_with(".pdf") {
+                name
+            } else {
+                format!("{}.pdf", name)
+            }
+        })
+        .unwrap_or_else(|| {
+            let stem = paths[0]
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "merged".to_string());
+            format!("{}.pdf", stem)
+        });
+
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?
+        .join("merged");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let output = download::unique_path(&dir, &download::safe_file_name(&file_name));
+
+    let page_count = paths.len();
+    let output_path = output.clone();
+    tauri::async_runtime::spawn_blocking(move || write_pdf(&paths, &output_path))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    println!("📑 Merged {} images into {:?}", page_count, output);
+    upload::enqueue_temporary(app, output, target, folder, source)
+        .ok_or_else(|| "Failed to queue merged PDF".to_string())
+}
+
+fn write_pdf(paths: &[PathBuf], output: &Path) -> Result<(), String> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let image_name = Name(b"Im1");
+
+    // Three objects per page: the page, its image and its content stream
+    let page_ids: Vec<Ref> = (0..paths.len())
+        .map(|index| Ref::new(3 + index as i32 * 3))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+
+    for (path, &page_id) in paths.iter().zip(&page_ids) {
+        let image_id = Ref::new(page_id.get() + 1);
+        let content_id = Ref::new(page_id.get() + 2);
+
+        let image = preprocess::open_image(path)?;
+        let height_px = (PAGE_WIDTH_PX as f32 * image.height() as f32 / image.width() as f32)
+            .round()
+            .max(1.0) as u32;
+        let image = image::DynamicImage::ImageRgb8(image.to_rgb8()).resize_exact(
+            PAGE_WIDTH_PX,
+            height_px,
+            image::imageops::FilterType::Lanczos3,
+        );
+
+        let mut jpeg = Cursor::new(Vec::new());
+        image
+            .write_to(&mut jpeg, image::ImageOutputFormat::Jpeg(JPEG_QUALITY))
+            .map_err(|e| format!("Failed to encode {:?}: {}", path, e))?;
+
+        let page_height = PAGE_WIDTH_PT * height_px as f32 / PAGE_WIDTH_PX as f32;
+
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH_PT, page_height));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().x_objects().pair(image_name, image_id);
+        page.finish();
+
+        let mut xobject = pdf.image_xobject(image_id, jpeg.get_ref());
+        xobject.filter(Filter::DctDecode);
+        xobject.width(PAGE_WIDTH_PX as i32);
+        xobject.height(height_px as i32);
+        xobject.color_space().device_rgb();
+        xobject.bits_per_component(8);
+        xobject.finish();
+
+        let mut content = Content::new();
+        content.save_state();
+        content.transform([PAGE_WIDTH_PT, 0.0, 0.0, page_height, 0.0, 0.0]);
+        content.x_object(image_name);
+        content.restore_state();
+        pdf.stream(content_id, &content.finish());
+    }
+
+    std::fs::write(output, pdf.finish()).map_err(|e| format!("Failed to write {:?}: {}", output, e))
+}
+
+/// Combine images, e.g. the pages of a paper invoice photographed one by
+/// one, into a single PDF and upload it as one document. `folder` is the
+/// Vault folder path, if any. Returns the upload id.
+#[tauri::command]
+pub async fn merge_to_pdf(
+    app: tauri::AppHandle,
+    paths: Vec<PathBuf>,
+    target: Option<UploadTarget>,
+    folder: Option<Vec<String>>,
+    file_name: Option<String>,
+) -> Result<String, String> {
+    merge_and_enqueue(
+        &app,
+        paths,
+        target.unwrap_or(UploadTarget::Inbox),
+        folder.unwrap_or_default(),
+        file_name,
+        UploadSource::Drop,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(natural_cmp("IMG_2.jpg", "IMG_10.jpg"), Ordering::Less);
+        assert_eq!(natural_cmp("IMG_10.jpg", "IMG_9.jpg"), Ordering::Greater);
+        assert_eq!(
+            natural_cmp("scan 2 page 3", "scan 2 page 12"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn leading_zeros_are_ignored() {
+        assert_eq!(natural_cmp("page007.png", "page7.png"), Ordering::Equal);
+        assert_eq!(natural_cmp("page002.png", "page10.png"), Ordering::Less);
+        assert_eq!(natural_cmp("0", "00"), Ordering::Equal);
+    }
+
+    #[test]
+    fn letters_ignore_case() {
+        assert_eq!(natural_cmp("img_1.JPG", "IMG_1.jpg"), Ordering::Equal);
+        assert_eq!(natural_cmp("a.jpg", "B.jpg"), Ordering::Less);
+        assert_eq!(natural_cmp("Receipt", "receipt 2"), Ordering::Less);
+    }
+
+    #[test]
+    fn sorts_pages_by_file_name() {
+        let mut paths = vec![
+            PathBuf::from("/scans/IMG_10.jpg"),
+            PathBuf::from("/tmp/IMG_2.jpg"),
+            PathBuf::from("/scans/img_1.JPG"),
+            PathBuf::from("/scans/IMG_003.jpg"),
+        ];
+        sort_pages(&mut paths);
+
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/scans/img_1.JPG"),
+                PathBuf::from("/tmp/IMG_2.jpg"),
+                PathBuf::from("/scans/IMG_003.jpg"),
+                PathBuf::from("/scans/IMG_10.jpg"),
+            ]
+        );
+    }
+}
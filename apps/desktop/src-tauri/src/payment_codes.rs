@@ -0,0 +1,556 @@
+//! Parsers for the payment QR codes printed on invoices: the Swiss QR-bill
+//! (SIX Swiss Payment Standards, version 2.x) and the EPC069-12 SEPA credit
+//! transfer code, also known as GiroCode.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaymentCodeKind {
+    SwissQrBill,
+    Epc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceType {
+    /// 27 digit QR reference, Swiss QR-IBANs only
+    Qrr,
+    /// ISO 11649 creditor reference, starts with `RF`
+    Scor,
+}
+
+/// Name and address of the creditor or debtor.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Party {
+    pub name: String,
+    /// Street and town lines, ready to display
+    pub address_lines: Vec<String>,
+    /// ISO 3166-1 alpha-2
+    pub country: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentCode {
+    pub kind: PaymentCodeKind,
+    /// Without spaces
+    pub iban: String,
+    pub bic: Option<String>,
+    pub creditor: Party,
+    pub debtor: Option<Party>,
+    /// `None` when the payer fills in the amount
+    pub amount: Option<f64>,
+    pub currency: String,
+    pub reference_type: Option<ReferenceType>,
+    pub reference: Option<String>,
+    /// Free text for the payer, e.g. the invoice number
+    pub message: Option<String>,
+    /// EPC purpose code, e.g. `GDDS`
+    pub purpose: Option<String>,
+    /// Structured billing information (Swiss `//S1/...` format)
+    pub billing_information: Option<String>,
+}
+
+/// Parse a decoded QR code. Returns `None` for QR codes that aren't payment
+/// codes and an error for payment codes that are malformed.
+pub fn parse(payload: &str) -> Option<Result<PaymentCode, String>> {
+    let lines: Vec<&str> = payload
+        .trim_start_matches('\u{feff}')
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .collect();
+
+    match lines.first().map(|line| line.trim()) {
+        Some("SPC") => Some(parse_swiss(&lines)),
+        Some("BCD") => Some(parse_epc(&lines)),
+        _ => None,
+    }
+}
+
+fn field<'a>(lines: &[&'a str], index: usize) -> Option<&'a str> {
+    lines
+        .get(index)
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+}
+
+fn owned(value: Option<&str>) -> Option<String> {
+    value.map(str::to_string)
+}
+
+/// Swiss Implementation Guidelines for the QR-bill, section 4.3.
+fn parse_swiss(lines: &[&str]) -> Result<PaymentCode, String> {
+    let version = field(lines, 1).unwrap_or_default();
+    if !version.starts_with("02") {
+        return Err(format!("Unsupported QR-bill version: {}", version));
+    }
+    if field(lines, 2) != Some("1") {
+        return Err("Unsupported QR-bill character set".to_string());
+    }
+    // Everything up to the trailer is mandatory, even when empty
+    if lines.len() < 31 || field(lines, 30) != Some("EPD") {
+        return Err("Incomplete QR-bill".to_string());
+    }
+
+    let iban = normalize_iban(field(lines, 3).unwrap_or_default());
+    if !iban_is_valid(&iban) {
+        return Err(format!("Invalid IBAN: {}", iban));
+    }
+
+    let creditor = swiss_party(lines, 4).ok_or("QR-bill without creditor")?;
+    let debtor = swiss_party(lines, 20);
+
+    let amount = match field(lines, 18) {
+        Some(amount) => Some(
+            amount
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid amount: {}", amount))?,
+        ),
+        None => None,
+    };
+    let currency = field(lines, 19).unwrap_or_default().to_string();
+    if currency != "CHF" && currency != "EUR" {
+        return Err(format!("Invalid currency: {}", currency));
+    }
+
+    let reference = field(lines, 28).map(|reference| reference.replace(' ', ""));
+    let reference_type = match field(lines, 27) {
+        Some("QRR") => {
+            let reference = reference.as_deref().unwrap_or_default();
+            if !qr_reference_is_valid(reference) {
+                return Err(format!("Invalid QR reference: {}", reference));
+            }
+            Some(ReferenceType::Qrr)
+        }
+        Some("SCOR") => {
+            let reference = reference.as_deref().unwrap_or_default();
+            if !creditor_reference_is_valid(reference) {
+                return Err(format!("Invalid creditor reference: {}", reference));
+            }
+            Some(ReferenceType::Scor)
+        }
+        Some("NON") => None,
+        other => {
+            return Err(format!(
+                "Invalid reference type: {}",
+                other.unwrap_or_default()
+            ));
+        }
+    };
+
+    Ok(PaymentCode {
+        kind: PaymentCodeKind::SwissQrBill,
+        iban,
+        bic: None,
+        creditor,
+        debtor,
+        amount,
+        currency,
+        reference: reference_type.and(reference),
+        reference_type,
+        message: owned(field(lines, 29)),
+        purpose: None,
+        billing_information: owned(field(lines, 31)),
+    })
+}
+
+/// Seven address fields starting at `start`: type, name, street or line 1,
+/// building number or line 2, postal code, town, country.
+fn swiss_party(lines: &[&str], start: usize) -> Option<Party> {
+    let name = field(lines, start + 1)?.to_string();
+    let address_type = field(lines, start);
+    let line_1 = field(lines, start + 2);
+    let line_2 = field(lines, start + 3);
+    let postal_code = field(lines, start + 4);
+    let town = field(lines, start + 5);
+
+    let address_lines = match address_type {
+        // Combined address: two free lines
+        Some("K") => [line_1, line_2]
+            .into_iter()
+            .flatten()
+            .map(str::to_string)
+            .collect(),
+        // Structured address: street, building number, postal code, town
+        _ => {
+            let street = [line_1, line_2]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let place = [postal_code, town]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+            [street, place]
+                .into_iter()
+                .filter(|line| !line.is_empty())
+                .collect()
+        }
+    };
+
+    Some(Party {
+        name,
+        address_lines,
+        country: owned(field(lines, start + 6)),
+    })
+}
+
+/// EPC069-12 "Quick Response Code: Guidelines to Enable Data Capture for the
+/// Initiation of a SEPA Credit Transfer", version 001 and 002.
+fn parse_epc(lines: &[&str]) -> Result<PaymentCode, String> {
+    let version = field(lines, 1).unwrap_or_default();
+    if version != "001" && version != "002" {
+        return Err(format!("Unsupported EPC version: {}", version));
+    }
+    if field(lines, 3) != Some("SCT") {
+        return Err("Not a SEPA credit transfer".to_string());
+    }
+
+    let bic = owned(field(lines, 4));
+    // Version 001 requires the BIC, 002 made it optional inside the EEA
+    if version == "001" && bic.is_none() {
+        return Err("EPC version 001 requires a BIC".to_string());
+    }
+
+    let name = field(lines, 5).ok_or("EPC code without beneficiary")?;
+    let iban = normalize_iban(field(lines, 6).unwrap_or_default());
+    if !iban_is_valid(&iban) {
+        return Err(format!("Invalid IBAN: {}", iban));
+    }
+
+    let (currency, amount) = match field(lines, 7) {
+        Some(value) => {
+            let (currency, amount) = value.split_at(value.len().min(3));
+            let amount = amount
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid amount: {}", value))?;
+            (currency.to_string(), Some(amount))
+        }
+        None => ("EUR".to_string(), None),
+    };
+    if currency != "EUR" {
+        return Err(format!("Invalid currency: {}", currency));
+    }
+
+    let reference = field(lines, 9).map(|reference| reference.replace(' ', ""));
+    let reference_type = match &reference {
+        Some(reference) if creditor_reference_is_valid(reference) => Some(ReferenceType::Scor),
+        Some(reference) => return Err(format!("Invalid creditor reference: {}", reference)),
+        None => None,
+    };
+
+    Ok(PaymentCode {
+        kind: PaymentCodeKind::Epc,
+        iban,
+        bic,
+        creditor: Party {
+            name: name.to_string(),
+            ..Party::default()
+        },
+        debtor: None,
+        amount,
+        currency,
+        reference_type,
+        reference,
+        message: owned(field(lines, 10)),
+        purpose: owned(field(lines, 8)),
+        billing_information: None,
+    })
+}
+
+fn normalize_iban(iban: &str) -> String {
+    iban.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// ISO 7064 MOD 97-10 over the value with its first four characters moved to
+/// the end and letters replaced by 10-35. Used by IBANs and RF references.
+fn mod97_is_valid(value: &str) -> bool {
+    if value.len() < 5 || !value.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+
+    let rearranged = value[4..].chars().chain(value[..4].chars());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let Some(digit) = c.to_digit(36) else {
+            return false;
+        };
+        remainder = if digit < 10 {
+            (remainder * 10 + digit) % 97
+        } else {
+            (remainder * 100 + digit) % 97
+        };
+    }
+    remainder == 1
+}
+
+pub fn iban_is_valid(iban: &str) -> bool {
+    iban.is_ascii()
+        && iban.len() >= 15
+        && iban.len() <= 34
+        && iban[..2].chars().all(|c| c.is_ascii_uppercase())
+        && mod97_is_valid(iban)
+}
+
+fn creditor_reference_is_valid(reference: &str) -> bool {
+    reference.len() <= 25
+        && reference.to_uppercase().starts_with("RF")
+        && mod97_is_valid(&reference.to_uppercase())
+}
+
+/// 27 digits, the last one a recursive modulo 10 check digit.
+fn qr_reference_is_valid(reference: &str) -> bool {
+    const TABLE: [u32; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+
+    if reference.len() != 27 || !reference.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    let digits: Vec<u32> = reference.chars().filter_map(|c| c.to_digit(10)).collect();
+    let carry = digits[..26]
+        .iter()
+        .fold(0, |carry, digit| TABLE[((carry + digit) % 10) as usize]);
+    (10 - carry) % 10 == digits[26]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swiss_payload(lines: &[&str]) -> String {
+        lines.join("\r\n")
+    }
+
+    /// Example 1 from the Swiss Implementation Guidelines: QR-IBAN with a QR
+    /// reference, structured addresses and billing information.
+    const SWISS_QRR: &[&str] = &[
+        "SPC",
+        "0200",
+        "1",
+        "CH4431999123000889012",
+        "S",
+        "Robert Schneider AG",
+        "Rue du Lac",
+        "1268",
+        "2501",
+        "Biel",
+        "CH",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "",
+        "1949.75",
+        "CHF",
+        "S",
+        "Pia-Maria Rutschmann-Schnyder",
+        "Grosse Marktgasse",
+        "28",
+        "9400",
+        "Rorschach",
+        "CH",
+        "QRR",
+        "210000000003139471430009017",
+        "Order of 15.10.2020",
+        "EPD",
+        "//S1/10/1234/11/201021/30/102673386/32/7.7/40/0:30",
+        "Name AV1: UV;UltraPay005;12345",
+    ];
+
+    #[test]
+    fn parses_swiss_qr_bill_with_qr_reference() {
+        let code = parse(&swiss_payload(SWISS_QRR)).unwrap().unwrap();
+
+        assert_eq!(code.kind, PaymentCodeKind::SwissQrBill);
+        assert_eq!(code.iban, "CH4431999123000889012");
+        assert_eq!(code.amount, Some(1949.75));
+        assert_eq!(code.currency, "CHF");
+        assert_eq!(code.reference_type, Some(ReferenceType::Qrr));
+        assert_eq!(
+            code.reference.as_deref(),
+            Some("210000000003139471430009017")
+        );
+        assert_eq!(code.message.as_deref(), Some("Order of 15.10.2020"));
+        assert_eq!(
+            code.billing_information.as_deref(),
+            Some("//S1/10/1234/11/201021/30/102673386/32/7.7/40/0:30")
+        );
+        assert_eq!(
+            code.creditor,
+            Party {
+                name: "Robert Schneider AG".to_string(),
+                address_lines: vec!["Rue du Lac 1268".to_string(), "2501 Biel".to_string()],
+                country: Some("CH".to_string()),
+            }
+        );
+        assert_eq!(code.debtor.unwrap().name, "Pia-Maria Rutschmann-Schnyder");
+    }
+
+    #[test]
+    fn parses_swiss_qr_bill_without_amount_or_debtor() {
+        let mut lines = SWISS_QRR.to_vec();
+        lines[3] = "CH58 0079 1123 0008 8901 2";
+        lines[4] = "K";
+        lines[6] = "Rue du Lac 1268";
+        lines[7] = "2501 Biel";
+        lines[8] = "";
+        lines[9] = "";
+        lines[18] = "";
+        lines[19] = "EUR";
+        lines[20..27].fill("");
+        lines[27] = "SCOR";
+        lines[28] = "RF18 5390 0754 7034";
+        lines[29] = "";
+        // Without the optional billing information and alternative schemes
+        lines.truncate(31);
+
+        let code = parse(&lines.join("\n")).unwrap().unwrap();
+
+        assert_eq!(code.iban, "CH5800791123000889012");
+        assert_eq!(code.amount, None);
+        assert_eq!(code.currency, "EUR");
+        assert_eq!(code.debtor, None);
+        assert_eq!(code.reference_type, Some(ReferenceType::Scor));
+        assert_eq!(code.reference.as_deref(), Some("RF18539007547034"));
+        assert_eq!(code.message, None);
+        assert_eq!(code.billing_information, None);
+        assert_eq!(
+            code.creditor.address_lines,
+            vec!["Rue du Lac 1268".to_string(), "2501 Biel".to_string()]
+        );
+    }
+
+    #[test]
+    fn parses_swiss_qr_bill_without_reference() {
+        let mut lines = SWISS_QRR.to_vec();
+        lines[3] = "CH5800791123000889012";
+        lines[27] = "NON";
+        lines[28] = "";
+
+        let code = parse(&swiss_payload(&lines)).unwrap().unwrap();
+        assert_eq!(code.reference_type, None);
+        assert_eq!(code.reference, None);
+    }
+
+    #[test]
+    fn rejects_invalid_swiss_qr_bills() {
+        let mut bad_iban = SWISS_QRR.to_vec();
+        bad_iban[3] = "CH4431999123000889013";
+        assert!(parse(&swiss_payload(&bad_iban)).unwrap().is_err());
+
+        let mut bad_reference = SWISS_QRR.to_vec();
+        bad_reference[28] = "210000000003139471430009018";
+        assert!(parse(&swiss_payload(&bad_reference)).unwrap().is_err());
+
+        let mut bad_currency = SWISS_QRR.to_vec();
+        bad_currency[19] = "USD";
+        assert!(parse(&swiss_payload(&bad_currency)).unwrap().is_err());
+
+        let truncated = &SWISS_QRR[..20];
+        assert!(parse(&swiss_payload(truncated)).unwrap().is_err());
+
+        let mut old_version = SWISS_QRR.to_vec();
+        old_version[1] = "0100";
+        assert!(parse(&swiss_payload(&old_version)).unwrap().is_err());
+    }
+
+    #[test]
+    fn parses_epc_version_002() {
+        let payload = "BCD\n002\n1\nSCT\n\nFranz Mustermänner\nDE89 3704 0044 0532 0130 00\nEUR12.3\nGDDS\n\nInvoice 2024-117\n";
+        let code = parse(payload).unwrap().unwrap();
+
+        assert_eq!(code.kind, PaymentCodeKind::Epc);
+        assert_eq!(code.iban, "DE89370400440532013000");
+        assert_eq!(code.bic, None);
+        assert_eq!(code.creditor.name, "Franz Mustermänner");
+        assert_eq!(code.amount, Some(12.3));
+        assert_eq!(code.currency, "EUR");
+        assert_eq!(code.purpose.as_deref(), Some("GDDS"));
+        assert_eq!(code.reference, None);
+        assert_eq!(code.message.as_deref(), Some("Invoice 2024-117"));
+    }
+
+    #[test]
+    fn parses_epc_version_001_with_creditor_reference() {
+        let payload = "BCD\r\n001\r\n1\r\nSCT\r\nGEBABEBB\r\nRed Cross of Belgium\r\nBE72000000001616\r\nEUR1\r\n\r\nRF18539007547034";
+        let code = parse(payload).unwrap().unwrap();
+
+        assert_eq!(code.bic.as_deref(), Some("GEBABEBB"));
+        assert_eq!(code.amount, Some(1.0));
+        assert_eq!(code.reference_type, Some(ReferenceType::Scor));
+        assert_eq!(code.reference.as_deref(), Some("RF18539007547034"));
+        assert_eq!(code.message, None);
+    }
+
+    #[test]
+    fn parses_epc_without_amount() {
+        let payload = "BCD\n002\n1\nSCT\n\nMidday Labs AB\nNL91ABNA0417164300";
+        let code = parse(payload).unwrap().unwrap();
+
+        assert_eq!(code.amount, None);
+        assert_eq!(code.currency, "EUR");
+    }
+
+    #[test]
+    fn rejects_invalid_epc_codes() {
+        // Version 001 without BIC
+        assert!(
+            parse("BCD\n001\n1\nSCT\n\nName\nBE72000000001616")
+                .unwrap()
+                .is_err()
+        );
+        // Instant transfer identification
+        assert!(
+            parse("BCD\n002\n1\nINST\n\nName\nBE72000000001616")
+                .unwrap()
+                .is_err()
+        );
+        // Bad checksum
+        assert!(
+            parse("BCD\n002\n1\nSCT\n\nName\nBE72000000001617")
+                .unwrap()
+                .is_err()
+        );
+        // Non-euro amount
+        assert!(
+            parse("BCD\n002\n1\nSCT\n\nName\nBE72000000001616\nUSD5")
+                .unwrap()
+                .is_err()
+        );
+        // Bad creditor reference
+        assert!(
+            parse("BCD\n002\n1\nSCT\n\nName\nBE72000000001616\nEUR5\n\nRF48539007547034")
+                .unwrap()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn ignores_other_qr_codes() {
+        assert!(parse("https://midday.ai").is_none());
+        assert!(parse("").is_none());
+        assert!(parse("WIFI:S:office;T:WPA;P:secret;;").is_none());
+    }
+
+    #[test]
+    fn validates_check_digits() {
+        assert!(iban_is_valid("CH9300762011623852957"));
+        assert!(!iban_is_valid("CH9300762011623852958"));
+        assert!(!iban_is_valid("ch9300762011623852957"));
+        assert!(!iban_is_valid("A€€€€€"));
+        assert!(!iban_is_valid("CHé300762011623852957"));
+        assert!(qr_reference_is_valid("210000000003139471430009017"));
+        assert!(!qr_reference_is_valid("21000000000313947143000901"));
+        assert!(creditor_reference_is_valid("RF18539007547034"));
+        assert!(!creditor_reference_is_valid("RF48539007547034"));
+    }
+}
@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::store;
+
+const SETTINGS_FILE: &str = "image-settings.json";
+
+/// Processed copies live here until their upload finishes.
+const CACHE_DIR: &str = "preprocessed";
+
+/// Formats uploaded as they are (after orientation and downscaling). Anything
+/// else that is an image gets converted to JPEG.
+const KEEP_FORMATS: &[&str] = &["jpg", "jpeg", "png"];
+const CONVERT_FORMATS: &[&str] = &["heic", "heif", "webp", "tif", "tiff", "bmp"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImageSettings {
+    /// When off, images are uploaded untouched
+    pub enabled: bool,
+    /// Longest edge in pixels, larger images are scaled down
+    pub max_edge: u32,
+    /// JPEG quality, 1-100
+    pub quality: u8,
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        ImageSettings {
+            enabled: true,
+            max_edge: 2400,
+            quality: 82,
+        }
+    }
+}
+
+pub type ImageSettingsState = Mutex<ImageSettings>;
+
+/// A processed copy of an image, uploaded instead of the original.
+pub struct Prepared {
+    pub path: PathBuf,
+    pub file_name: String,
+}
+
+/// Load the settings and remove copies left behind by uploads that were
+/// interrupted by a quit.
+pub fn setup(app: &tauri::AppHandle) {
+    let settings: ImageSettings = store::config_file(app, SETTINGS_FILE)
+        .map(|path| store::load(&path))
+        .unwrap_or_default();
+    app.manage::<ImageSettingsState>(Mutex::new(settings));
+
+    if let Ok(dir) = cache_dir(app) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+fn cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(CACHE_DIR))
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Prepare an image for upload: apply the EXIF orientation, scale it down,
+/// recompress it and convert formats the inbox can't read to JPEG. Re-encoding
+/// drops all metadata, including the location. Returns `None` when the file
+/// should be uploaded as it is, including when it can't be processed.
+pub async fn prepare(
+    app: &tauri::AppHandle,
+    id: &str,
+    path: &Path,
+    file_name: &str,
+) -> Option<Prepared> {
+    let settings = app
+        .try_state::<ImageSettingsState>()?
+        .lock()
+        .unwrap()
+        .clone();
+    let extension = extension(path);
+    let convert = CONVERT_FORMATS.contains(&extension.as_str());
+    if !settings.enabled || !(convert || KEEP_FORMATS.contains(&extension.as_str())) {
+        return None;
+    }
+
+    let dir = cache_dir(app).ok()?;
+    let source = path.to_path_buf();
+    let id = id.to_string();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        process(&source, &extension, &dir, &id, &settings)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+
+    match result {
+        Ok(Some((path, output_extension))) => {
+            let stem = Path::new(file_name)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| file_name.to_string());
+            Some(Prepared {
+                path,
+                file_name: format!("{}.{}", stem, output_extension),
+            })
+        }
+        Ok(None) => None,
+        Err(e) => {
+            eprintln!("🖼️ Uploading {} unprocessed: {}", file_name, e);
+            None
+        }
+    }
+}
+
+/// What the EXIF block says about an image.
+#[derive(Default)]
+struct ExifInfo {
+    orientation: u32,
+    has_location: bool,
+}
+
+/// Returns the processed file and its extension, or `None` when the image
+/// is fine as it is.
+fn process(
+    source: &Path,
+    extension: &str,
+    dir: &Path,
+    id: &str,
+    settings: &ImageSettings,
+) -> Result<Option<(PathBuf, &'static str)>, String> {
+    // HEIC needs a platform decoder, the converted JPEG still carries the
+    // EXIF block so it goes through the same steps below
+    let converted = if matches!(extension, "heic" | "heif") {
+        let output = dir.join(format!("{}-heic.jpg", id));
+        convert_heic(source, &output)?;
+        Some(output)
+    } else {
+        None
+    };
+    let input = converted.as_deref().unwrap_or(source);
+    let result = process_file(input, extension, dir, id, settings, converted.is_some());
+    if let Some(converted) = &converted {
+        let _ = std::fs::remove_file(converted);
+    }
+    result
+}
+
+fn process_file(
+    input: &Path,
+    extension: &str,
+    dir: &Path,
+    id: &str,
+    settings: &ImageSettings,
+    converted: bool,
+) -> Result<Option<(PathBuf, &'static str)>, String> {
+    let exif = read_exif(input);
+    let (width, height) =
+        image::image_dimensions(input).map_err(|e| format!("Failed to read image: {}", e))?;
+    let needs_resize = width.max(height) > settings.max_edge;
+    let needs_conversion = converted || !KEEP_FORMATS.contains(&extension);
+
+    // Small, upright images without a location are left alone instead of
+    // being recompressed for nothing
+    if !needs_resize && !needs_conversion && exif.orientation <= 1 && !exif.has_location {
+        return Ok(None);
+    }
+
+    let mut image = image::open(input).map_err(|e| format!("Failed to read image: {}", e))?;
+    image = apply_orientation(image, exif.orientation);
+    if needs_resize {
+        image = image.resize(
+            settings.max_edge,
+            settings.max_edge,
+            image::imageops::FilterType::Lanczos3,
+        );
+    }
+
+    // PNGs are usually screenshots, where JPEG artifacts hurt OCR
+    let (bytes, output_extension) = if extension == "png" {
+        let mut png = Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .map_err(|e| format!("Failed to encode image: {}", e))?;
+        (png.into_inner(), "png")
+    } else {
+        let mut jpeg = Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(
+                &mut jpeg,
+                image::ImageOutputFormat::Jpeg(settings.quality.clamp(1, 100)),
+            )
+            .map_err(|e| format!("Failed to encode image: {}", e))?;
+        (jpeg.into_inner(), "jpg")
+    };
+
+    let output = dir.join(format!("{}.{}", id, output_extension));
+    std::fs::write(&output, &bytes).map_err(|e| format!("Failed to write {:?}: {}", output, e))?;
+    println!(
+        "🖼️ Prepared {:?}: {}x{} -> {}x{}, {} bytes",
+        input,
+        width,
+        height,
+        image.width(),
+        image.height(),
+        bytes.len()
+    );
+    Ok(Some((output, output_extension)))
+}
+
+/// Decode any image we accept, HEIC included, upright according to its EXIF
+/// orientation.
+pub fn open_image(path: &Path) -> Result<image::DynamicImage, String> {
+    if matches!(extension(path).as_str(), "heic" | "heif") {
+        let converted = std::env::temp_dir().join(format!(
+            "midday-heic-{}.jpg",
+            crate::upload::next_upload_id()
+        ));
+        convert_heic(path, &converted)?;
+        let image = open_image(&converted);
+        let _ = std::fs::remove_file(&converted);
+        return image;
+    }
+
+    let orientation = read_exif(path).orientation;
+    let image = image::open(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    Ok(apply_orientation(image, orientation))
+}
+
+fn read_exif(path: &Path) -> ExifInfo {
+    let Ok(file) = std::fs::File::open(path) else {
+        return ExifInfo::default();
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file))
+    else {
+        return ExifInfo::default();
+    };
+
+    ExifInfo {
+        orientation: exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0))
+            .unwrap_or(1),
+        has_location: exif
+            .get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY)
+            .is_some(),
+    }
+}
+
+fn apply_orientation(image: image::DynamicImage, orientation: u32) -> image::DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// macOS converts HEIC with `sips`, other platforms with `heif-convert` from
+/// libheif when it is installed. Without it the original is uploaded.
+fn convert_heic(source: &Path, output: &Path) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("sips");
+        command
+            .args(["-s", "format", "jpeg"])
+            .arg(source)
+            .arg("--out")
+            .arg(output);
+        command
+    } else {
+        let mut command = std::process::Command::new("heif-convert");
+        command.args(["-q", "95"]).arg(source).arg(output);
+        command
+    };
+
+    let status = command
+        .stdout(std::process::Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to convert HEIC: {}", e))?;
+    if !status.success() || !output.exists() {
+        return Err(format!("Failed to convert HEIC: {}", status));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_image_settings(state: tauri::State<'_, ImageSettingsState>) -> ImageSettings {
+    state.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_image_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ImageSettingsState>,
+    settings: ImageSettings,
+) -> Result<(), String> {
+    if settings.max_edge < 500 {
+        return Err("The maximum edge must be at least 500 pixels".to_string());
+    }
+
+    store::save(&store::config_file(&app, SETTINGS_FILE)?, &settings)?;
+    *state.lock().unwrap() = settings;
+    Ok(())
+}
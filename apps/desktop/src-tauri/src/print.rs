@@ -0,0 +1,529 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tokio::sync::oneshot;
+
+use crate::download;
+
+/// How long a page may take to load and print to a PDF
+const PDF_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Windows rendering untrusted documents, e.g. email bodies. They can't call
+/// commands and don't navigate away from the document.
+const ISOLATED_LABEL_PREFIX: &str = "render-";
+
+/// Resolved once a PDF has been written, or with the reason it wasn't
+type PrintDone = oneshot::Sender<Result<(), String>>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperSize {
+    #[default]
+    A4,
+    A3,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PaperSize {
+    fn css_name(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A3 => "A3",
+            PaperSize::A5 => "A5",
+            PaperSize::Letter => "letter",
+            PaperSize::Legal => "legal",
+        }
+    }
+
+    /// Width and height in millimetres, portrait
+    fn size_mm(&self) -> (f64, f64) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+            PaperSize::A5 => (148.0, 210.0),
+            PaperSize::Letter => (215.9, 279.4),
+            PaperSize::Legal => (215.9, 355.6),
+        }
+    }
+
+    /// PWG 5101.1 name used by GTK
+    #[cfg(target_os = "linux")]
+    fn gtk_name(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "iso_a4",
+            PaperSize::A3 => "iso_a3",
+            PaperSize::A5 => "iso_a5",
+            PaperSize::Letter => "na_letter",
+            PaperSize::Legal => "na_legal",
+        }
+    }
+}
+
+/// Page margins in millimetres.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Margins {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Margins {
+            top: 10.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrintOptions {
+    #[serde(default)]
+    pub paper_size: PaperSize,
+    #[serde(default)]
+    pub margins: Margins,
+    #[serde(default)]
+    pub landscape: bool,
+    /// Write a PDF here instead of showing the print dialog, for batch use
+    #[serde(default)]
+    pub pdf_path: Option<PathBuf>,
+}
+
+/// `@page` rule applied to HTML pages before printing. PDFs keep their own
+/// page layout.
+fn page_style(options: &PrintOptions) -> String {
+    let Margins {
+        top,
+        right,
+        bottom,
+        left,
+    } = options.margins;
+    format!(
+        "@page {{ size: {}{}; margin: {}mm {}mm {}mm {}mm; }}",
+        options.paper_size.css_name(),
+        if options.landscape { " landscape" } else { "" },
+        top,
+        right,
+        bottom,
+        left
+    )
+}
+
+/// Add the `@page` rule to the page, replacing one from an earlier print.
+fn inject_page_style(webview: &tauri::Webview, options: &PrintOptions) -> Result<(), String> {
+    let css = serde_json::to_string(&page_style(options)).map_err(|e| e.to_string())?;
+    let script = format!(
+        r#"(function () {{
+            var style = document.getElementById("midday-print-style");
+            if (!style) {{
+                style = document.createElement("style");
+                style.id = "midday-print-style";
+                document.head.appendChild(style);
+            }}
+            style.textContent = {};
+        }})();"#,
+        css
+    );
+    webview
+        .eval(script)
+        .map_err(|e| format!("Failed to prepare page for printing: {}", e))
+}
+
+/// Print a webview, with the platform print dialog or straight to a PDF.
+/// `done` is taken by the print operation and resolved when the PDF is
+/// written; it's left in place when printing fails before that.
+fn print_webview(
+    webview: &tauri::Webview,
+    options: &PrintOptions,
+    done: &mut Option<PrintDone>,
+) -> Result<(), String> {
+    inject_page_style(webview, options)?;
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::print(webview, options.clone(), done.take())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if options.pdf_path.is_some() {
+            return macos::create_pdf(webview, options.clone(), done.take());
+        }
+        webview
+            .print()
+            .map_err(|e| format!("Failed to print: {}", e))
+    }
+
+    // WebView2 ignores `window.print()` from the app, so nothing would happen
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = done;
+        Err("Printing is only supported on macOS and Linux".to_string())
+    }
+}
+
+fn main_webview(app: &tauri::AppHandle) -> Result<tauri::WebviewWindow, String> {
+    app.get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())
+}
+
+/// Print what the main window currently shows, e.g. a report.
+#[tauri::command]
+pub async fn print_current_view(
+    app: tauri::AppHandle,
+    options: Option<PrintOptions>,
+) -> Result<(), String> {
+    let window = main_webview(&app)?;
+    let options = options.unwrap_or_default();
+    println!("🖨️ Printing current view");
+
+    if options.pdf_path.is_none() {
+        return print_webview(window.as_ref(), &options, &mut None);
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    print_webview(window.as_ref(), &options, &mut Some(sender))?;
+    wait_for_pdf(receiver).await
+}
+
+async fn wait_for_pdf(receiver: oneshot::Receiver<Result<(), String>>) -> Result<(), String> {
+    match tokio::time::timeout(PDF_TIMEOUT, receiver).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("Printing stopped before the PDF was written".to_string()),
+        Err(_) => Err("Timed out writing the PDF".to_string()),
+    }
+}
+
+/// Print a document, typically an invoice PDF. PDFs are written directly when
+/// `pdfPath` is set, everything else is loaded in a print window first.
+#[tauri::command]
+pub async fn print_url(
+    app: tauri::AppHandle,
+    url: String,
+    options: Option<PrintOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let url = tauri::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let main = main_webview(&app)?;
+
+    let response = download::authenticated_get(main.as_ref(), &url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to load {}: {}", url, e))?;
+
+    let is_pdf = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/pdf"));
+
+    if is_pdf {
+        if let Some(pdf_path) = &options.pdf_path {
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to load {}: {}", url, e))?;
+            write_pdf(pdf_path, &bytes).await?;
+            println!("🖨️ Saved {} to {:?}", url, pdf_path);
+            return Ok(());
+        }
+
+        // WebKitGTK can't display PDFs, hand them to the default viewer instead
+        #[cfg(target_os = "linux")]
+        {
+            use tauri_plugin_opener::OpenerExt;
+
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to load {}: {}", url, e))?;
+            let file_name = download::safe_file_name(
+                url.path_segments()
+                    .and_then(|mut segments| segments.next_back())
+                    .unwrap_or("document.pdf"),
+            );
+            let path = std::env::temp_dir().join("midday-print").join(file_name);
+            write_pdf(&path, &bytes).await?;
+            println!("🖨️ Opening {:?} in the default PDF viewer", path);
+            return app
+                .opener()
+                .open_path(path.to_string_lossy(), None::<&str>)
+                .map_err(|e| format!("Failed to open {:?}: {}", path, e));
+        }
+    }
+
+    drop(response);
+    open_print_window(&app, url, options, false).await
+}
+
+/// Render an HTML document to a PDF without showing it, e.g. the body of an
+/// email. Only possible where pages can be printed to a file (Linux, macOS).
+pub async fn html_to_pdf(
+    app: &tauri::AppHandle,
+    html: &str,
+    pdf_path: &Path,
+) -> Result<(), String> {
+    use base64::Engine;
+
+    if !cfg!(any(target_os = "linux", target_os = "macos")) {
+        return Err("Rendering PDFs is only supported on Linux and macOS".to_string());
+    }
+
+    let url = format!(
+        "data:text/html;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(html)
+    );
+    let url = tauri::Url::parse(&url).map_err(|e| e.to_string())?;
+    let options = PrintOptions {
+        pdf_path: Some(pdf_path.to_path_buf()),
+        ..PrintOptions::default()
+    };
+    open_print_window(app, url, options, true).await
+}
+
+async fn write_pdf(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    tokio::fs::write(path, bytes)
+        .await
+        .map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Whether a webview renders untrusted documents and must not reach the app.
+pub fn is_isolated(label: &str) -> bool {
+    label.starts_with(ISOLATED_LABEL_PREFIX)
+}
+
+/// Load `url` in a separate window and print it once loaded. The window stays
+/// open as a preview, or is hidden and closed once the PDF is written when
+/// `pdfPath` is set. An `isolated` window stays on `url` and can't call
+/// commands.
+async fn open_print_window(
+    app: &tauri::AppHandle,
+    url: tauri::Url,
+    options: PrintOptions,
+    isolated: bool,
+) -> Result<(), String> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let label = format!(
+        "{}{}",
+        if isolated {
+            ISOLATED_LABEL_PREFIX
+        } else {
+            "print-"
+        },
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let silent = options.pdf_path.is_some();
+    // WKWebView writes the page as laid out, so hidden windows get the
+    // paper's width (CSS pixels are 1/96 inch)
+    let (paper_width, paper_height) = options.paper_size.size_mm();
+    let width = if options.landscape {
+        paper_height
+    } else {
+        paper_width
+    };
+    let width = if silent { width * 96.0 / 25.4 } else { 800.0 };
+    let (sender, receiver) = oneshot::channel();
+    // Only the first load prints, later ones are links followed in the preview
+    let sender = Mutex::new(Some(sender));
+
+    let document = url.clone();
+    let mut builder = WebviewWindowBuilder::new(app, &label, WebviewUrl::External(url))
+        .title("Print")
+        .inner_size(width, 1000.0)
+        .visible(!silent)
+        .on_page_load(move |window, payload| {
+            if payload.event() != tauri::webview::PageLoadEvent::Finished {
+                return;
+            }
+            let Some(sender) = sender.lock().unwrap().take() else {
+                return;
+            };
+
+            println!("🖨️ Printing {}", payload.url());
+            let mut done = silent.then_some(sender);
+            if let Err(e) = print_webview(window.as_ref(), &options, &mut done) {
+                eprintln!("🖨️ {}", e);
+                if let Some(done) = done {
+                    let _ = done.send(Err(e));
+                }
+            }
+        })
+        .on_navigation(move |url| !isolated || *url == document);
+
+    if let Some(data_directory) = crate::profile_data_directory(app) {
+        builder = builder.data_directory(data_directory);
+    }
+
+    let window = builder
+        .build()
+        .map_err(|e| format!("Failed to open print window: {}", e))?;
+
+    if !silent {
+        return Ok(());
+    }
+
+    let result = wait_for_pdf(receiver).await;
+    let _ = window.destroy();
+    result
+}
+
+/// WebKitGTK's print operation takes the page setup directly and can print to
+/// a file without showing a dialog.
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{Margins, PrintDone, PrintOptions};
+    use gtk::prelude::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use webkit2gtk::PrintOperationExt;
+
+    pub fn print(
+        webview: &tauri::Webview,
+        options: PrintOptions,
+        done: Option<PrintDone>,
+    ) -> Result<(), String> {
+        webview
+            .with_webview(move |platform| {
+                let webview = platform.inner();
+                let operation = webkit2gtk::PrintOperation::new(&webview);
+
+                let page_setup = gtk::PageSetup::new();
+                page_setup
+                    .set_paper_size(&gtk::PaperSize::new(Some(options.paper_size.gtk_name())));
+                page_setup.set_orientation(if options.landscape {
+                    gtk::PageOrientation::Landscape
+                } else {
+                    gtk::PageOrientation::Portrait
+                });
+                let Margins {
+                    top,
+                    right,
+                    bottom,
+                    left,
+                } = options.margins;
+                page_setup.set_top_margin(top, gtk::Unit::Mm);
+                page_setup.set_right_margin(right, gtk::Unit::Mm);
+                page_setup.set_bottom_margin(bottom, gtk::Unit::Mm);
+                page_setup.set_left_margin(left, gtk::Unit::Mm);
+                operation.set_page_setup(&page_setup);
+
+                // WebKit emits `failed` before `finished`, whichever comes
+                // first resolves `done`
+                let done = Rc::new(RefCell::new(done));
+                let failed = done.clone();
+                operation.connect_failed(move |_, error| {
+                    eprintln!("🖨️ Printing failed: {}", error);
+                    if let Some(done) = failed.borrow_mut().take() {
+                        let _ = done.send(Err(format!("Printing failed: {}", error)));
+                    }
+                });
+
+                match &options.pdf_path {
+                    Some(pdf_path) => {
+                        let settings = gtk::PrintSettings::new();
+                        settings.set_printer("Print to File");
+                        settings.set("output-file-format", Some("pdf"));
+                        let uri = tauri::Url::from_file_path(pdf_path)
+                            .map(|uri| uri.to_string())
+                            .unwrap_or_default();
+                        settings.set("output-uri", Some(&uri));
+                        operation.set_print_settings(&settings);
+
+                        let pdf_path = pdf_path.clone();
+                        operation.connect_finished(move |_| {
+                            if let Some(done) = done.borrow_mut().take() {
+                                println!("🖨️ Saved PDF to {:?}", pdf_path);
+                                let _ = done.send(Ok(()));
+                            }
+                        });
+                        operation.print();
+                    }
+                    None => {
+                        let parent = webview
+                            .toplevel()
+                            .and_then(|toplevel| toplevel.downcast::<gtk::Window>().ok());
+                        operation.run_dialog(parent.as_ref());
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to print: {}", e))
+    }
+}
+
+/// WKWebView has no print-to-file without a dialog, so `createPDF` renders
+/// the page instead. The result is a single page as tall as the content at
+/// the webview's width; `@page` rules only apply to print operations.
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::{PrintDone, PrintOptions};
+    use block2::RcBlock;
+    use objc2_foundation::{NSData, NSError};
+    use objc2_web_kit::WKWebView;
+    use std::cell::RefCell;
+
+    pub fn create_pdf(
+        webview: &tauri::Webview,
+        options: PrintOptions,
+        done: Option<PrintDone>,
+    ) -> Result<(), String> {
+        let Some(pdf_path) = options.pdf_path else {
+            return Err("No PDF path given".to_string());
+        };
+
+        webview
+            .with_webview(move |platform| {
+                // SAFETY: `inner` is the webview's WKWebView, alive for the
+                // duration of this callback on the main thread
+                let webview = unsafe { &*platform.inner().cast::<WKWebView>() };
+
+                let done = RefCell::new(done);
+                let handler = RcBlock::new(move |data: *mut NSData, error: *mut NSError| {
+                    // SAFETY: WebKit passes either the PDF data or an error
+                    let result = match unsafe { data.as_ref() } {
+                        Some(data) => write(&pdf_path, &data.to_vec()),
+                        None => Err(format!(
+                            "Printing failed: {}",
+                            unsafe { error.as_ref() }
+                                .map(|error| error.localizedDescription().to_string())
+                                .unwrap_or_else(|| "no PDF was created".to_string())
+                        )),
+                    };
+                    match &result {
+                        Ok(()) => println!("🖨️ Saved PDF to {:?}", pdf_path),
+                        Err(e) => eprintln!("🖨️ {}", e),
+                    }
+                    if let Some(done) = done.borrow_mut().take() {
+                        let _ = done.send(result);
+                    }
+                });
+
+                // SAFETY: a nil configuration captures the whole page
+                unsafe { webview.createPDFWithConfiguration_completionHandler(None, &handler) };
+            })
+            .map_err(|e| format!("Failed to print: {}", e))
+    }
+
+    fn write(path: &std::path::Path, bytes: &[u8]) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        std::fs::write(path, bytes).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+}
@@ -0,0 +1,230 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::merge;
+use crate::payment_codes::{self, PaymentCode};
+use crate::preprocess;
+
+/// Payment codes sit on the first page of an invoice, or the last one for
+/// Swiss QR-bills with a payment part. Only this many pages are scanned.
+const MAX_PDF_PAGES: usize = 4;
+
+/// Resolution PDF pages are rendered at. A QR-bill code is 46 mm wide, this
+/// gives it roughly 270 pixels.
+const PDF_DPI: u32 = 150;
+
+/// Photos are scaled down to this before scanning, finding the finder
+/// patterns in a 12 megapixel photo takes seconds and gains nothing.
+const MAX_SCAN_EDGE: u32 = 2000;
+
+/// Payment codes found in a file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentCodeScan {
+    pub path: PathBuf,
+    pub codes: Vec<PaymentCode>,
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Whether a file can carry a payment code we are able to read.
+pub fn is_scannable(path: &Path) -> bool {
+    is_pdf(path) || merge::is_image(path)
+}
+
+/// Look for payment codes on a blocking thread. Files without any, and files
+/// that can't be read, return an empty list.
+pub async fn scan(path: &Path) -> Vec<PaymentCode> {
+    if !is_scannable(path) {
+        return Vec::new();
+    }
+
+    let path = path.to_path_buf();
+    let result = tauri::async_runtime::spawn_blocking(move || scan_file(&path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+    match result {
+        Ok(codes) => codes,
+        Err(e) => {
+            eprintln!("🧾 Failed to scan for payment codes: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn scan_file(path: &Path) -> Result<Vec<PaymentCode>, String> {
+    let images = if is_pdf(path) {
+        pdf_images(path)?
+    } else {
+        vec![preprocess::open_image(path)?]
+    };
+
+    let mut codes: Vec<PaymentCode> = Vec::new();
+    for image in images {
+        for code in decode(&image) {
+            // The same code is often printed twice, e.g. on a reminder
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+    }
+
+    if !codes.is_empty() {
+        println!("🧾 Found {} payment code(s) in {:?}", codes.len(), path);
+    }
+    Ok(codes)
+}
+
+/// Decode every QR code in an image and keep the payment codes.
+fn decode(image: &image::DynamicImage) -> Vec<PaymentCode> {
+    let image = if image.width().max(image.height()) > MAX_SCAN_EDGE {
+        image.resize(
+            MAX_SCAN_EDGE,
+            MAX_SCAN_EDGE,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        image.clone()
+    };
+    let luma = image.to_luma8();
+
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        luma.width() as usize,
+        luma.height() as usize,
+        |x, y| luma.get_pixel(x as u32, y as u32)[0],
+    );
+
+    prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok())
+        .filter_map(|(_, content)| match payment_codes::parse(&content)? {
+            Ok(code) => Some(code),
+            Err(e) => {
+                eprintln!("🧾 Ignoring malformed payment code: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Render the first pages with `pdftoppm` from poppler when it is installed.
+/// Without it, fall back to the images embedded in the PDF, which covers
+/// scans but not invoices generated with a vector QR code.
+fn pdf_images(path: &Path) -> Result<Vec<image::DynamicImage>, String> {
+    match render_pdf(path) {
+        Ok(images) => Ok(images),
+        Err(e) => {
+            println!("🧾 Reading embedded images instead of rendering: {}", e);
+            embedded_images(path)
+        }
+    }
+}
+
+fn render_pdf(path: &Path) -> Result<Vec<image::DynamicImage>, String> {
+    let dir = std::env::temp_dir().join(format!("midday-qr-{}", crate::upload::next_upload_id()));
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+
+    let status = std::process::Command::new("pdftoppm")
+        .args(["-r", &PDF_DPI.to_string(), "-gray", "-png"])
+        .args(["-f", "1", "-l", &MAX_PDF_PAGES.to_string()])
+        .arg(path)
+        .arg(dir.join("page"))
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+
+    let result = match status {
+        Ok(status) if status.success() => {
+            let mut pages: Vec<PathBuf> = std::fs::read_dir(&dir)
+                .map_err(|e| format!("Failed to read {:?}: {}", dir, e))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect();
+            merge::sort_pages(&mut pages);
+            pages
+                .iter()
+                .map(|page| image::open(page).map_err(|e| format!("Failed to read page: {}", e)))
+                .collect()
+        }
+        Ok(status) => Err(format!("pdftoppm failed: {}", status)),
+        Err(e) => Err(format!("Failed to run pdftoppm: {}", e)),
+    };
+
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn embedded_images(path: &Path) -> Result<Vec<image::DynamicImage>, String> {
+    let document =
+        lopdf::Document::load(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+    let mut images = Vec::new();
+    for page_id in document.get_pages().into_values().take(MAX_PDF_PAGES) {
+        let Ok(page_images) = document.get_page_images(page_id) else {
+            continue;
+        };
+
+        for pdf_image in page_images {
+            let filters = pdf_image.filters.clone().unwrap_or_default();
+            let image = if filters.iter().any(|filter| filter == "DCTDecode") {
+                image::load_from_memory_with_format(pdf_image.content, image::ImageFormat::Jpeg)
+                    .ok()
+            } else {
+                raw_image(&document, &pdf_image)
+            };
+            images.extend(image);
+        }
+    }
+    Ok(images)
+}
+
+/// Uncompressed or Flate compressed 8-bit gray and RGB images, which is what
+/// most scanners write for black and white pages.
+fn raw_image(
+    document: &lopdf::Document,
+    pdf_image: &lopdf::xobject::PdfImage,
+) -> Option<image::DynamicImage> {
+    if pdf_image.bits_per_component != Some(8) {
+        return None;
+    }
+
+    let width = u32::try_from(pdf_image.width).ok()?;
+    let height = u32::try_from(pdf_image.height).ok()?;
+    let data = if pdf_image.filters.is_none() {
+        pdf_image.content.to_vec()
+    } else {
+        document
+            .get_object(pdf_image.id)
+            .and_then(|object| object.as_stream())
+            .and_then(|stream| stream.decompressed_content())
+            .ok()?
+    };
+
+    match pdf_image.color_space.as_deref() {
+        Some("DeviceGray") => {
+            image::GrayImage::from_raw(width, height, data).map(image::DynamicImage::ImageLuma8)
+        }
+        Some("DeviceRGB") => {
+            image::RgbImage::from_raw(width, height, data).map(image::DynamicImage::ImageRgb8)
+        }
+        _ => None,
+    }
+}
+
+/// Read Swiss QR-bill and EPC (GiroCode) payment codes from receipts and
+/// invoices, images or PDFs, so the dashboard can prefill a payment.
+#[tauri::command]
+pub async fn scan_payment_codes(paths: Vec<PathBuf>) -> Vec<PaymentCodeScan> {
+    let mut scans = Vec::with_capacity(paths.len());
+    for path in paths {
+        let codes = scan(&path).await;
+        scans.push(PaymentCodeScan { path, codes });
+    }
+    scans
+}
@@ -0,0 +1,616 @@
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Receipts and invoices put everything we look for on the first pages.
+const MAX_PAGES: usize = 3;
+
+/// The vendor is nearly always in the letterhead.
+const VENDOR_LINES: usize = 15;
+
+/// Amounts with two decimals, with `.`, `,`, `'` or a space as thousands
+/// separator: `12.50`, `1,234.56`, `1.234,56`, `1'234.50`, `1 234,50`.
+static AMOUNT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\d{1,3}(?:[.,'’ \u{a0}\u{202f}]\d{3})+|\d+)[.,]\d{2}\b").unwrap()
+});
+
+static TOTAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(grand total|total amount|amount due|balance due|total due|amount paid|total|gesamtbetrag|gesamtsumme|endbetrag|rechnungsbetrag|zu zahlen|gesamt|summe|montant ttc|net à payer|totale|importe total|te betalen|totaal|att betala|summa|totalt)\b").unwrap()
+});
+
+/// Total lines that are the final amount, not just "a total".
+static STRONG_TOTAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(grand total|amount due|balance due|total due|amount paid|gesamtbetrag|endbetrag|zu zahlen|montant ttc|net à payer|te betalen|att betala)\b").unwrap()
+});
+
+static NOT_TOTAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(sub ?total|zwischensumme|subtotaal|netto|net amount|net total|excl|exkl|ohne|before tax)").unwrap()
+});
+
+static VAT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(\b(vat|mwst|ust|tva|iva|btw|moms|gst|mva|alv|tax)\b|mehrwertsteuer|umsatzsteuer)",
+    )
+    .unwrap()
+});
+
+/// VAT lines that are a total including VAT rather than the VAT itself.
+static NOT_VAT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(\bincl|\binkl|\bexcl|\bexkl|\bohne|\bnet\b|\bnetto)").unwrap()
+});
+
+static RATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d{1,2}(?:[.,]\d{1,3})?)\s?%").unwrap());
+
+static DATE_KEYWORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(date|dated|datum|rechnungsdatum|belegdatum|fecha|data)\b").unwrap()
+});
+
+static DUE_KEYWORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(\bdue\b|fällig|payable|zahlbar|échéance|period|zeitraum)").unwrap()
+});
+
+static ISO_DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap());
+
+static NUMERIC_DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{1,2})([./])(\d{1,2})[./](\d{4}|\d{2})\b").unwrap());
+
+/// `12 Jan 2024`, `12. Januar 2024`, `12 janvier 2024`
+static DAY_MONTH_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(\d{1,2})\.?\s+([a-zäéû]{3,10})\.?,?\s+(\d{4})\b").unwrap()
+});
+
+/// `Jan 12, 2024`, `January 12 2024`
+static MONTH_DAY_DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b([a-zäéû]{3,10})\.?\s+(\d{1,2}),?\s+(\d{4})\b").unwrap());
+
+/// Case sensitive, `AB` and `SA` are company forms but `ab` and `sa` aren't.
+static LEGAL_FORM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(GmbH|AG|Ltd|LTD|Limited|Inc|INC|LLC|PLC|plc|AB|Oy|A/S|SA|SAS|SARL|S\.r\.l|S\.L|B\.V|BV|KG|e\.K)\b").unwrap()
+});
+
+static DOCUMENT_WORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(invoice|receipt|rechnung|quittung|beleg|facture|factura|fattura|faktura|kvitto|page|seite|tax invoice|order)\b").unwrap()
+});
+
+/// ISO codes and symbols, most specific first.
+const CURRENCIES: &[(&str, &str)] = &[
+    ("EUR", "EUR"),
+    ("USD", "USD"),
+    ("GBP", "GBP"),
+    ("CHF", "CHF"),
+    ("SEK", "SEK"),
+    ("NOK", "NOK"),
+    ("DKK", "DKK"),
+    ("PLN", "PLN"),
+    ("CZK", "CZK"),
+    ("CAD", "CAD"),
+    ("AUD", "AUD"),
+    ("JPY", "JPY"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("$", "USD"),
+    ("¥", "JPY"),
+    ("Fr.", "CHF"),
+];
+
+/// What a receipt seems to say, extracted locally from its text. Every field
+/// is a guess the user confirms in the dashboard.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptHints {
+    pub vendor: Option<String>,
+    /// `YYYY-MM-DD`
+    pub date: Option<String>,
+    pub total: Option<f64>,
+    /// ISO 4217
+    pub currency: Option<String>,
+    pub vat: Option<f64>,
+    /// Percent, only when the receipt has a single rate
+    pub vat_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptPreview {
+    pub path: PathBuf,
+    pub hints: Option<ReceiptHints>,
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Read hints from a PDF with embedded text on a blocking thread. Returns
+/// `None` for other files, scanned PDFs without text and when nothing was
+/// recognized.
+pub async fn extract(path: &Path) -> Option<ReceiptHints> {
+    if !is_pdf(path) {
+        return None;
+    }
+
+    let path = path.to_path_buf();
+    let result = tauri::async_runtime::spawn_blocking(move || extract_file(&path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+    match result {
+        Ok(hints) => hints,
+        Err(e) => {
+            eprintln!("🧾 Failed to read receipt text: {}", e);
+            None
+        }
+    }
+}
+
+fn extract_file(path: &Path) -> Result<Option<ReceiptHints>, String> {
+    // pdf-extract panics on some malformed PDFs instead of returning an error
+    let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_by_pages(path))
+        .map_err(|_| format!("Failed to read {:?}", path))?
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+    let text = pages
+        .into_iter()
+        .take(MAX_PAGES)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let hints = parse(&text);
+    if hints == ReceiptHints::default() {
+        return Ok(None);
+    }
+
+    println!("🧾 Receipt hints for {:?}: {:?}", path, hints);
+    Ok(Some(hints))
+}
+
+/// Run the heuristics over a receipt's text.
+pub fn parse(text: &str) -> ReceiptHints {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let total_line = find_total(&lines);
+    let currency = total_line
+        .and_then(|(index, _)| currency_in(lines[index]))
+        .or_else(|| most_common_currency(&lines));
+    let (vat, vat_rate) = find_vat(&lines);
+
+    ReceiptHints {
+        vendor: find_vendor(&lines),
+        // `01/02/2024` is January 2nd on US receipts
+        date: find_date(&lines, currency.as_deref() == Some("USD")),
+        total: total_line.map(|(_, total)| total),
+        currency,
+        vat,
+        vat_rate,
+    }
+}
+
+/// Parse an amount matched by `AMOUNT`. The last `.` or `,` followed by two
+/// digits is the decimal separator, everything else groups thousands.
+fn parse_amount(value: &str) -> Option<f64> {
+    let split = value.len() - 3;
+    let (whole, fraction) = (&value[..split], &value[split + 1..]);
+    let whole: String = whole.chars().filter(|c| c.is_ascii_digit()).collect();
+    format!("{}.{}", whole, fraction).parse().ok()
+}
+
+fn amounts_in(line: &str) -> Vec<f64> {
+    AMOUNT
+        .find_iter(line)
+        .filter_map(|amount| parse_amount(amount.as_str()))
+        .collect()
+}
+
+/// The line index and amount of the total. Lines saying "amount due" win
+/// over lines just saying "total", and the amount may be on the next line
+/// when the text comes from a table.
+fn find_total(lines: &[&str]) -> Option<(usize, f64)> {
+    let mut best: Option<(u8, usize, f64)> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        if !TOTAL.is_match(line) || NOT_TOTAL.is_match(line) {
+            continue;
+        }
+        // "Total VAT" is the VAT, "Total incl. VAT" is the total
+        if VAT.is_match(line) && !NOT_VAT.is_match(line) && !STRONG_TOTAL.is_match(line) {
+            continue;
+        }
+
+        let mut candidates = amounts_in(line);
+        let mut amount_index = index;
+        if candidates.is_empty()
+            && let Some(next) = lines.get(index + 1)
+        {
+            candidates = amounts_in(next);
+            amount_index = index + 1;
+        }
+        let Some(amount) = candidates.last().copied() else {
+            continue;
+        };
+
+        let score = if STRONG_TOTAL.is_match(line) { 2 } else { 1 };
+        let better = match best {
+            None => true,
+            Some((best_score, _, best_amount)) => {
+                score > best_score || (score == best_score && amount > best_amount)
+            }
+        };
+        if better {
+            best = Some((score, amount_index, amount));
+        }
+    }
+
+    best.map(|(_, index, amount)| (index, amount))
+}
+
+fn currency_in(line: &str) -> Option<String> {
+    CURRENCIES.iter().find_map(|(token, code)| {
+        let found = if token.chars().all(|c| c.is_ascii_uppercase()) {
+            line.split(|c: char| !c.is_ascii_alphabetic())
+                .any(|word| word == *token)
+        } else {
+            line.contains(token)
+        };
+        found.then(|| code.to_string())
+    })
+}
+
+fn most_common_currency(lines: &[&str]) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for currency in lines.iter().filter_map(|line| currency_in(line)) {
+        match counts.iter_mut().find(|(code, _)| *code == currency) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((currency, 1)),
+        }
+    }
+    // Stable, so ties go to the currency mentioned first
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts.into_iter().next().map(|(code, _)| code)
+}
+
+/// The VAT amount, summed over all rates, and the rate when there is one.
+fn find_vat(lines: &[&str]) -> (Option<f64>, Option<f64>) {
+    let mut vat = 0.0;
+    let mut rates: Vec<f64> = Vec::new();
+    let mut found = false;
+
+    for line in lines {
+        if !VAT.is_match(line) || NOT_VAT.is_match(line) {
+            continue;
+        }
+        // `19% MwSt 3,19`: the rate is not an amount, the amount is last
+        let without_rate = RATE.replace_all(line, "");
+        let Some(amount) = amounts_in(&without_rate).last().copied() else {
+            continue;
+        };
+
+        vat += amount;
+        found = true;
+        if let Some(rate) = RATE
+            .captures(line)
+            .and_then(|captures| captures[1].replace(',', ".").parse::<f64>().ok())
+            && !rates.contains(&rate)
+        {
+            rates.push(rate);
+        }
+    }
+
+    if !found {
+        return (None, None);
+    }
+    let rate = if rates.len() == 1 {
+        Some(rates[0])
+    } else {
+        None
+    };
+    (Some((vat * 100.0).round() / 100.0), rate)
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    let prefix: String = name.chars().take(3).collect();
+    let month = match prefix.as_str() {
+        "jan" | "jän" => 1,
+        "feb" | "fév" | "fev" => 2,
+        "mar" | "mär" | "mrt" => 3,
+        "apr" | "avr" => 4,
+        "may" | "mai" | "mei" | "maj" => 5,
+        "jun" | "jui" if name.starts_with("juin") || name.starts_with("jun") => 6,
+        "jul" | "jui" => 7,
+        "aug" | "aoû" | "aou" => 8,
+        "sep" => 9,
+        "oct" | "okt" => 10,
+        "nov" => 11,
+        "dec" | "dez" | "déc" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+fn full_year(year: i32) -> i32 {
+    if year < 100 { 2000 + year } else { year }
+}
+
+fn dates_in(line: &str, month_first: bool) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+
+    for captures in ISO_DATE.captures_iter(line) {
+        dates.extend(NaiveDate::from_ymd_opt(
+            captures[1].parse().unwrap_or(0),
+            captures[2].parse().unwrap_or(0),
+            captures[3].parse().unwrap_or(0),
+        ));
+    }
+
+    for captures in NUMERIC_DATE.captures_iter(line) {
+        let first: u32 = captures[1].parse().unwrap_or(0);
+        let second: u32 = captures[3].parse().unwrap_or(0);
+        let year = full_year(captures[4].parse().unwrap_or(0));
+        // Dots are always day first, slashes depend on the country unless
+        // one of the numbers can only be a day
+        let (day, month) = if &captures[2] == "/" && (second > 12 || (month_first && first <= 12)) {
+            (second, first)
+        } else {
+            (first, second)
+        };
+        dates.extend(NaiveDate::from_ymd_opt(year, month, day));
+    }
+
+    for captures in DAY_MONTH_DATE.captures_iter(line) {
+        if let Some(month) = month_number(&captures[2]) {
+            dates.extend(NaiveDate::from_ymd_opt(
+                captures[3].parse().unwrap_or(0),
+                month,
+                captures[1].parse().unwrap_or(0),
+            ));
+        }
+    }
+
+    for captures in MONTH_DAY_DATE.captures_iter(line) {
+        if let Some(month) = month_number(&captures[1]) {
+            dates.extend(NaiveDate::from_ymd_opt(
+                captures[3].parse().unwrap_or(0),
+                month,
+                captures[2].parse().unwrap_or(0),
+            ));
+        }
+    }
+
+    dates
+}
+
+/// The document date: one labelled as such, otherwise the first date that
+/// isn't a due date or service period.
+fn find_date(lines: &[&str], month_first: bool) -> Option<String> {
+    let mut first = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        if DUE_KEYWORD.is_match(line) {
+            continue;
+        }
+        let mut dates = dates_in(line, month_first);
+        // Labels often sit above their value in tables
+        if dates.is_empty()
+            && DATE_KEYWORD.is_match(line)
+            && let Some(next) = lines.get(index + 1)
+        {
+            dates = dates_in(next, month_first);
+        }
+        let Some(date) = dates.first().copied() else {
+            continue;
+        };
+
+        if DATE_KEYWORD.is_match(line) {
+            return Some(date.format("%Y-%m-%d").to_string());
+        }
+        first.get_or_insert(date);
+    }
+
+    first.map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+fn is_vendor_candidate(line: &str) -> bool {
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    (2..=60).contains(&line.chars().count())
+        && letters * 2 >= line.chars().count()
+        && !line.contains('@')
+        && !line.to_lowercase().contains("www.")
+        && !line.to_lowercase().contains("http")
+        && !AMOUNT.is_match(line)
+        && !DOCUMENT_WORD.is_match(line)
+        && dates_in(line, false).is_empty()
+}
+
+/// The letterhead line with a legal form (`GmbH`, `Ltd`, `AB`), otherwise the
+/// first line that looks like a name.
+fn find_vendor(lines: &[&str]) -> Option<String> {
+    let head = &lines[..lines.len().min(VENDOR_LINES)];
+    head.iter()
+        .find(|line| is_vendor_candidate(line) && LEGAL_FORM.is_match(line))
+        .or_else(|| head.iter().find(|line| is_vendor_candidate(line)))
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Read date, total, currency, VAT and vendor from PDF receipts without
+/// uploading them, e.g. to preview a drop. Works offline.
+#[tauri::command]
+pub async fn preview_receipts(paths: Vec<PathBuf>) -> Vec<ReceiptPreview> {
+    let mut previews = Vec::with_capacity(paths.len());
+    for path in paths {
+        let hints = extract(&path).await;
+        previews.push(ReceiptPreview { path, hints });
+    }
+    previews
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GERMAN_INVOICE: &str = "
+        Muster Bürobedarf GmbH
+        Hauptstraße 12, 10115 Berlin
+        Rechnung Nr. 2024-117
+        Rechnungsdatum: 05.03.2024
+        Fällig am: 04.04.2024
+        Druckerpapier A4 5x 24,90
+        Zwischensumme 124,50
+        19% MwSt 23,66
+        Gesamtbetrag 148,16 EUR
+    ";
+
+    const US_RECEIPT: &str = "
+        www.example.com
+        Corner Coffee Co
+        Receipt
+        03/04/2024 08:12
+        Latte 4.50
+        Bagel 3.25
+        Subtotal $7.75
+        Tax $0.62
+        Total $8.37
+    ";
+
+    #[test]
+    fn reads_german_invoice() {
+        let hints = parse(GERMAN_INVOICE);
+
+        assert_eq!(hints.vendor.as_deref(), Some("Muster Bürobedarf GmbH"));
+        // The labelled date, not the due date
+        assert_eq!(hints.date.as_deref(), Some("2024-03-05"));
+        assert_eq!(hints.total, Some(148.16));
+        assert_eq!(hints.currency.as_deref(), Some("EUR"));
+        assert_eq!(hints.vat, Some(23.66));
+        assert_eq!(hints.vat_rate, Some(19.0));
+    }
+
+    #[test]
+    fn reads_us_receipt() {
+        let hints = parse(US_RECEIPT);
+
+        assert_eq!(hints.vendor.as_deref(), Some("Corner Coffee Co"));
+        // Month first for dollar receipts
+        assert_eq!(hints.date.as_deref(), Some("2024-03-04"));
+        assert_eq!(hints.total, Some(8.37));
+        assert_eq!(hints.currency.as_deref(), Some("USD"));
+        assert_eq!(hints.vat, Some(0.62));
+        assert_eq!(hints.vat_rate, None);
+    }
+
+    #[test]
+    fn parses_amounts_with_any_separators() {
+        assert_eq!(amounts_in("1,234.56"), vec![1234.56]);
+        assert_eq!(amounts_in("1.234,56"), vec![1234.56]);
+        assert_eq!(amounts_in("1'234.50"), vec![1234.5]);
+        assert_eq!(amounts_in("1 234,50"), vec![1234.5]);
+        assert_eq!(amounts_in("12.50 and 3,20"), vec![12.5, 3.2]);
+        assert!(amounts_in("Invoice 2024").is_empty());
+    }
+
+    #[test]
+    fn prefers_the_amount_due_over_other_totals() {
+        let lines = [
+            "Total 100.00",
+            "Total incl. VAT 119.00",
+            "Amount due 119.00",
+            "Subtotal 200.00",
+        ];
+        assert_eq!(find_total(&lines), Some((2, 119.0)));
+    }
+
+    #[test]
+    fn reads_the_total_from_the_next_line() {
+        let lines = ["Zu zahlen", "CHF 1'250.40"];
+        assert_eq!(find_total(&lines), Some((1, 1250.4)));
+        assert_eq!(currency_in(lines[1]).as_deref(), Some("CHF"));
+    }
+
+    #[test]
+    fn skips_vat_totals_and_net_amounts() {
+        let lines = ["Total VAT 19.00", "Net total 100.00", "Total 119.00"];
+        assert_eq!(find_total(&lines), Some((2, 119.0)));
+    }
+
+    #[test]
+    fn sums_vat_over_rates() {
+        let lines = ["MwSt 7% 1,40", "MwSt 19% 3,80", "Total inkl. MwSt 25,20"];
+        assert_eq!(find_vat(&lines), (Some(5.2), None));
+
+        let lines = ["TVA 20 % 4,00"];
+        assert_eq!(find_vat(&lines), (Some(4.0), Some(20.0)));
+
+        assert_eq!(find_vat(&["Total 10.00"]), (None, None));
+    }
+
+    #[test]
+    fn finds_currencies_by_code_or_symbol() {
+        assert_eq!(currency_in("Total 12,00 €").as_deref(), Some("EUR"));
+        assert_eq!(currency_in("Amount GBP 5.00").as_deref(), Some("GBP"));
+        assert_eq!(currency_in("Fr. 9.90").as_deref(), Some("CHF"));
+        // Codes only count as whole words
+        assert_eq!(currency_in("EURO 5.00"), None);
+        assert_eq!(
+            most_common_currency(&["12 SEK", "$ 3.00", "4 SEK"]).as_deref(),
+            Some("SEK")
+        );
+    }
+
+    #[test]
+    fn reads_dates_in_common_formats() {
+        let date = |line: &str, month_first: bool| {
+            dates_in(line, month_first)
+                .first()
+                .map(|date| date.format("%Y-%m-%d").to_string())
+        };
+
+        assert_eq!(date("2024-01-31", false).as_deref(), Some("2024-01-31"));
+        assert_eq!(date("31.01.24", false).as_deref(), Some("2024-01-31"));
+        assert_eq!(date("02/01/2024", false).as_deref(), Some("2024-01-02"));
+        assert_eq!(date("02/01/2024", true).as_deref(), Some("2024-02-01"));
+        // 13 can only be the day
+        assert_eq!(date("01/13/2024", false).as_deref(), Some("2024-01-13"));
+        assert_eq!(
+            date("12. Januar 2024", false).as_deref(),
+            Some("2024-01-12")
+        );
+        assert_eq!(date("3 juin 2024", false).as_deref(), Some("2024-06-03"));
+        assert_eq!(date("July 4, 2024", false).as_deref(), Some("2024-07-04"));
+        assert_eq!(date("31.02.2024", false), None);
+    }
+
+    #[test]
+    fn reads_a_labelled_date_below_its_label() {
+        let lines = ["Period 01.01.2024 - 31.01.2024", "Datum", "15.02.2024"];
+        assert_eq!(find_date(&lines, false).as_deref(), Some("2024-02-15"));
+    }
+
+    #[test]
+    fn prefers_vendors_with_a_legal_form() {
+        let lines = [
+            "Invoice",
+            "Jane Doe",
+            "hello@example.com",
+            "Acme Widgets Ltd",
+            "12 High Street",
+        ];
+        assert_eq!(find_vendor(&lines).as_deref(), Some("Acme Widgets Ltd"));
+        assert_eq!(
+            find_vendor(&["INVOICE", "Jane   Doe Design"]).as_deref(),
+            Some("Jane Doe Design")
+        );
+    }
+
+    #[test]
+    fn finds_nothing_in_unrelated_text() {
+        assert_eq!(parse("Page 1 of 2\n"), ReceiptHints::default());
+    }
+}
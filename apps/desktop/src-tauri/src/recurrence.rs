@@ -0,0 +1,577 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, Weekday};
+
+/// Periods to walk before giving up, e.g. a daily rule from decades ago.
+const MAX_PERIODS: u32 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// An iCalendar `RRULE`, e.g. `FREQ=MONTHLY;BYDAY=-1FR;COUNT=12`.
+///
+/// Sub-daily frequencies and `BYWEEKNO`, `BYYEARDAY`, `BYHOUR`, ... are
+/// rejected; calendars use them rarely and the caller keeps the first event.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    /// In the same local time as the start
+    until: Option<NaiveDateTime>,
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+    by_set_pos: Vec<i32>,
+    week_start: Weekday,
+}
+
+impl Rule {
+    /// `until` converts the `UNTIL` value to the local time of the event.
+    pub fn parse(
+        value: &str,
+        until: impl Fn(&str) -> Option<NaiveDateTime>,
+    ) -> Result<Self, String> {
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        };
+        let mut frequency = None;
+
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid rule part {}", part))?;
+            let list = || value.split(',').map(str::trim);
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("Unsupported frequency {}", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("Invalid interval {}", value))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid count {}", value))?,
+                    )
+                }
+                "UNTIL" => {
+                    rule.until =
+                        Some(until(value).ok_or_else(|| format!("Invalid until {}", value))?)
+                }
+                "BYDAY" => {
+                    rule.by_day = list()
+                        .map(parse_day)
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| format!("Invalid BYDAY {}", value))?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = list()
+                        .map(|day| {
+                            day.parse()
+                                .ok()
+                                .filter(|day: &i32| (1..=31).contains(&day.abs()))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| format!("Invalid BYMONTHDAY {}", value))?
+                }
+                "BYMONTH" => {
+                    rule.by_month = list()
+                        .map(|month| month.parse().ok().filter(|month| (1..=12).contains(month)))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| format!("Invalid BYMONTH {}", value))?
+                }
+                "BYSETPOS" => {
+                    rule.by_set_pos = list()
+                        .map(|position| position.parse().ok().filter(|position| *position != 0))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| format!("Invalid BYSETPOS {}", value))?
+                }
+                "WKST" => {
+                    rule.week_start =
+                        parse_weekday(value).ok_or_else(|| format!("Invalid WKST {}", value))?
+                }
+                other => return Err(format!("Unsupported rule part {}", other)),
+            }
+        }
+
+        rule.frequency = frequency.ok_or("Rule without FREQ")?;
+        Ok(rule)
+    }
+
+    /// Occurrences from `start`, the first one, that fall within `from` and
+    /// `to`. `COUNT` still counts the ones before `from`.
+    pub fn between(
+        &self,
+        start: NaiveDateTime,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Vec<NaiveDateTime> {
+        let mut occurrences = Vec::new();
+        let mut counted = 0;
+        let mut accept = |occurrence: NaiveDateTime| {
+            if self.until.is_some_and(|until| occurrence > until) || occurrence > to {
+                return false;
+            }
+            counted += 1;
+            if self.count.is_some_and(|count| counted > count) {
+                return false;
+            }
+            if occurrence >= from {
+                occurrences.push(occurrence);
+            }
+            true
+        };
+
+        if !accept(start) {
+            return occurrences;
+        }
+        for period in 0..MAX_PERIODS {
+            let Some(days) = self.period(start.date(), period) else {
+                break;
+            };
+            for day in days {
+                let occurrence = day.and_time(start.time());
+                if occurrence <= start {
+                    continue;
+                }
+                if !accept(occurrence) {
+                    return occurrences;
+                }
+            }
+        }
+        occurrences
+    }
+
+    /// The days of the `index`th period, sorted.
+    fn period(&self, start: NaiveDate, index: u32) -> Option<Vec<NaiveDate>> {
+        let step = index.checked_mul(self.interval)?;
+        let mut days = match self.frequency {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(Duration::days(step as i64))?;
+                let matches = self.in_months(day.month())
+                    && (self.by_month_day.is_empty()
+                        || self.by_month_day.iter().any(|n| is_month_day(day, *n)))
+                    && (self.by_day.is_empty()
+                        || self
+                            .by_day
+                            .iter()
+                            .any(|(_, weekday)| *weekday == day.weekday()));
+                if matches { vec![day] } else { Vec::new() }
+            }
+            Frequency::Weekly => {
+                let offset = start.weekday().days_since(self.week_start) as i64;
+                let first = start.checked_add_signed(Duration::days(7 * step as i64 - offset))?;
+                (0..7)
+                    .map(|day| first + Duration::days(day))
+                    .filter(|day| self.in_months(day.month()))
+                    .filter(|day| match self.by_day.is_empty() {
+                        true => day.weekday() == start.weekday(),
+                        false => self
+                            .by_day
+                            .iter()
+                            .any(|(_, weekday)| *weekday == day.weekday()),
+                    })
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let month = start.with_day(1)?.checked_add_months(Months::new(step))?;
+                if self.in_months(month.month()) {
+                    self.expand(&month_days(month), Some(start.day()))
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(step as i32)?;
+                if !self.by_month.is_empty() {
+                    self.by_month
+                        .iter()
+                        .filter_map(|month| NaiveDate::from_ymd_opt(year, *month, 1))
+                        .flat_map(|month| self.expand(&month_days(month), Some(start.day())))
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    (1..=12)
+                        .filter_map(|month| NaiveDate::from_ymd_opt(year, month, 1))
+                        .flat_map(|month| self.expand(&month_days(month), None))
+                        .collect()
+                } else if !self.by_day.is_empty() {
+                    let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                    let days: Vec<NaiveDate> = first
+                        .iter_days()
+                        .take_while(|day| day.year() == year)
+                        .collect();
+                    self.expand(&days, None)
+                } else {
+                    NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                        .into_iter()
+                        .collect()
+                }
+            }
+        };
+
+        days.sort();
+        days.dedup();
+        if !self.by_set_pos.is_empty() {
+            let positioned = self
+                .by_set_pos
+                .iter()
+                .filter_map(|position| nth(&days, *position))
+                .collect();
+            days = positioned;
+            days.sort();
+            days.dedup();
+        }
+        Some(days)
+    }
+
+    fn in_months(&self, month: u32) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&month)
+    }
+
+    /// The days of `scope`, a month or a year, picked by `BYMONTHDAY` and
+    /// `BYDAY`, or `default_day` without either.
+    fn expand(&self, scope: &[NaiveDate], default_day: Option<u32>) -> Vec<NaiveDate> {
+        if !self.by_month_day.is_empty() {
+            return scope
+                .iter()
+                .filter(|day| self.by_month_day.iter().any(|n| is_month_day(**day, *n)))
+                .filter(|day| {
+                    self.by_day.is_empty()
+                        || self
+                            .by_day
+                            .iter()
+                            .any(|(_, weekday)| *weekday == day.weekday())
+                })
+                .copied()
+                .collect();
+        }
+        if !self.by_day.is_empty() {
+            return self
+                .by_day
+                .iter()
+                .flat_map(|(ordinal, weekday)| {
+                    let matching: Vec<NaiveDate> = scope
+                        .iter()
+                        .filter(|day| day.weekday() == *weekday)
+                        .copied()
+                        .collect();
+                    match ordinal {
+                        Some(ordinal) => nth(&matching, *ordinal).into_iter().collect(),
+                        None => matching,
+                    }
+                })
+                .collect();
+        }
+        scope
+            .iter()
+            .filter(|day| default_day == Some(day.day()))
+            .copied()
+            .collect()
+    }
+}
+
+/// `1` is the first, `-1` the last.
+fn nth(days: &[NaiveDate], position: i32) -> Option<NaiveDate> {
+    let index = match position {
+        1.. => position as usize - 1,
+        _ => days.len().checked_sub(position.unsigned_abs() as usize)?,
+    };
+    days.get(index).copied()
+}
+
+/// Whether `day` is the `n`th day of its month, counting back for negative `n`.
+fn is_month_day(day: NaiveDate, n: i32) -> bool {
+    let length = month_days(day.with_day(1).unwrap()).len() as i32;
+    let n = if n < 0 { length + n + 1 } else { n };
+    day.day() as i32 == n
+}
+
+fn month_days(first: NaiveDate) -> Vec<NaiveDate> {
+    first
+        .iter_days()
+        .take_while(|day| day.month() == first.month())
+        .collect()
+}
+
+/// `MO`, `2TU` or `-1FR`.
+fn parse_day(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let split = value.len().checked_sub(2)?;
+    let weekday = parse_weekday(value.get(split..)?)?;
+    let ordinal = match value.get(..split)? {
+        "" => None,
+        ordinal => Some(
+            ordinal
+                .trim_start_matches('+')
+                .parse()
+                .ok()
+                .filter(|ordinal: &i32| *ordinal != 0)?,
+        ),
+    };
+    Some((ordinal, weekday))
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    Some(match value.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").unwrap()
+    }
+
+    /// The first `limit` occurrences of `rule` from `start`, the examples of
+    /// RFC 5545 section 3.8.5.3 in floating time.
+    fn dates(rule: &str, start: &str, limit: usize) -> Vec<String> {
+        let start = at(start);
+        Rule::parse(rule, |value| Some(at(value)))
+            .unwrap()
+            .between(start, start, at("20000101T000000"))
+            .iter()
+            .take(limit)
+            .map(|occurrence| occurrence.format("%Y-%m-%d").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn count_and_until() {
+        let daily = dates("FREQ=DAILY;COUNT=10", "19970902T090000", 100);
+        assert_eq!(daily.len(), 10);
+        assert_eq!(daily.last().unwrap(), "1997-09-11");
+
+        let until = dates("FREQ=DAILY;UNTIL=19971224T000000Z", "19970902T090000", 200);
+        assert_eq!(until.len(), 113);
+        assert_eq!(until.last().unwrap(), "1997-12-23");
+
+        // Whichever ends first, UNTIL includes an occurrence right on it
+        assert_eq!(
+            dates(
+                "FREQ=DAILY;COUNT=5;UNTIL=19970904T090000",
+                "19970902T090000",
+                100
+            ),
+            ["1997-09-02", "1997-09-03", "1997-09-04"]
+        );
+    }
+
+    #[test]
+    fn count_includes_occurrences_before_the_window() {
+        let start = at("19970902T090000");
+        let rule = Rule::parse("FREQ=DAILY;COUNT=10", |_| None).unwrap();
+        assert_eq!(
+            rule.between(start, at("19970910T000000"), at("19971231T000000")),
+            [at("19970910T090000"), at("19970911T090000")]
+        );
+    }
+
+    #[test]
+    fn weekly_with_interval_and_week_start() {
+        assert_eq!(
+            dates(
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO",
+                "19970805T090000",
+                10
+            ),
+            ["1997-08-05", "1997-08-10", "1997-08-19", "1997-08-24"]
+        );
+        assert_eq!(
+            dates(
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU",
+                "19970805T090000",
+                10
+            ),
+            ["1997-08-05", "1997-08-17", "1997-08-19", "1997-08-31"]
+        );
+
+        let every_other = dates(
+            "FREQ=WEEKLY;INTERVAL=2;UNTIL=19971224T000000Z;WKST=SU;BYDAY=MO,WE,FR",
+            "19970901T090000",
+            100,
+        );
+        assert_eq!(every_other.len(), 25);
+        assert_eq!(
+            every_other[..4],
+            ["1997-09-01", "1997-09-03", "1997-09-05", "1997-09-15"]
+        );
+        assert_eq!(every_other.last().unwrap(), "1997-12-22");
+    }
+
+    #[test]
+    fn monthly_by_day_ordinals() {
+        assert_eq!(
+            dates("FREQ=MONTHLY;COUNT=10;BYDAY=1FR", "19970905T090000", 20),
+            [
+                "1997-09-05",
+                "1997-10-03",
+                "1997-11-07",
+                "1997-12-05",
+                "1998-01-02",
+                "1998-02-06",
+                "1998-03-06",
+                "1998-04-03",
+                "1998-05-01",
+                "1998-06-05"
+            ]
+        );
+        assert_eq!(
+            dates(
+                "FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=1SU,-1SU",
+                "19970907T090000",
+                20
+            ),
+            [
+                "1997-09-07",
+                "1997-09-28",
+                "1997-11-02",
+                "1997-11-30",
+                "1998-01-04",
+                "1998-01-25",
+                "1998-03-01",
+                "1998-03-29",
+                "1998-05-03",
+                "1998-05-31"
+            ]
+        );
+        // Friday the 13th, after the start
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13", "19970902T090000", 5)[1..],
+            ["1998-02-13", "1998-03-13", "1998-11-13", "1999-08-13"]
+        );
+    }
+
+    #[test]
+    fn monthly_by_month_day() {
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=-2", "19970929T090000", 7),
+            [
+                "1997-09-29",
+                "1997-10-30",
+                "1997-11-29",
+                "1997-12-30",
+                "1998-01-30",
+                "1998-02-27",
+                "1998-03-30"
+            ]
+        );
+        // Months without a 31st are skipped, not moved
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=31;COUNT=5", "19970131T090000", 10),
+            [
+                "1997-01-31",
+                "1997-03-31",
+                "1997-05-31",
+                "1997-07-31",
+                "1997-08-31"
+            ]
+        );
+        assert_eq!(
+            dates("FREQ=MONTHLY;COUNT=4", "19970131T090000", 10),
+            ["1997-01-31", "1997-03-31", "1997-05-31", "1997-07-31"]
+        );
+    }
+
+    #[test]
+    fn monthly_by_set_position() {
+        assert_eq!(
+            dates(
+                "FREQ=MONTHLY;COUNT=3;BYDAY=TU,WE,TH;BYSETPOS=3",
+                "19970904T090000",
+                10
+            ),
+            ["1997-09-04", "1997-10-07", "1997-11-06"]
+        );
+        // The last weekday of the month
+        assert_eq!(
+            dates(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "19970930T090000",
+                7
+            ),
+            [
+                "1997-09-30",
+                "1997-10-31",
+                "1997-11-28",
+                "1997-12-31",
+                "1998-01-30",
+                "1998-02-27",
+                "1998-03-31"
+            ]
+        );
+    }
+
+    #[test]
+    fn yearly() {
+        assert_eq!(
+            dates("FREQ=YEARLY;BYMONTH=3;BYDAY=TH", "19970313T090000", 11),
+            [
+                "1997-03-13",
+                "1997-03-20",
+                "1997-03-27",
+                "1998-03-05",
+                "1998-03-12",
+                "1998-03-19",
+                "1998-03-26",
+                "1999-03-04",
+                "1999-03-11",
+                "1999-03-18",
+                "1999-03-25"
+            ]
+        );
+        assert_eq!(
+            dates("FREQ=YEARLY;BYDAY=20MO", "19970519T090000", 3),
+            ["1997-05-19", "1998-05-18", "1999-05-17"]
+        );
+        assert_eq!(
+            dates("FREQ=YEARLY;INTERVAL=2;COUNT=3", "19960229T090000", 10),
+            ["1996-02-29"]
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        for rule in [
+            "FREQ=HOURLY",
+            "FREQ=YEARLY;BYWEEKNO=20",
+            "COUNT=3",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=MONTHLY;BYDAY=0MO",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+        ] {
+            assert!(Rule::parse(rule, |_| None).is_err(), "{}", rule);
+        }
+    }
+}
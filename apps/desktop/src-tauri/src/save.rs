@@ -0,0 +1,302 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_opener::OpenerExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::download;
+use crate::upload::next_upload_id;
+
+/// Header carrying the save id on `write_save_chunk` requests, whose body is
+/// the raw chunk.
+const SAVE_ID_HEADER: &str = "x-save-id";
+
+/// A save in progress. Data goes to a temporary file next to the destination
+/// and only replaces an existing file once everything was written.
+struct PendingSave {
+    path: PathBuf,
+    temp_path: PathBuf,
+    file: Option<tokio::fs::File>,
+    bytes_written: u64,
+}
+
+#[derive(Default)]
+pub struct SaveState {
+    pending: Mutex<HashMap<String, PendingSave>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveTarget {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.part", file_name))
+}
+
+/// Ask where to save `file_name`, starting in Downloads. Without `ask` the
+/// file goes straight to Downloads under a name that doesn't exist yet.
+/// The native dialog confirms overwriting an existing file itself.
+pub async fn choose_path(
+    app: &tauri::AppHandle,
+    window: &tauri::Window,
+    file_name: &str,
+    ask: bool,
+) -> Result<Option<PathBuf>, String> {
+    let file_name = download::safe_file_name(file_name);
+    let downloads = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Failed to resolve Downloads folder: {}", e))?;
+
+    if !ask {
+        return Ok(Some(download::unique_path(&downloads, &file_name)));
+    }
+
+    let mut dialog = app
+        .dialog()
+        .file()
+        .set_title("Save File")
+        .set_parent(window)
+        .set_directory(&downloads)
+        .set_file_name(&file_name);
+
+    if let Some(extension) = Path::new(&file_name).extension() {
+        let extension = extension.to_string_lossy().to_lowercase();
+        dialog = dialog.add_filter("Files", &[extension.as_str()]);
+    }
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    dialog.save_file(move |path| {
+        let _ = sender.send(path);
+    });
+
+    match receiver.await.ok().flatten() {
+        Some(path) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| format!("Invalid save path: {}", e)),
+        None => Ok(None),
+    }
+}
+
+/// Move a completely written temporary file into place.
+async fn commit(temp_path: &Path, path: &Path) -> Result<(), String> {
+    tokio::fs::rename(temp_path, path)
+        .await
+        .map_err(|e| format!("Failed to save {:?}: {}", path, e))
+}
+
+/// Start saving a file written by the dashboard in chunks. Returns `None` when
+/// the save dialog was cancelled.
+#[tauri::command]
+pub async fn begin_save(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: tauri::State<'_, SaveState>,
+    file_name: String,
+    ask: Option<bool>,
+) -> Result<Option<SaveTarget>, String> {
+    let Some(path) = choose_path(&app, &window, &file_name, ask.unwrap_or(true)).await? else {
+        println!("💾 Save of {} cancelled", file_name);
+        return Ok(None);
+    };
+
+    let temp_path = temp_path_for(&path);
+    let file = tokio::fs::File::create(&temp_path)
+        .await
+        .map_err(|e| format!("Failed to create {:?}: {}", temp_path, e))?;
+
+    let id = next_upload_id();
+    println!("💾 Saving {} to {:?}", file_name, path);
+    state.pending.lock().unwrap().insert(
+        id.clone(),
+        PendingSave {
+            path: path.clone(),
+            temp_path,
+            file: Some(file),
+            bytes_written: 0,
+        },
+    );
+
+    Ok(Some(SaveTarget { id, path }))
+}
+
+/// Append the raw request body to a save started with `begin_save`. Chunks
+/// must be sent one after another.
+#[tauri::command]
+pub async fn write_save_chunk(
+    state: tauri::State<'_, SaveState>,
+    request: tauri::ipc::Request<'_>,
+) -> Result<(), String> {
+    let tauri::ipc::InvokeBody::Raw(chunk) = request.body() else {
+        return Err("Expected a binary body".to_string());
+    };
+    let id = request
+        .headers()
+        .get(SAVE_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| format!("Missing {} header", SAVE_ID_HEADER))?
+        .to_string();
+
+    // The file is taken out while writing so the lock isn't held across awaits
+    let mut file = state
+        .pending
+        .lock()
+        .unwrap()
+        .get_mut(&id)
+        .ok_or_else(|| format!("Unknown save: {}", id))?
+        .file
+        .take()
+        .ok_or_else(|| format!("Save {} is already being written", id))?;
+
+    let result = file
+        .write_all(chunk)
+        .await
+        .map_err(|e| format!("Failed to write: {}", e));
+
+    if let Some(pending) = state.pending.lock().unwrap().get_mut(&id) {
+        pending.file = Some(file);
+        if result.is_ok() {
+            pending.bytes_written += chunk.len() as u64;
+        }
+    }
+
+    result
+}
+
+/// Finish a chunked save, replacing any existing file. Returns the saved path.
+#[tauri::command]
+pub async fn finish_save(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SaveState>,
+    id: String,
+    reveal: Option<bool>,
+) -> Result<PathBuf, String> {
+    let pending = state
+        .pending
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .ok_or_else(|| format!("Unknown save: {}", id))?;
+
+    let mut file = pending
+        .file
+        .ok_or_else(|| format!("Save {} is still being written", id))?;
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write: {}", e))?;
+    drop(file);
+
+    commit(&pending.temp_path, &pending.path).await?;
+    println!(
+        "💾 Saved {} bytes to {:?}",
+        pending.bytes_written, pending.path
+    );
+
+    if reveal.unwrap_or(false) {
+        reveal_in_folder(app, pending.path.clone())?;
+    }
+
+    Ok(pending.path)
+}
+
+/// Give up on a chunked save, e.g. when the dashboard failed to read the Blob.
+#[tauri::command]
+pub async fn abort_save(state: tauri::State<'_, SaveState>, id: String) -> Result<(), String> {
+    let pending = state.pending.lock().unwrap().remove(&id);
+    if let Some(pending) = pending {
+        drop(pending.file);
+        let _ = tokio::fs::remove_file(&pending.temp_path).await;
+    }
+    Ok(())
+}
+
+/// Save a URL to disk, authenticated with the webview's cookies. The response
+/// is streamed to disk instead of passing through the dashboard. Returns
+/// `None` when the save dialog was cancelled.
+#[tauri::command]
+pub async fn save_url(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    webview: tauri::Webview,
+    url: String,
+    file_name: Option<String>,
+    ask: Option<bool>,
+    reveal: Option<bool>,
+) -> Result<Option<PathBuf>, String> {
+    let url = tauri::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+
+    let response = download::authenticated_get(&webview, &url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+    let file_name = file_name
+        .or_else(|| {
+            response
+                .headers()
+                .get(reqwest::header::CONTENT_DISPOSITION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(download::file_name_from_disposition)
+        })
+        .unwrap_or_else(|| "download".to_string());
+
+    let Some(path) = choose_path(&app, &window, &file_name, ask.unwrap_or(true)).await? else {
+        println!("💾 Save of {} cancelled", file_name);
+        return Ok(None);
+    };
+
+    let temp_path = temp_path_for(&path);
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp_path)
+            .await
+            .map_err(|e| format!("Failed to create {:?}: {}", temp_path, e))?;
+
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write: {}", e))?;
+        }
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to write: {}", e))
+    }
+    .await;
+
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        return Err(e);
+    }
+
+    commit(&temp_path, &path).await?;
+    println!("💾 Saved {} to {:?}", url, path);
+
+    if reveal.unwrap_or(false) {
+        reveal_in_folder(app, path.clone())?;
+    }
+
+    Ok(Some(path))
+}
+
+/// Show a file selected in Finder, Explorer or the file manager (through
+/// the freedesktop FileManager1 interface on Linux).
+#[tauri::command]
+pub fn reveal_in_folder(app: tauri::AppHandle, path: PathBuf) -> Result<(), String> {
+    app.opener()
+        .reveal_item_in_dir(&path)
+        .map_err(|e| format!("Failed to show {:?}: {}", path, e))
+}
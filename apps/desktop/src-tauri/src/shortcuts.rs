@@ -0,0 +1,289 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::SearchWindowState;
+
+/// Shown to users whose session can't grab global keys, so they can bind it
+/// in their desktop environment's keyboard settings instead.
+pub const TOGGLE_SEARCH_COMMAND: &str = "midday --toggle-search";
+pub const CAPTURE_COMMAND: &str = "midday capture";
+pub const CLIPBOARD_COMMAND: &str = "midday clipboard";
+
+const SEARCH_SHORTCUT_LABEL: &str = "Shift+Alt+K";
+const CAPTURE_SHORTCUT_LABEL: &str = "Shift+Alt+R";
+const CLIPBOARD_SHORTCUT_LABEL: &str = "Shift+Alt+V";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShortcutBackend {
+    /// Registered through tauri_plugin_global_shortcut
+    Native,
+    /// Bound through the XDG desktop portal GlobalShortcuts interface
+    Portal,
+    /// Nothing could be registered, the user has to bind the fallback command
+    Command,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutsStatus {
+    pub session_type: String,
+    pub backend: ShortcutBackend,
+    pub registered: bool,
+    pub shortcut: &'static str,
+    pub fallback_command: &'static str,
+    pub capture_shortcut: &'static str,
+    pub capture_fallback_command: &'static str,
+    pub clipboard_shortcut: &'static str,
+    pub clipboard_fallback_command: &'static str,
+    pub error: Option<String>,
+}
+
+pub type ShortcutsState = Mutex<ShortcutsStatus>;
+
+#[tauri::command]
+pub fn shortcuts_status(state: tauri::State<'_, ShortcutsState>) -> ShortcutsStatus {
+    state.lock().unwrap().clone()
+}
+
+/// Toggle the search window from any thread. Window creation in
+/// toggle_search_window blocks on the async runtime, so it has to run outside of it.
+pub fn toggle_search(app: &tauri::AppHandle) {
+    let app_handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        if let Some(search_state) = app_handle.try_state::<SearchWindowState>() {
+            if let Err(e) = crate::toggle_search_window(&app_handle, &search_state) {
+                println!("🔍 toggle_search_window returned Err: {}", e);
+            }
+        }
+    });
+}
+
+fn session_type() -> String {
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(session) = std::env::var("XDG_SESSION_TYPE") {
+            if !session.is_empty() {
+                return session.to_lowercase();
+            }
+        }
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return "wayland".to_string();
+        }
+        "x11".to_string()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        std::env::consts::OS.to_string()
+    }
+}
+
+/// Register the search, capture and clipboard shortcuts natively, or through the
+/// desktop portal on Wayland where tauri_plugin_global_shortcut can't grab keys.
+pub fn setup(app: &tauri::AppHandle) {
+    use tauri_plugin_global_shortcut::{
+        Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState,
+    };
+
+    let search_shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::ALT), Code::KeyK);
+    let capture_shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::ALT), Code::KeyR);
+    let clipboard_shortcut = Shortcut::new(Some(Modifiers::SHIFT | Modifiers::ALT), Code::KeyV);
+
+    let session_type = session_type();
+    let is_wayland = session_type == "wayland";
+
+    let plugin_result = app
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(move |app_handle, shortcut, event| {
+                    if shortcut == &search_shortcut && event.state() == ShortcutState::Pressed {
+                        println!("🔍 Global shortcut triggered - checking search state via managed state");
+                        // Get search state from managed state (same as commands use)
+                        if let Some(managed_search_state) = app_handle.try_state::<SearchWindowState>() {
+                            let current_search_state = *managed_search_state.lock().unwrap();
+                            println!("🔍 Shortcut: Search state from managed state: {}", current_search_state);
+
+                            // Use the same app_handle for both search state and toggle function
+                            let result = crate::toggle_search_window(app_handle, &managed_search_state);
+                            match result {
+                                Ok(_) => println!("🔍 Shortcut: toggle_search_window returned Ok"),
+                                Err(e) => println!("🔍 Shortcut: toggle_search_window returned Err: {}", e)
+                            }
+                        } else {
+                            println!("❌ Failed to get managed search state for shortcut");
+                        }
+                    } else if shortcut == &capture_shortcut && event.state() == ShortcutState::Pressed {
+                        println!("📸 Capture shortcut triggered");
+                        crate::capture::capture_to_inbox(app_handle);
+                    } else if shortcut == &clipboard_shortcut && event.state() == ShortcutState::Pressed {
+                        println!("📋 Clipboard shortcut triggered");
+                        crate::clipboard::upload_clipboard(app_handle);
+                    }
+                })
+                .build(),
+        )
+        .map_err(|e| e.to_string());
+
+    let status = if is_wayland {
+        // Native registration may "succeed" through XWayland but never fire, so
+        // only the portal is used. The status is updated once it has bound them.
+        println!("⌨️ Wayland session detected, binding shortcuts through the desktop portal");
+        ShortcutsStatus {
+            session_type,
+            backend: ShortcutBackend::Command,
+            registered: false,
+            shortcut: SEARCH_SHORTCUT_LABEL,
+            fallback_command: TOGGLE_SEARCH_COMMAND,
+            capture_shortcut: CAPTURE_SHORTCUT_LABEL,
+            capture_fallback_command: CAPTURE_COMMAND,
+            clipboard_shortcut: CLIPBOARD_SHORTCUT_LABEL,
+            clipboard_fallback_command: CLIPBOARD_COMMAND,
+            error: None,
+        }
+    } else {
+        let native_result = plugin_result.and_then(|_| {
+            app.global_shortcut()
+                .register_multiple([search_shortcut, capture_shortcut, clipboard_shortcut])
+                .map_err(|e| e.to_string())
+        });
+        if let Err(e) = &native_result {
+            eprintln!("⌨️ Failed to register shortcuts: {}", e);
+        }
+        ShortcutsStatus {
+            session_type,
+            backend: if native_result.is_ok() {
+                ShortcutBackend::Native
+            } else {
+                ShortcutBackend::Command
+            },
+            registered: native_result.is_ok(),
+            shortcut: SEARCH_SHORTCUT_LABEL,
+            fallback_command: TOGGLE_SEARCH_COMMAND,
+            capture_shortcut: CAPTURE_SHORTCUT_LABEL,
+            capture_fallback_command: CAPTURE_COMMAND,
+            clipboard_shortcut: CLIPBOARD_SHORTCUT_LABEL,
+            clipboard_fallback_command: CLIPBOARD_COMMAND,
+            error: native_result.err(),
+        }
+    };
+
+    app.manage::<ShortcutsState>(Mutex::new(status));
+
+    #[cfg(target_os = "linux")]
+    if is_wayland {
+        let app_handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            portal::bind(app_handle).await;
+        });
+    }
+}
+
+fn update_status(app: &tauri::AppHandle, update: impl FnOnce(&mut ShortcutsStatus)) {
+    if let Some(state) = app.try_state::<ShortcutsState>() {
+        let status = {
+            let mut status = state.lock().unwrap();
+            update(&mut status);
+            status.clone()
+        };
+        let _ = app.emit("shortcuts-status-changed", status);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+    use futures_util::StreamExt;
+
+    use super::{ShortcutBackend, update_status};
+
+    const TOGGLE_SEARCH_ID: &str = "toggle-search";
+    const CAPTURE_ID: &str = "capture-receipt";
+    const CLIPBOARD_ID: &str = "upload-clipboard";
+
+    /// Bind the search, capture and clipboard shortcuts through the GlobalShortcuts portal
+    /// and keep the session alive for as long as the app runs.
+    pub async fn bind(app: tauri::AppHandle) {
+        if let Err(e) = bind_and_listen(&app).await {
+            eprintln!("⌨️ GlobalShortcuts portal unavailable: {}", e);
+            update_status(&app, |status| {
+                status.error = Some(format!(
+                    "Global shortcuts portal unavailable ({}). Bind `{}`, `{}` and `{}` in your desktop settings instead.",
+                    e,
+                    super::TOGGLE_SEARCH_COMMAND,
+                    super::CAPTURE_COMMAND,
+                    super::CLIPBOARD_COMMAND
+                ));
+            });
+        }
+    }
+
+    async fn bind_and_listen(app: &tauri::AppHandle) -> Result<(), ashpd::Error> {
+        let proxy = GlobalShortcuts::new().await?;
+        let session = proxy.create_session().await?;
+
+        let shortcuts = [
+            NewShortcut::new(TOGGLE_SEARCH_ID, "Toggle Midday search")
+                .preferred_trigger("SHIFT+ALT+k"),
+            NewShortcut::new(CAPTURE_ID, "Capture a receipt to Midday Inbox")
+                .preferred_trigger("SHIFT+ALT+r"),
+            NewShortcut::new(CLIPBOARD_ID, "Upload the clipboard to Midday Inbox")
+                .preferred_trigger("SHIFT+ALT+v"),
+        ];
+        let bound = proxy
+            .bind_shortcuts(&session, &shortcuts, None)
+            .await?
+            .response()?;
+
+        println!("⌨️ Portal shortcuts bound: {:?}", bound.shortcuts());
+        // The user may decline some or all of them in the portal dialog
+        let bound_ids: Vec<&str> = bound.shortcuts().iter().map(|shortcut| shortcut.id()).collect();
+        let missing: Vec<&str> = [
+            (TOGGLE_SEARCH_ID, super::TOGGLE_SEARCH_COMMAND),
+            (CAPTURE_ID, super::CAPTURE_COMMAND),
+            (CLIPBOARD_ID, super::CLIPBOARD_COMMAND),
+        ]
+        .into_iter()
+        .filter(|(id, _)| !bound_ids.contains(id))
+        .map(|(_, command)| command)
+        .collect();
+        update_status(app, |status| {
+            status.registered = !bound_ids.is_empty();
+            status.backend = if status.registered {
+                ShortcutBackend::Portal
+            } else {
+                ShortcutBackend::Command
+            };
+            status.error = (!missing.is_empty()).then(|| {
+                format!(
+                    "Some shortcuts weren't bound by the desktop portal. Bind `{}` in your desktop settings instead.",
+                    missing.join("`, `")
+                )
+            });
+        });
+
+        let mut activated = proxy.receive_activated().await?;
+        while let Some(event) = activated.next().await {
+            match event.shortcut_id() {
+                TOGGLE_SEARCH_ID => {
+                    println!("🔍 Portal shortcut triggered");
+                    super::toggle_search(app);
+                }
+                CAPTURE_ID => {
+                    println!("📸 Portal capture shortcut triggered");
+                    crate::capture::capture_to_inbox(app);
+                }
+                CLIPBOARD_ID => {
+                    println!("📋 Portal clipboard shortcut triggered");
+                    crate::clipboard::upload_clipboard(app);
+                }
+                _ => {}
+            }
+        }
+
+        // Keep the session open until the signal stream ends
+        drop(session);
+        Ok(())
+    }
+}
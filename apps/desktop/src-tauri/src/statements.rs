@@ -0,0 +1,1159 @@
+use chrono::{Datelike, NaiveDate};
+use quick_xml::events::Event;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::csv_import;
+
+/// `:61:` statement line: value date, optional entry date, debit/credit mark,
+/// optional funds code, amount and transaction type.
+static MT940_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{6})(\d{4})?(RC|RD|EC|ED|C|D)([A-Z])?(\d+,\d*)([NFS][A-Z0-9]{3})(.*)$")
+        .unwrap()
+});
+
+/// `:60F:` and `:62F:` balances: debit/credit mark, date, currency and amount.
+static MT940_BALANCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([CD])(\d{6})([A-Z]{3})(\d+,\d*)").unwrap());
+
+/// A field tag at the start of a line, e.g. `:61:` or `:60F:`.
+static MT940_FIELD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^:(\d{2}[A-Z]?):(.*)$").unwrap());
+
+/// Keys of the `/KEY/value` layout of `:86:` used by Dutch and Belgian banks.
+static MT940_SLASH_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"/(TRTP|IBAN|BIC|NAME|REMI|EREF|MARF|CSID|ORDP|BENM|ADDR|RTRN|PREF|ULTC|ULTD|PURP|CNTP|ISDT|SWOC|FX)/",
+    )
+    .unwrap()
+});
+
+/// SEPA keys in the purpose of the German `?20`-`?29` layout, `SVWZ+` is the
+/// free text the sender entered.
+static SEPA_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(EREF|KREF|MREF|CRED|DEBT|COAM|OAMT|SVWZ|ABWA|ABWE|IBAN|BIC)\+").unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    /// OFX 1.x (SGML) and 2.x (XML), including Quicken's QFX
+    Ofx,
+    /// ISO 20022 bank to customer statement
+    Camt053,
+    /// SWIFT customer statement
+    Mt940,
+}
+
+/// A booked transaction, in the columns of the dashboard's CSV import.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementTransaction {
+    /// YYYY-MM-DD, the booking date
+    pub date: String,
+    /// Negative for money going out
+    pub amount: f64,
+    pub currency: String,
+    pub description: Option<String>,
+    pub counterparty: Option<String>,
+    /// Account balance after this transaction, when the statement has an
+    /// opening or closing balance
+    pub balance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    pub format: StatementFormat,
+    /// IBAN or account number of the first account in the file
+    pub account: Option<String>,
+    pub currency: Option<String>,
+    /// Oldest first
+    pub transactions: Vec<StatementTransaction>,
+}
+
+/// One account statement, files can hold several.
+#[derive(Default)]
+struct Section {
+    account: Option<String>,
+    currency: Option<String>,
+    opening: Option<f64>,
+    closing: Option<f64>,
+    transactions: Vec<StatementTransaction>,
+}
+
+/// Parse an OFX/QFX, CAMT.053 or MT940 statement. The format is detected
+/// from the contents.
+pub fn parse(bytes: &[u8]) -> Result<Statement, String> {
+    let text = decode(bytes);
+    let format = detect(&text).ok_or("Not an OFX, CAMT.053 or MT940 statement")?;
+    let sections = match format {
+        StatementFormat::Ofx => parse_ofx(&text)?,
+        StatementFormat::Camt053 => parse_camt(&text)?,
+        StatementFormat::Mt940 => parse_mt940(&text)?,
+    };
+
+    let account = sections.iter().find_map(|section| section.account.clone());
+    let currency = sections.iter().find_map(|section| section.currency.clone());
+    let mut transactions: Vec<StatementTransaction> =
+        sections.into_iter().flat_map(with_balances).collect();
+    // Dates are YYYY-MM-DD, the sort is stable so same-day order is kept
+    transactions.sort_by(|a, b| a.date.cmp(&b.date));
+
+    Ok(Statement {
+        format,
+        account,
+        currency,
+        transactions,
+    })
+}
+
+/// UTF-8, or Windows-1252 which older OFX exports and most MT940 files use.
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::WINDOWS_1252.decode(bytes).0.into_owned(),
+    }
+}
+
+fn detect(text: &str) -> Option<StatementFormat> {
+    if text.contains("<BkToCstmrStmt") || text.contains(":BkToCstmrStmt") {
+        return Some(StatementFormat::Camt053);
+    }
+
+    let upper = text.to_ascii_uppercase();
+    if upper.contains("OFXHEADER") || upper.contains("<OFX>") {
+        return Some(StatementFormat::Ofx);
+    }
+
+    let tags: Vec<String> = text
+        .lines()
+        .filter_map(|line| MT940_FIELD.captures(line.trim_start()))
+        .map(|captures| captures[1].to_string())
+        .collect();
+    if tags.iter().any(|tag| tag == "20") && tags.iter().any(|tag| tag == "60F" || tag == "60M") {
+        return Some(StatementFormat::Mt940);
+    }
+    None
+}
+
+/// Running balances from the opening balance, or from the closing balance
+/// back when there is none.
+fn with_balances(mut section: Section) -> Vec<StatementTransaction> {
+    section.transactions.sort_by(|a, b| a.date.cmp(&b.date));
+
+    let total: f64 = section.transactions.iter().map(|t| t.amount).sum();
+    let opening = section
+        .opening
+        .or_else(|| section.closing.map(|closing| closing - total));
+    if let Some(mut balance) = opening {
+        for transaction in &mut section.transactions {
+            balance = round(balance + transaction.amount);
+            transaction.balance = Some(balance);
+        }
+    }
+    section.transactions
+}
+
+/// No currency has more than 4 decimals, this only drops float noise.
+fn round(amount: f64) -> f64 {
+    (amount * 10_000.0).round() / 10_000.0
+}
+
+/// `1234.56`, `-1234,56` or `+12`.
+fn parse_amount(value: &str) -> Option<f64> {
+    let value = value.trim().trim_start_matches('+');
+    let value = if value.contains('.') {
+        value.to_string()
+    } else {
+        value.replace(',', ".")
+    };
+    value.parse().ok().filter(|amount: &f64| amount.is_finite())
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Collapse line breaks and runs of spaces, `None` when nothing is left.
+fn clean(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn unescape_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+// OFX
+
+enum OfxToken {
+    Open(String),
+    Close(String),
+    /// An element with a value. In SGML files these aren't closed.
+    Value(String, String),
+}
+
+/// Split OFX into tags, reading SGML and XML alike: a tag followed by text is
+/// a value, anything else opens or closes an aggregate.
+fn ofx_tokens(text: &str) -> Vec<OfxToken> {
+    let upper = text.to_ascii_uppercase();
+    let start = upper.find("<OFX>").unwrap_or(0);
+    let mut rest = &text[start..];
+
+    let mut tokens = Vec::new();
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|close| open + close) else {
+            break;
+        };
+        let tag = rest[open + 1..close].trim();
+        rest = &rest[close + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') || tag.ends_with('/') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(OfxToken::Close(name.trim().to_ascii_uppercase()));
+            continue;
+        }
+
+        let name = tag
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let value = rest[..rest.find('<').unwrap_or(rest.len())].trim();
+        if value.is_empty() {
+            tokens.push(OfxToken::Open(name));
+        } else {
+            tokens.push(OfxToken::Value(name, unescape_entities(value)));
+        }
+    }
+    tokens
+}
+
+#[derive(Default)]
+struct OfxTransaction {
+    date: Option<NaiveDate>,
+    amount: Option<f64>,
+    name: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+    /// Set when the amount is in another currency than the statement
+    currency: Option<String>,
+    rate: Option<f64>,
+}
+
+/// `20240115`, `20240115120000.000[-5:EST]`
+fn parse_ofx_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+fn parse_ofx(text: &str) -> Result<Vec<Section>, String> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut section: Option<Section> = None;
+    let mut transaction: Option<OfxTransaction> = None;
+    let mut stack: Vec<String> = Vec::new();
+
+    for token in ofx_tokens(text) {
+        match token {
+            OfxToken::Open(name) => {
+                match name.as_str() {
+                    "STMTRS" | "CCSTMTRS" => {
+                        sections.extend(section.take());
+                        section = Some(Section::default());
+                    }
+                    "STMTTRN" => transaction = Some(OfxTransaction::default()),
+                    _ => {}
+                }
+                stack.push(name);
+            }
+            OfxToken::Close(name) => {
+                // Closing tags of values (XML) aren't on the stack
+                let Some(position) = stack.iter().rposition(|open| *open == name) else {
+                    continue;
+                };
+                stack.truncate(position);
+
+                match name.as_str() {
+                    "STMTRS" | "CCSTMTRS" => sections.extend(section.take()),
+                    "STMTTRN" => {
+                        let Some(transaction) = transaction.take() else {
+                            continue;
+                        };
+                        let section = section.get_or_insert_with(Section::default);
+                        match ofx_transaction(transaction, section.currency.as_deref()) {
+                            Some(transaction) => section.transactions.push(transaction),
+                            None => eprintln!("🏦 Skipping OFX transaction without date or amount"),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            OfxToken::Value(name, value) => {
+                let parent = stack.last().map(String::as_str).unwrap_or_default();
+
+                if let Some(transaction) = transaction.as_mut() {
+                    match (parent, name.as_str()) {
+                        (_, "DTPOSTED") => transaction.date = parse_ofx_date(&value),
+                        (_, "TRNAMT") => transaction.amount = parse_amount(&value),
+                        ("PAYEE", "NAME") => transaction.payee = Some(value),
+                        (_, "NAME") => transaction.name = Some(value),
+                        (_, "MEMO") => transaction.memo = Some(value),
+                        ("CURRENCY", "CURSYM") => transaction.currency = Some(value),
+                        ("CURRENCY", "CURRATE") => transaction.rate = parse_amount(&value),
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                let section = section.get_or_insert_with(Section::default);
+                match (parent, name.as_str()) {
+                    (_, "CURDEF") => section.currency = Some(value.to_ascii_uppercase()),
+                    ("BANKACCTFROM" | "CCACCTFROM", "ACCTID") => section.account = Some(value),
+                    ("LEDGERBAL", "BALAMT") => section.closing = parse_amount(&value),
+                    _ => {}
+                }
+            }
+        }
+    }
+    sections.extend(section);
+
+    if sections.is_empty() {
+        return Err("No bank or credit card statement in the OFX file".to_string());
+    }
+    Ok(sections)
+}
+
+fn ofx_transaction(
+    transaction: OfxTransaction,
+    statement_currency: Option<&str>,
+) -> Option<StatementTransaction> {
+    let mut amount = transaction.amount?;
+    let mut currency = transaction.currency.clone();
+    // Amounts in another currency come with the rate to the statement's
+    if let (Some(rate), Some(statement_currency)) = (transaction.rate, statement_currency) {
+        amount = (amount * rate * 100.0).round() / 100.0;
+        currency = Some(statement_currency.to_string());
+    }
+
+    let counterparty = transaction.payee.or(transaction.name);
+    Some(StatementTransaction {
+        date: format_date(transaction.date?),
+        amount,
+        currency: currency
+            .or(statement_currency.map(str::to_string))
+            .unwrap_or_default(),
+        description: transaction
+            .memo
+            .as_deref()
+            .and_then(clean)
+            .or_else(|| counterparty.as_deref().and_then(clean)),
+        counterparty: counterparty.as_deref().and_then(clean),
+        balance: None,
+    })
+}
+
+// CAMT.053
+
+/// Just enough of a DOM to walk CAMT, names without namespace prefix.
+#[derive(Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn find(&self, path: &[&str]) -> Option<&Element> {
+        path.iter().try_fold(self, |element, name| {
+            element.children.iter().find(|child| child.name == *name)
+        })
+    }
+
+    fn text_at(&self, path: &[&str]) -> Option<&str> {
+        let text = self.find(path)?.text.trim();
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn parse_xml(text: &str) -> Result<Element, String> {
+    let mut reader = quick_xml::Reader::from_str(text);
+    let mut stack = vec![Element::default()];
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Invalid XML at {}: {}", reader.buffer_position(), e))?;
+        let is_empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(start) | Event::Empty(start) => {
+                let mut element = Element {
+                    name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+                    ..Default::default()
+                };
+                for attribute in start.attributes().flatten() {
+                    let value = attribute
+                        .unescape_value()
+                        .map_err(|e| format!("Invalid XML attribute: {}", e))?;
+                    element.attributes.push((
+                        String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string(),
+                        value.to_string(),
+                    ));
+                }
+
+                if is_empty {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                } else {
+                    stack.push(element);
+                }
+            }
+            Event::End(_) => {
+                if stack.len() > 1
+                    && let Some(element) = stack.pop()
+                    && let Some(parent) = stack.last_mut()
+                {
+                    parent.children.push(element);
+                }
+            }
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|e| format!("Invalid XML text: {}", e))?;
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    stack
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Empty XML document".to_string())
+}
+
+/// `Amt` with its `Ccy`, negative when `CdtDbtInd` says debit.
+fn camt_amount(amount: &Element, indicator: Option<&str>) -> Option<(f64, Option<String>)> {
+    let value = parse_amount(&amount.text)?;
+    let value = if indicator == Some("DBIT") {
+        -value
+    } else {
+        value
+    };
+    Some((value, amount.attribute("Ccy").map(str::to_string)))
+}
+
+/// `2024-03-01` or `2024-03-01T09:30:00`
+fn camt_date(element: &Element) -> Option<NaiveDate> {
+    let value = element.text_at(&["Dt"]).or(element.text_at(&["DtTm"]))?;
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn parse_camt(text: &str) -> Result<Vec<Section>, String> {
+    let root = parse_xml(text)?;
+    let statements = root
+        .find(&["Document", "BkToCstmrStmt"])
+        .or_else(|| root.find(&["BkToCstmrStmt"]))
+        .ok_or("No BkToCstmrStmt in the CAMT.053 file")?;
+
+    let mut sections = Vec::new();
+    for statement in statements.children("Stmt") {
+        let mut section = Section {
+            account: statement
+                .text_at(&["Acct", "Id", "IBAN"])
+                .or(statement.text_at(&["Acct", "Id", "Othr", "Id"]))
+                .map(str::to_string),
+            currency: statement.text_at(&["Acct", "Ccy"]).map(str::to_string),
+            ..Default::default()
+        };
+
+        for balance in statement.children("Bal") {
+            let Some((amount, currency)) = balance
+                .find(&["Amt"])
+                .and_then(|amount| camt_amount(amount, balance.text_at(&["CdtDbtInd"])))
+            else {
+                continue;
+            };
+            if section.currency.is_none() {
+                section.currency = currency;
+            }
+
+            match balance.text_at(&["Tp", "CdOrPrtry", "Cd"]) {
+                // Opening booked, or the previous statement's closing
+                Some("OPBD" | "PRCD") if section.opening.is_none() => {
+                    section.opening = Some(amount)
+                }
+                Some("CLBD") => section.closing = Some(amount),
+                _ => {}
+            }
+        }
+
+        for entry in statement.children("Ntry") {
+            section
+                .transactions
+                .extend(camt_entry(entry, section.currency.as_deref()));
+        }
+        sections.push(section);
+    }
+
+    if sections.is_empty() {
+        return Err("No statements in the CAMT.053 file".to_string());
+    }
+    Ok(sections)
+}
+
+/// One row per booked entry, or per transaction for batch bookings with
+/// the amount of each payment.
+fn camt_entry(entry: &Element, statement_currency: Option<&str>) -> Vec<StatementTransaction> {
+    // Pending and informational entries change once booked
+    let status = entry.text_at(&["Sts", "Cd"]).or(entry.text_at(&["Sts"]));
+    if status.is_some_and(|status| status != "BOOK") {
+        return Vec::new();
+    }
+
+    let indicator = entry.text_at(&["CdtDbtInd"]);
+    let Some(date) = entry
+        .find(&["BookgDt"])
+        .and_then(camt_date)
+        .or_else(|| entry.find(&["ValDt"]).and_then(camt_date))
+    else {
+        eprintln!("🏦 Skipping CAMT entry without booking date");
+        return Vec::new();
+    };
+    let Some((amount, currency)) = entry
+        .find(&["Amt"])
+        .and_then(|amount| camt_amount(amount, indicator))
+    else {
+        eprintln!("🏦 Skipping CAMT entry without amount");
+        return Vec::new();
+    };
+    let currency = currency
+        .or(statement_currency.map(str::to_string))
+        .unwrap_or_default();
+    let entry_info = entry.text_at(&["AddtlNtryInf"]);
+
+    let details: Vec<&Element> = entry
+        .children("NtryDtls")
+        .flat_map(|details| details.children("TxDtls"))
+        .collect();
+    let detail_amounts: Option<Vec<(f64, Option<String>)>> = details
+        .iter()
+        .map(|detail| {
+            let amount = detail
+                .find(&["Amt"])
+                .or(detail.find(&["AmtDtls", "TxAmt", "Amt"]))?;
+            camt_amount(amount, detail.text_at(&["CdtDbtInd"]).or(indicator))
+        })
+        .collect();
+
+    match detail_amounts {
+        Some(amounts) if details.len() > 1 => details
+            .iter()
+            .zip(amounts)
+            .map(|(detail, (amount, detail_currency))| StatementTransaction {
+                date: format_date(date),
+                amount,
+                currency: detail_currency.unwrap_or_else(|| currency.clone()),
+                description: camt_description(Some(detail)).or(entry_info.and_then(clean)),
+                counterparty: camt_counterparty(detail, amount),
+                balance: None,
+            })
+            .collect(),
+        _ => {
+            let detail = details.first().copied();
+            vec![StatementTransaction {
+                date: format_date(date),
+                amount,
+                currency,
+                description: camt_description(detail).or(entry_info.and_then(clean)),
+                counterparty: detail.and_then(|detail| camt_counterparty(detail, amount)),
+                balance: None,
+            }]
+        }
+    }
+}
+
+/// The sender for money coming in, the recipient for money going out.
+fn camt_counterparty(detail: &Element, amount: f64) -> Option<String> {
+    let parties = if amount < 0.0 {
+        ["Cdtr", "UltmtCdtr"]
+    } else {
+        ["Dbtr", "UltmtDbtr"]
+    };
+    parties.iter().find_map(|party| {
+        detail
+            .text_at(&["RltdPties", party, "Nm"])
+            // Version 8 and later wrap the party
+            .or(detail.text_at(&["RltdPties", party, "Pty", "Nm"]))
+            .and_then(clean)
+    })
+}
+
+/// Unstructured remittance info, or the creditor reference, or what the bank
+/// added.
+fn camt_description(detail: Option<&Element>) -> Option<String> {
+    let detail = detail?;
+    let unstructured: Vec<&str> = detail
+        .find(&["RmtInf"])
+        .map(|info| {
+            info.children("Ustrd")
+                .map(|line| line.text.trim())
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    clean(&unstructured.join(" "))
+        .or_else(|| {
+            detail
+                .text_at(&["RmtInf", "Strd", "CdtrRefInf", "Ref"])
+                .and_then(clean)
+        })
+        .or_else(|| detail.text_at(&["AddtlTxInf"]).and_then(clean))
+}
+
+// MT940
+
+/// `240308`, years are this century.
+fn parse_mt940_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("20{}", value.get(..6)?), "%Y%m%d").ok()
+}
+
+/// `C240308EUR2500,00`
+fn parse_mt940_balance(value: &str) -> Option<(f64, String)> {
+    let captures = MT940_BALANCE.captures(value.trim())?;
+    let amount = parse_amount(&captures[4])?;
+    let amount = if &captures[1] == "D" { -amount } else { amount };
+    Some((amount, captures[3].to_string()))
+}
+
+/// Fields in order as (tag, value), continuation lines joined with `\n`.
+fn mt940_fields(text: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        // SWIFT envelope around the message
+        let line = line.strip_prefix("{4:").unwrap_or(line);
+        if line.starts_with('{') || line == "-" || line.starts_with("-}") {
+            continue;
+        }
+
+        if let Some(captures) = MT940_FIELD.captures(line) {
+            fields.push((captures[1].to_string(), captures[2].to_string()));
+        } else if let Some((_, value)) = fields.last_mut()
+            && !line.is_empty()
+        {
+            value.push('\n');
+            value.push_str(line);
+        }
+    }
+    fields
+}
+
+fn parse_mt940(text: &str) -> Result<Vec<Section>, String> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut section: Option<Section> = None;
+
+    for (tag, value) in mt940_fields(text) {
+        match tag.as_str() {
+            "20" => {
+                sections.extend(section.take());
+                section = Some(Section::default());
+            }
+            "25" => {
+                section.get_or_insert_with(Section::default).account =
+                    Some(value.trim().to_string())
+            }
+            "60F" | "60M" => {
+                let section = section.get_or_insert_with(Section::default);
+                if let Some((amount, currency)) = parse_mt940_balance(&value) {
+                    section.opening = Some(amount);
+                    section.currency = Some(currency);
+                }
+            }
+            "62F" | "62M" => {
+                let section = section.get_or_insert_with(Section::default);
+                if let Some((amount, _)) = parse_mt940_balance(&value) {
+                    section.closing = Some(amount);
+                }
+            }
+            "61" => {
+                let section = section.get_or_insert_with(Section::default);
+                match mt940_transaction(&value, section.currency.as_deref()) {
+                    Some(transaction) => section.transactions.push(transaction),
+                    None => eprintln!("🏦 Skipping unreadable MT940 line {:?}", value),
+                }
+            }
+            "86" => {
+                // Details of the `:61:` line right before
+                let Some(transaction) = section
+                    .as_mut()
+                    .and_then(|section| section.transactions.last_mut())
+                else {
+                    continue;
+                };
+                let (description, counterparty) = mt940_details(&value);
+                if description.is_some() {
+                    transaction.description = description;
+                }
+                transaction.counterparty = counterparty;
+            }
+            _ => {}
+        }
+    }
+    sections.extend(section);
+
+    if sections.is_empty() {
+        return Err("No statements in the MT940 file".to_string());
+    }
+    Ok(sections)
+}
+
+/// `2403080308DR89,99NDDTNONREF//0311A0042`, with supplementary details on
+/// the next line.
+fn mt940_transaction(value: &str, currency: Option<&str>) -> Option<StatementTransaction> {
+    let mut lines = value.lines();
+    let captures = MT940_LINE.captures(lines.next()?.trim())?;
+
+    let value_date = parse_mt940_date(&captures[1])?;
+    // The entry date has no year, it is the value date's unless they straddle
+    // New Year
+    let date = match captures.get(2) {
+        Some(entry) => {
+            let month: u32 = entry.as_str()[..2].parse().ok()?;
+            let day: u32 = entry.as_str()[2..].parse().ok()?;
+            let year = match (value_date.month(), month) {
+                (12, 1) => value_date.year() + 1,
+                (1, 12) => value_date.year() - 1,
+                _ => value_date.year(),
+            };
+            NaiveDate::from_ymd_opt(year, month, day).unwrap_or(value_date)
+        }
+        None => value_date,
+    };
+
+    let amount = parse_amount(&captures[5])?;
+    // A reversed credit takes money out, a reversed debit brings it back
+    let amount = match &captures[3] {
+        "D" | "ED" | "RC" => -amount,
+        _ => amount,
+    };
+
+    Some(StatementTransaction {
+        date: format_date(date),
+        amount,
+        currency: currency.unwrap_or_default().to_string(),
+        description: lines.next().and_then(clean),
+        counterparty: None,
+        balance: None,
+    })
+}
+
+/// Description and counterparty from `:86:`, which banks fill in one of
+/// three ways: German `?20` subfields, Dutch `/NAME/` keys or free text.
+fn mt940_details(value: &str) -> (Option<String>, Option<String>) {
+    // Lines are wrapped at a fixed width, mid-word
+    let joined: String = value.lines().collect();
+
+    // `166?00SEPA-GUTSCHRIFT?20...`, the separator is usually `?`
+    let separator = joined.chars().nth(3);
+    if joined.len() > 4
+        && joined.chars().take(3).all(|c| c.is_ascii_digit())
+        && let Some(separator) = separator.filter(|c| !c.is_alphanumeric() && !c.is_whitespace())
+    {
+        let mut purpose = String::new();
+        let mut name = String::new();
+        let mut posting_text = String::new();
+        for subfield in joined.split(separator).skip(1) {
+            let code_end = subfield
+                .char_indices()
+                .nth(2)
+                .map_or(subfield.len(), |(index, _)| index);
+            let (code, text) = subfield.split_at(code_end);
+            match code {
+                "00" => posting_text.push_str(text),
+                "20" | "21" | "22" | "23" | "24" | "25" | "26" | "27" | "28" | "29" | "60"
+                | "61" | "62" | "63" => purpose.push_str(text),
+                "32" | "33" => name.push_str(text),
+                _ => {}
+            }
+        }
+
+        // Only what the sender entered, not the mandate and end to end ids
+        let sender_text = purpose.find("SVWZ+").map(|start| {
+            let text = &purpose[start + 5..];
+            let end = SEPA_KEY
+                .find(text)
+                .map(|key| key.start())
+                .unwrap_or(text.len());
+            &text[..end]
+        });
+        let description = sender_text
+            .and_then(clean)
+            .or_else(|| clean(&purpose))
+            .or_else(|| clean(&posting_text));
+        return (description, clean(&name));
+    }
+
+    if MT940_SLASH_KEY.is_match(&joined) {
+        let keys: Vec<_> = MT940_SLASH_KEY.captures_iter(&joined).collect();
+        let mut description = None;
+        let mut counterparty = None;
+        for (index, captures) in keys.iter().enumerate() {
+            let whole = captures.get(0).unwrap();
+            let end = keys
+                .get(index + 1)
+                .map(|next| next.get(0).unwrap().start())
+                .unwrap_or(joined.len());
+            let text = joined[whole.end()..end].trim_matches('/');
+
+            match &captures[1] {
+                "NAME" => counterparty = counterparty.or(clean(text)),
+                // Account, BIC, name, city
+                "CNTP" => counterparty = counterparty.or(text.split('/').nth(2).and_then(clean)),
+                "REMI" => {
+                    let text = text
+                        .strip_prefix("USTD//")
+                        .or_else(|| text.strip_prefix("STRD/CUR/"))
+                        .unwrap_or(text);
+                    description = clean(text.trim_matches('/'));
+                }
+                _ => {}
+            }
+        }
+        return (description, counterparty);
+    }
+
+    (clean(&value.lines().collect::<Vec<_>>().join(" ")), None)
+}
+
+/// Parse an OFX/QFX, CAMT.053 or MT940 bank statement sent as the raw request
+/// body into rows for the CSV import.
+#[tauri::command]
+pub async fn parse_statement(request: tauri::ipc::Request<'_>) -> Result<Statement, String> {
+    let tauri::ipc::InvokeBody::Raw(bytes) = request.body() else {
+        return Err("Expected a binary body".to_string());
+    };
+
+    let bytes = bytes.clone();
+    let statement = tauri::async_runtime::spawn_blocking(move || parse(&bytes))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    println!(
+        "🏦 Parsed {} transaction(s) from {:?} statement",
+        statement.transactions.len(),
+        statement.format
+    );
+    Ok(statement)
+}
+
+/// Parse a bank statement picked for the import with `pick_import_file` or
+/// `import_dropped_file`.
+#[tauri::command]
+pub async fn parse_statement_file(app: tauri::AppHandle, id: u64) -> Result<Statement, String> {
+    let path = csv_import::picked_path(&app, id)?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let statement = tauri::async_runtime::spawn_blocking(move || parse(&bytes))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    println!(
+        "🏦 Parsed {} transaction(s) from {:?} statement",
+        statement.transactions.len(),
+        statement.format
+    );
+    Ok(statement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Statement {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/statements")
+            .join(name);
+        parse(&std::fs::read(&path).unwrap()).unwrap()
+    }
+
+    fn row(
+        date: &str,
+        amount: f64,
+        currency: &str,
+        description: Option<&str>,
+        counterparty: Option<&str>,
+        balance: Option<f64>,
+    ) -> StatementTransaction {
+        StatementTransaction {
+            date: date.to_string(),
+            amount,
+            currency: currency.to_string(),
+            description: description.map(str::to_string),
+            counterparty: counterparty.map(str::to_string),
+            balance,
+        }
+    }
+
+    #[test]
+    fn ofx_sgml() {
+        let statement = fixture("checking.ofx");
+        assert_eq!(statement.format, StatementFormat::Ofx);
+        assert_eq!(statement.account.as_deref(), Some("000123456789"));
+        assert_eq!(statement.currency.as_deref(), Some("USD"));
+        assert_eq!(
+            statement.transactions,
+            vec![
+                row(
+                    "2024-01-03",
+                    -1200.0,
+                    "USD",
+                    Some("Rent & parking"),
+                    Some("Oak Street Properties"),
+                    Some(1300.0),
+                ),
+                row(
+                    "2024-01-15",
+                    2500.0,
+                    "USD",
+                    Some("Salary January"),
+                    Some("ACME PAYROLL"),
+                    Some(3800.0),
+                ),
+                row(
+                    "2024-01-22",
+                    -45.9,
+                    "USD",
+                    Some("POS PURCHASE 4411"),
+                    Some("Café Lumière"),
+                    Some(3754.1),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn qfx_xml() {
+        let statement = fixture("credit-card.qfx");
+        assert_eq!(statement.format, StatementFormat::Ofx);
+        assert_eq!(statement.account.as_deref(), Some("XXXXXXXXXXXX4242"));
+        assert_eq!(
+            statement.transactions,
+            vec![
+                row(
+                    "2024-02-05",
+                    -19.99,
+                    "EUR",
+                    Some("Streamly Subscription"),
+                    Some("Streamly Subscription"),
+                    Some(-382.88),
+                ),
+                row(
+                    "2024-02-12",
+                    -79.49,
+                    "EUR",
+                    Some("Booking 77123"),
+                    Some("Hotel Seaside"),
+                    Some(-462.37),
+                ),
+                row(
+                    "2024-02-20",
+                    150.0,
+                    "EUR",
+                    Some("Payment - Thank You"),
+                    Some("Payment - Thank You"),
+                    Some(-312.37),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn camt053() {
+        let statement = fixture("camt053.xml");
+        assert_eq!(statement.format, StatementFormat::Camt053);
+        assert_eq!(statement.account.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(statement.currency.as_deref(), Some("EUR"));
+        assert_eq!(
+            statement.transactions,
+            vec![
+                row(
+                    "2024-03-01",
+                    1250.0,
+                    "EUR",
+                    Some("Invoice 2024-017 Thank you"),
+                    Some("Nordlicht GmbH"),
+                    Some(2250.0),
+                ),
+                row(
+                    "2024-03-02",
+                    -120.0,
+                    "EUR",
+                    Some("RF18539007547034"),
+                    Some("Stadtwerke München"),
+                    Some(2130.0),
+                ),
+                row(
+                    "2024-03-02",
+                    -180.0,
+                    "EUR",
+                    Some("Order 5521"),
+                    Some("Office & More"),
+                    Some(1950.0),
+                ),
+                row(
+                    "2024-03-04",
+                    -35.7,
+                    "EUR",
+                    Some("Account maintenance fee"),
+                    None,
+                    Some(1914.3),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn camt053_version_8() {
+        let statement = fixture("camt053-v8.xml");
+        assert_eq!(statement.account.as_deref(), Some("CH9300762011623852957"));
+        assert_eq!(
+            statement.transactions,
+            vec![
+                row(
+                    "2024-04-08",
+                    850.0,
+                    "CHF",
+                    Some("210000000003139471430009017"),
+                    Some("Alpenblick AG"),
+                    Some(800.0),
+                ),
+                row(
+                    "2024-04-09",
+                    -70.45,
+                    "CHF",
+                    Some("eBill April"),
+                    Some("Swisscom (Schweiz) AG"),
+                    Some(729.55),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn mt940_german_subfields() {
+        let statement = fixture("mt940.sta");
+        assert_eq!(statement.format, StatementFormat::Mt940);
+        assert_eq!(statement.account.as_deref(), Some("37040044/0532013000"));
+        assert_eq!(statement.currency.as_deref(), Some("EUR"));
+        assert_eq!(
+            statement.transactions,
+            vec![
+                row(
+                    "2024-03-08",
+                    -89.99,
+                    "EUR",
+                    Some("Stromabschlag März 2024"),
+                    Some("Grünstrom Energie GmbH"),
+                    Some(2410.01),
+                ),
+                row(
+                    "2024-03-11",
+                    1500.0,
+                    "EUR",
+                    Some("Rechnung 2024-031"),
+                    Some("Muster Consulting"),
+                    Some(3910.01),
+                ),
+                row(
+                    "2024-03-11",
+                    -12.5,
+                    "EUR",
+                    Some("Kontofuehrungsgebuehr Maerz 2024"),
+                    None,
+                    Some(3897.51),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn mt940_subfield_codes_with_non_ascii() {
+        assert_eq!(
+            mt940_details("166?2€uro?20Miete?32Müller?€"),
+            (Some("Miete".to_string()), Some("Müller".to_string()))
+        );
+    }
+
+    #[test]
+    fn mt940_slash_keys_and_reversal() {
+        let statement = fixture("ing.940");
+        assert_eq!(statement.account.as_deref(), Some("NL20INGB0001234567"));
+        assert_eq!(
+            statement.transactions,
+            vec![
+                row(
+                    "2024-04-12",
+                    -42.15,
+                    "EUR",
+                    Some("Factuur 2024-04 klant 6612"),
+                    Some("Netwerk Telecom B.V."),
+                    Some(957.85),
+                ),
+                row(
+                    "2024-04-15",
+                    600.0,
+                    "EUR",
+                    Some("INV-88 website"),
+                    Some("De Vries Design"),
+                    Some(1557.85),
+                ),
+                row(
+                    "2024-04-16",
+                    -600.0,
+                    "EUR",
+                    Some("Reversal INV-88"),
+                    Some("De Vries Design"),
+                    Some(957.85),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse(b"Date,Amount\n2024-01-01,12.50\n").is_err());
+        assert!(parse(b"<?xml version=\"1.0\"?><Document><CstmrCdtTrfInitn/></Document>").is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// Path of a JSON file in the app config directory (user settings).
+pub fn config_file(app: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve config directory: {}", e))?;
+    Ok(dir.join(name))
+}
+
+/// Path of a JSON file in the app data directory (state the app keeps for itself).
+pub fn data_file(app: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data directory: {}", e))?;
+    Ok(dir.join(name))
+}
+
+/// Read a JSON file, falling back to the default value when it is missing or
+/// unreadable so a corrupt file never prevents the app from starting.
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> T {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            eprintln!("💾 Ignoring unreadable {:?}: {}", path, e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Write a JSON file through a temporary file so a crash never leaves it half written.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+
+    let bytes = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, bytes).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    std::fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+
+    Ok(())
+}
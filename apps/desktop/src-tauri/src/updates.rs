@@ -0,0 +1,630 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::menu::MenuItem;
+use tauri::{Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+#[cfg(desktop)]
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::capture;
+use crate::store;
+
+const TRAY_LABEL: &str = "Check for Updates...";
+/// Progress for downloads without a length is reported every this many bytes.
+const PROGRESS_STEP: u64 = 1024 * 1024;
+const PREFERENCES_FILE: &str = "updates.json";
+const DEFAULT_REMIND_DAYS: u64 = 3;
+
+/// Tray menu item for checking for updates, which shows the download
+/// progress, installs a downloaded update and restarts once one is installed.
+pub struct UpdateTrayItem(pub MenuItem<tauri::Wry>);
+
+#[derive(Default)]
+pub struct UpdateState {
+    inner: Mutex<UpdateInner>,
+}
+
+#[derive(Default)]
+struct UpdateInner {
+    /// An update is downloading or installing
+    busy: bool,
+    /// Downloaded in the background, waiting for the user to choose
+    #[cfg(desktop)]
+    ready: Option<(Update, Vec<u8>)>,
+    /// Install `ready` when the app quits
+    install_on_quit: bool,
+    /// Version installed and waiting for a restart
+    installed: Option<String>,
+}
+
+/// Choices for an update downloaded in the background, kept in `updates.json`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct UpdatePreferences {
+    /// Not offered again, newer releases are
+    skipped_version: Option<String>,
+    /// Not offered again before `remind_after`, newer releases are
+    snoozed_version: Option<String>,
+    /// Unix seconds
+    remind_after: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateAction {
+    InstallNow,
+    InstallOnQuit,
+    Skip,
+    RemindLater,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadyUpdate {
+    version: String,
+    notes: Option<String>,
+    install_on_quit: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateProgress {
+    version: String,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateFailed {
+    version: String,
+    error: String,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn load_preferences(app: &tauri::AppHandle) -> UpdatePreferences {
+    store::config_file(app, PREFERENCES_FILE)
+        .map(|path| store::load(&path))
+        .unwrap_or_default()
+}
+
+fn save_preferences(app: &tauri::AppHandle, preferences: &UpdatePreferences) -> Result<(), String> {
+    store::save(&store::config_file(app, PREFERENCES_FILE)?, preferences)
+}
+
+fn set_tray(app: &tauri::AppHandle, text: &str, enabled: bool) {
+    if let Some(item) = app.try_state::<UpdateTrayItem>() {
+        let _ = item.0.set_text(text);
+        let _ = item.0.set_enabled(enabled);
+    }
+}
+
+/// Show a message dialog without blocking a runtime worker. Returns whether
+/// the first button was chosen.
+async fn ask(
+    app: &tauri::AppHandle,
+    title: &str,
+    message: String,
+    kind: MessageDialogKind,
+    buttons: MessageDialogButtons,
+) -> bool {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(kind)
+        .buttons(buttons)
+        .show(move |answer| {
+            let _ = sender.send(answer);
+        });
+    receiver.await.unwrap_or(false)
+}
+
+/// The tray item restarts into an installed update, installs a downloaded
+/// one, or checks for one.
+pub fn tray_clicked(app: &tauri::AppHandle) {
+    let state = app.state::<UpdateState>();
+    let inner = state.inner.lock().unwrap();
+    if inner.installed.is_some() {
+        drop(inner);
+        app.request_restart();
+        return;
+    }
+    if inner.busy {
+        return;
+    }
+    #[cfg(desktop)]
+    let ready = inner.ready.is_some();
+    #[cfg(not(desktop))]
+    let ready = false;
+    drop(inner);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if ready {
+            let _ = update_action(app, UpdateAction::InstallNow, None).await;
+        } else {
+            let _ = check_for_updates(app).await;
+        }
+    });
+}
+
+/// Ask to download and install an update found by a manual check.
+#[cfg(desktop)]
+async fn prompt_and_install_update(app: &tauri::AppHandle, update: Update) {
+    let answer = ask(
+        app,
+        "Update Available",
+        format!(
+            "A new version {} is available. Would you like to update now?",
+            update.version
+        ),
+        MessageDialogKind::Info,
+        MessageDialogButtons::OkCancel,
+    )
+    .await;
+
+    if answer && start_install(app) {
+        install(app, update, None, true).await;
+    }
+}
+
+/// Claim `busy` for an install. False when a download or install is running.
+#[cfg(desktop)]
+fn start_install(app: &tauri::AppHandle) -> bool {
+    let state = app.state::<UpdateState>();
+    let mut inner = state.inner.lock().unwrap();
+    !std::mem::replace(&mut inner.busy, true)
+}
+
+/// Install with progress, offering a retry when it fails. Once done it asks
+/// to restart, or restarts right away without `ask_restart`. The caller has
+/// claimed `busy`.
+#[cfg(desktop)]
+async fn install(
+    app: &tauri::AppHandle,
+    update: Update,
+    mut bytes: Option<Vec<u8>>,
+    ask_restart: bool,
+) {
+    let result = loop {
+        // A failed download or install starts over with a fresh download
+        match download_and_install(app, &update, bytes.take()).await {
+            Ok(()) => break Ok(()),
+            Err(e) => {
+                eprintln!("🔄 Update to {} failed: {}", update.version, e);
+                set_tray(app, TRAY_LABEL, false);
+                let _ = app.emit(
+                    "update-failed",
+                    UpdateFailed {
+                        version: update.version.clone(),
+                        error: e.clone(),
+                    },
+                );
+
+                let retry = ask(
+                    app,
+                    "Update Failed",
+                    format!("Midday {} couldn't be installed.\n\n{}", update.version, e),
+                    MessageDialogKind::Error,
+                    MessageDialogButtons::OkCancelCustom("Retry".into(), "Cancel".into()),
+                )
+                .await;
+                if !retry {
+                    break Err(e);
+                }
+            }
+        }
+    };
+
+    {
+        let state = app.state::<UpdateState>();
+        let mut inner = state.inner.lock().unwrap();
+        inner.busy = false;
+        inner.install_on_quit = false;
+        if result.is_ok() {
+            inner.installed = Some(update.version.clone());
+        }
+    }
+    if result.is_err() {
+        set_tray(app, TRAY_LABEL, true);
+        return;
+    }
+
+    println!("🔄 Installed update {}", update.version);
+    set_tray(
+        app,
+        &format!("Restart to Update to {}", update.version),
+        true,
+    );
+    let _ = app.emit(
+        "update-installed",
+        serde_json::json!({ "version": update.version }),
+    );
+
+    let restart = !ask_restart
+        || ask(
+            app,
+            "Update Installed",
+            format!(
+                "Midday {} is installed. Restart now to start using it?",
+                update.version
+            ),
+            MessageDialogKind::Info,
+            MessageDialogButtons::OkCancelCustom("Restart Now".into(), "Later".into()),
+        )
+        .await;
+    if restart {
+        // Through the exit events like any quit, so `RunEvent::Exit` still
+        // runs. The process plugin's `restart` command does the same from
+        // JavaScript, which the native dialog doesn't need.
+        app.request_restart();
+    }
+}
+
+#[cfg(desktop)]
+async fn download_and_install(
+    app: &tauri::AppHandle,
+    update: &Update,
+    bytes: Option<Vec<u8>>,
+) -> Result<(), String> {
+    let bytes = match bytes {
+        Some(bytes) => bytes,
+        None => download(app, update).await?,
+    };
+
+    set_tray(app, "Installing Update...", false);
+    let update = update.clone();
+    tauri::async_runtime::spawn_blocking(move || update.install(bytes))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Install failed: {}", e))
+}
+
+/// Emits `update-progress` as the download goes, once per percent.
+#[cfg(desktop)]
+async fn download(app: &tauri::AppHandle, update: &Update) -> Result<Vec<u8>, String> {
+    set_tray(app, "Downloading Update...", false);
+
+    let mut downloaded = 0u64;
+    let mut reported = None;
+    update
+        .download(
+            |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                let step = match content_length {
+                    Some(total) => downloaded * 100 / total.max(1),
+                    None => downloaded / PROGRESS_STEP,
+                };
+                if reported == Some(step) {
+                    return;
+                }
+                reported = Some(step);
+
+                if content_length.is_some() {
+                    set_tray(
+                        app,
+                        &format!("Downloading Update... {}%", step.min(100)),
+                        false,
+                    );
+                }
+                let _ = app.emit(
+                    "update-progress",
+                    UpdateProgress {
+                        version: update.version.clone(),
+                        downloaded,
+                        total: content_length,
+                    },
+                );
+            },
+            || println!("🔄 Downloaded update {}", update.version),
+        )
+        .await
+        .map_err(|e| format!("Download failed: {}", e))
+}
+
+/// Background update check, used on startup and by the periodic timer.
+/// Downloads a new version without asking, then lets the user pick when to
+/// install it with `update_action`.
+#[cfg(desktop)]
+pub async fn silent_update_check(app: tauri::AppHandle) {
+    {
+        let state = app.state::<UpdateState>();
+        let inner = state.inner.lock().unwrap();
+        if inner.busy || inner.ready.is_some() || inner.installed.is_some() {
+            return;
+        }
+    }
+
+    let Ok(updater) = app.updater() else {
+        return;
+    };
+    let update = match updater.check().await {
+        Ok(Some(update)) => update,
+        Ok(None) => {
+            println!("No updates available");
+            return;
+        }
+        Err(e) => {
+            println!("Silent update check failed: {}", e);
+            return;
+        }
+    };
+
+    let preferences = load_preferences(&app);
+    if preferences.skipped_version.as_deref() == Some(update.version.as_str()) {
+        println!("🔄 Skipping update {} as asked", update.version);
+        return;
+    }
+    if preferences.snoozed_version.as_deref() == Some(update.version.as_str())
+        && preferences
+            .remind_after
+            .is_some_and(|remind_after| unix_now() < remind_after)
+    {
+        println!("🔄 Update {} snoozed", update.version);
+        return;
+    }
+
+    println!("Update available: {}", update.version);
+    {
+        let state = app.state::<UpdateState>();
+        let mut inner = state.inner.lock().unwrap();
+        if inner.busy {
+            return;
+        }
+        inner.busy = true;
+    }
+    let result = download(&app, &update).await;
+
+    let state = app.state::<UpdateState>();
+    let mut inner = state.inner.lock().unwrap();
+    inner.busy = false;
+    let bytes = match result {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            // Tried again at the next check
+            drop(inner);
+            eprintln!("🔄 Background download of {} failed: {}", update.version, e);
+            set_tray(&app, TRAY_LABEL, true);
+            return;
+        }
+    };
+
+    let ready = ReadyUpdate {
+        version: update.version.clone(),
+        notes: update.body.clone(),
+        install_on_quit: false,
+    };
+    inner.ready = Some((update, bytes));
+    drop(inner);
+
+    set_tray(&app, &format!("Install Update to {}", ready.version), true);
+    capture::notify(
+        &app,
+        &format!("Midday {} is ready to install", ready.version),
+        "Install it now, when you quit, or skip it from Midday or the tray menu.",
+    );
+    let _ = app.emit("update-ready", ready);
+}
+
+/// The update downloaded in the background, if any, for a dashboard that
+/// missed `update-ready`.
+#[tauri::command]
+pub fn pending_update(state: tauri::State<'_, UpdateState>) -> Option<ReadyUpdate> {
+    #[cfg(desktop)]
+    {
+        let inner = state.inner.lock().unwrap();
+        inner.ready.as_ref().map(|(update, _)| ReadyUpdate {
+            version: update.version.clone(),
+            notes: update.body.clone(),
+            install_on_quit: inner.install_on_quit,
+        })
+    }
+
+    #[cfg(not(desktop))]
+    {
+        let _ = state;
+        None
+    }
+}
+
+/// Decide what happens to the update downloaded in the background.
+/// `days` is how long `remindLater` waits, 3 by default.
+#[tauri::command]
+pub async fn update_action(
+    app: tauri::AppHandle,
+    action: UpdateAction,
+    days: Option<u64>,
+) -> Result<(), String> {
+    #[cfg(desktop)]
+    {
+        let (version, ready) = {
+            let state = app.state::<UpdateState>();
+            let mut inner = state.inner.lock().unwrap();
+            let version = inner
+                .ready
+                .as_ref()
+                .map(|(update, _)| update.version.clone())
+                .ok_or("No update is ready to install")?;
+            let ready = match action {
+                // Only taken once the install is ours, it would be lost otherwise
+                UpdateAction::InstallNow if inner.busy => {
+                    return Err("An update is already being installed".to_string());
+                }
+                UpdateAction::InstallNow => {
+                    inner.busy = true;
+                    inner.ready.take()
+                }
+                UpdateAction::InstallOnQuit => {
+                    inner.install_on_quit = true;
+                    None
+                }
+                UpdateAction::Skip | UpdateAction::RemindLater => {
+                    inner.ready = None;
+                    inner.install_on_quit = false;
+                    None
+                }
+            };
+            (version, ready)
+        };
+        println!("🔄 {:?} for update {}", action, version);
+
+        match action {
+            UpdateAction::InstallNow => {
+                if let Some((update, bytes)) = ready {
+                    install(&app, update, Some(bytes), false).await;
+                }
+            }
+            UpdateAction::InstallOnQuit => {
+                set_tray(
+                    &app,
+                    &format!("Update to {} Installs on Quit", version),
+                    true,
+                );
+            }
+            UpdateAction::Skip | UpdateAction::RemindLater => {
+                let mut preferences = load_preferences(&app);
+                if matches!(action, UpdateAction::Skip) {
+                    preferences.skipped_version = Some(version);
+                } else {
+                    let days = days.unwrap_or(DEFAULT_REMIND_DAYS).max(1);
+                    preferences.snoozed_version = Some(version);
+                    preferences.remind_after = Some(unix_now() + days * 24 * 60 * 60);
+                }
+                save_preferences(&app, &preferences)?;
+                set_tray(&app, TRAY_LABEL, true);
+            }
+        }
+    }
+
+    #[cfg(not(desktop))]
+    {
+        let _ = (app, action, days);
+    }
+
+    Ok(())
+}
+
+/// Install an update the user chose to install on quit. Called as the app
+/// exits, where the installer can replace it.
+pub fn install_on_exit(app: &tauri::AppHandle) {
+    #[cfg(desktop)]
+    {
+        let Some(state) = app.try_state::<UpdateState>() else {
+            return;
+        };
+        let mut inner = state.inner.lock().unwrap();
+        if !inner.install_on_quit {
+            return;
+        }
+        let Some((update, bytes)) = inner.ready.take() else {
+            return;
+        };
+
+        println!("🔄 Installing update {} on quit", update.version);
+        if let Err(e) = update.install(bytes) {
+            eprintln!(
+                "🔄 Failed to install update {} on quit: {}",
+                update.version, e
+            );
+        }
+    }
+
+    #[cfg(not(desktop))]
+    let _ = app;
+}
+
+/// Manual update check (triggered from tray menu).
+/// Shows dialogs for all outcomes: update available, up-to-date, and errors.
+/// Skipped and snoozed versions are offered too.
+#[tauri::command]
+pub async fn check_for_updates(app: tauri::AppHandle) -> Result<(), String> {
+    #[cfg(desktop)]
+    {
+        // Offer the one already downloaded instead of downloading it again
+        let ready_version = {
+            let state = app.state::<UpdateState>();
+            let inner = state.inner.lock().unwrap();
+            inner
+                .ready
+                .as_ref()
+                .map(|(update, _)| update.version.clone())
+        };
+        if let Some(version) = ready_version {
+            let answer = ask(
+                &app,
+                "Update Available",
+                format!(
+                    "A new version {} is downloaded. Would you like to install it now?",
+                    version
+                ),
+                MessageDialogKind::Info,
+                MessageDialogButtons::OkCancel,
+            )
+            .await;
+            if answer {
+                update_action(app, UpdateAction::InstallNow, None).await?;
+            }
+            return Ok(());
+        }
+
+        if let Ok(updater) = app.updater() {
+            match updater.check().await {
+                Ok(Some(update)) => {
+                    prompt_and_install_update(&app, update).await;
+                }
+                Ok(None) => {
+                    let version = app.package_info().version.to_string();
+                    ask(
+                        &app,
+                        "No Updates Available",
+                        format!("Midday\nversion {}\n\nYou're up to date!", version),
+                        MessageDialogKind::Info,
+                        MessageDialogButtons::Ok,
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    ask(
+                        &app,
+                        "Update Check Failed",
+                        format!("Failed to check for updates: {}", e),
+                        MessageDialogKind::Error,
+                        MessageDialogButtons::Ok,
+                    )
+                    .await;
+                }
+            }
+        } else {
+            ask(
+                &app,
+                "Updates Not Available",
+                "Update checking is not available in this build.".to_string(),
+                MessageDialogKind::Warning,
+                MessageDialogButtons::Ok,
+            )
+            .await;
+        }
+    }
+
+    #[cfg(not(desktop))]
+    {
+        ask(
+            &app,
+            "Check App Store",
+            "Updates are managed through your app store.".to_string(),
+            MessageDialogKind::Info,
+            MessageDialogButtons::Ok,
+        )
+        .await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1274 @@
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+use tokio::sync::{Notify, Semaphore, oneshot};
+use tokio_util::sync::CancellationToken;
+
+use crate::duplicates::{self, DuplicateAction, DuplicateMatch};
+use crate::email::EmailMetadata;
+use crate::frontend_queue::FrontendQueue;
+use crate::payment_codes::PaymentCode;
+use crate::preprocess;
+use crate::qr;
+use crate::receipt::{self, ReceiptHints};
+use crate::store;
+
+/// Storage bucket used by the dashboard for both Inbox and Vault files
+const BUCKET: &str = "vault";
+
+/// Read size for streaming uploads, also how often progress is reported
+const CHUNK_SIZE: usize = 256 * 1024;
+
+const QUEUE_FILE: &str = "upload-queue.json";
+const MAX_CONCURRENT_UPLOADS: usize = 3;
+/// Scans can run `pdftoppm`, so only a few at a time
+const MAX_CONCURRENT_SCANS: usize = 2;
+
+/// After this many attempts a job stays failed until `retry_upload` is called.
+const MAX_ATTEMPTS: u32 = 8;
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30 * 60);
+
+/// How often jobs parked while offline check whether the connection is back.
+const OFFLINE_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Asks the dashboard to upload a watched file with `tauri_plugin_upload`.
+const PLUGIN_UPLOAD_EVENT: &str = "plugin-upload-requested";
+/// A transfer through the dashboard that takes longer is retried.
+const PLUGIN_UPLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadTarget {
+    Inbox,
+    Vault,
+}
+
+/// Where a job came from, shown in the dashboard's upload list.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadSource {
+    Drop,
+    Watch,
+    Cli,
+    Capture,
+    Clipboard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadStatus {
+    Queued,
+    Uploading,
+    Failed,
+    /// In storage, kept until the dashboard acknowledges it with `ack_upload`
+    Uploaded,
+}
+
+/// Storage credentials handed over by the dashboard, which owns the
+/// Supabase session and refreshes it.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSession {
+    /// e.g. https://<project>.supabase.co/storage/v1
+    pub storage_url: String,
+    pub access_token: String,
+    pub api_key: String,
+    pub team_id: String,
+}
+
+pub type UploadSessionState = Mutex<Option<UploadSession>>;
+
+/// A file waiting to be uploaded. Jobs are stored on disk until the dashboard
+/// has processed them, so they survive hidden windows, lost connections and
+/// restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadJob {
+    pub id: String,
+    pub path: PathBuf,
+    pub file_name: String,
+    pub target: UploadTarget,
+    /// Vault folder path, empty for Inbox
+    pub folder: Vec<String>,
+    pub source: UploadSource,
+    pub status: UploadStatus,
+    pub attempts: u32,
+    /// Unix milliseconds before which the job is not retried
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    /// The file was created by the app (e.g. a screen capture) and is
+    /// deleted once uploaded or cancelled
+    #[serde(default)]
+    pub temporary: bool,
+    /// The email the file was attached to
+    #[serde(default)]
+    pub email: Option<EmailMetadata>,
+    /// Set once the file is in storage
+    #[serde(default)]
+    pub uploaded: Option<UploadedFile>,
+    /// Parked after the session was missing or expired, until
+    /// `set_upload_session` hands over a new one
+    #[serde(default)]
+    pub waiting_for_session: bool,
+    /// Swiss QR-bill and EPC codes printed on the document
+    #[serde(default)]
+    pub payment_codes: Vec<PaymentCode>,
+    /// Date, total, VAT and vendor read from a PDF's text
+    #[serde(default)]
+    pub hints: Option<ReceiptHints>,
+    /// The file was scanned for payment codes and receipt hints. The scan
+    /// runs next to the upload, `upload-finished` is emitted once both are
+    /// done.
+    #[serde(default)]
+    pub scanned: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadProgress<'a> {
+    id: &'a str,
+    file_name: &'a str,
+    bytes_sent: u64,
+    total_bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginUploadRequest<'a> {
+    id: &'a str,
+    file_name: &'a str,
+    url: &'a str,
+    file_path: &'a Path,
+    headers: HashMap<&'static str, String>,
+}
+
+/// Sent once a file is in storage. The dashboard uses `file_path` to create
+/// the Inbox item or Vault document, like it does for browser uploads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedFile {
+    pub id: String,
+    pub target: UploadTarget,
+    pub file_name: String,
+    pub file_path: Vec<String>,
+    pub mimetype: String,
+    pub size: u64,
+    /// Swiss QR-bill and EPC codes printed on the document
+    pub payment_codes: Vec<PaymentCode>,
+    /// Date, total, VAT and vendor read from a PDF's text, to speed up matching
+    pub hints: Option<ReceiptHints>,
+    pub email: Option<EmailMetadata>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadFailed<'a> {
+    id: &'a str,
+    file_name: &'a str,
+    error: &'a str,
+    attempts: u32,
+    /// Unix milliseconds of the next attempt, `None` when the job gave up
+    /// or waits for a session
+    retry_at: Option<u64>,
+    /// Offline or signed out, which doesn't count as an attempt
+    waiting: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadDuplicate<'a> {
+    id: &'a str,
+    file_name: &'a str,
+    duplicate: &'a DuplicateMatch,
+    /// The file was not uploaded
+    skipped: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PaymentCodeDetected<'a> {
+    id: &'a str,
+    file_name: &'a str,
+    codes: &'a [PaymentCode],
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSummary {
+    pub pending: usize,
+    pub failed: usize,
+}
+
+#[derive(Default)]
+struct QueueInner {
+    jobs: Vec<UploadJob>,
+    running: HashMap<String, CancellationToken>,
+}
+
+pub struct UploadQueue {
+    inner: Mutex<QueueInner>,
+    /// Wakes the worker when jobs are added or the session changes
+    wake: Notify,
+    scans: Semaphore,
+    /// Transfers handed to the dashboard, by job id, until
+    /// `finish_plugin_upload` reports the outcome
+    plugin_uploads: Mutex<HashMap<String, oneshot::Sender<Result<(), String>>>>,
+}
+
+/// Why an attempt failed, which decides whether it is worth retrying.
+enum UploadError {
+    Retry(String),
+    Fatal(String),
+    /// No connection, retried without counting as an attempt
+    Offline(String),
+    /// No session or an expired one, parked until the dashboard signs in
+    SignedOut(String),
+    /// Storage already has an object at the path
+    Exists(String),
+    /// Skipped because the same file was uploaded before
+    Duplicate(DuplicateMatch),
+}
+
+impl UploadError {
+    fn message(&self) -> &str {
+        match self {
+            UploadError::Retry(message)
+            | UploadError::Fatal(message)
+            | UploadError::Offline(message)
+            | UploadError::SignedOut(message)
+            | UploadError::Exists(message) => message,
+            UploadError::Duplicate(_) => "Already uploaded",
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+pub fn next_upload_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "{:x}-{}",
+        now_millis(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// 5s, 10s, 20s, ... capped at 30 minutes.
+fn retry_delay(attempts: u32) -> Duration {
+    BASE_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// `receipt.pdf` becomes `receipt-<id>.pdf`.
+fn unique_file_name(file_name: &str, id: &str) -> String {
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{}-{}.{}", stem, id, extension),
+        _ => format!("{}-{}", file_name, id),
+    }
+}
+
+/// Same rules as `stripSpecialCharacters` in @midday/utils, so paths match
+/// the ones the dashboard creates for browser uploads.
+pub fn sanitize_file_name(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+    let mut in_whitespace = false;
+
+    for c in name.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                sanitized.push('-');
+            }
+            in_whitespace = true;
+        } else if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+            sanitized.push(c.to_ascii_lowercase());
+            in_whitespace = false;
+        }
+    }
+
+    sanitized
+}
+
+pub fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "tif" | "tiff" => "image/tiff",
+        "bmp" => "image/bmp",
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        "zip" => "application/zip",
+        "eml" => "message/rfc822",
+        _ => "application/octet-stream",
+    }
+}
+
+impl UploadQueue {
+    fn save(&self, app: &tauri::AppHandle, inner: &QueueInner) {
+        match store::data_file(app, QUEUE_FILE) {
+            Ok(path) => {
+                if let Err(e) = store::save(&path, &inner.jobs) {
+                    eprintln!("📤 Failed to save upload queue: {}", e);
+                }
+            }
+            Err(e) => eprintln!("📤 Failed to save upload queue: {}", e),
+        }
+    }
+
+    fn counts(&self) -> QueueSummary {
+        let inner = self.inner.lock().unwrap();
+        QueueSummary {
+            pending: inner
+                .jobs
+                .iter()
+                .filter(|job| matches!(job.status, UploadStatus::Queued | UploadStatus::Uploading))
+                .count(),
+            failed: inner
+                .jobs
+                .iter()
+                .filter(|job| job.status == UploadStatus::Failed)
+                .count(),
+        }
+    }
+}
+
+/// Number of queued and failed uploads.
+pub fn summary(app: &tauri::AppHandle) -> QueueSummary {
+    app.try_state::<UploadQueue>()
+        .map(|queue| queue.counts())
+        .unwrap_or(QueueSummary {
+            pending: 0,
+            failed: 0,
+        })
+}
+
+fn emit_changed(app: &tauri::AppHandle) {
+    let _ = app.emit("uploads-changed", summary(app));
+}
+
+/// Load the queue left by the previous run and start the upload worker.
+pub fn setup(app: &tauri::AppHandle) {
+    let mut jobs: Vec<UploadJob> = store::data_file(app, QUEUE_FILE)
+        .map(|path| store::load(&path))
+        .unwrap_or_default();
+
+    // Uploads interrupted by a quit start over, and so do scans unless the
+    // file is gone
+    let mut unscanned = Vec::new();
+    for job in jobs.iter_mut() {
+        if job.status == UploadStatus::Uploading {
+            job.status = UploadStatus::Queued;
+        }
+        if !job.scanned {
+            if job.path.is_file() {
+                unscanned.push((job.id.clone(), job.path.clone()));
+            } else {
+                job.scanned = true;
+            }
+        }
+    }
+
+    if !jobs.is_empty() {
+        println!("📤 Resuming {} queued uploads", jobs.len());
+    }
+
+    app.manage(UploadQueue {
+        inner: Mutex::new(QueueInner {
+            jobs,
+            running: HashMap::new(),
+        }),
+        wake: Notify::new(),
+        scans: Semaphore::new(MAX_CONCURRENT_SCANS),
+        plugin_uploads: Mutex::new(HashMap::new()),
+    });
+
+    for (id, path) in unscanned {
+        spawn_scan(app, id, path);
+    }
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        worker(app_handle).await;
+    });
+}
+
+/// Add files to the queue. Returns one job id per file that exists.
+pub fn enqueue(
+    app: &tauri::AppHandle,
+    paths: Vec<PathBuf>,
+    target: UploadTarget,
+    folder: Vec<String>,
+    source: UploadSource,
+) -> Vec<String> {
+    push_jobs(app, paths, target, folder, source, false, None)
+}
+
+/// Queue a file the app created itself, it is deleted once uploaded.
+pub fn enqueue_temporary(
+    app: &tauri::AppHandle,
+    path: PathBuf,
+    target: UploadTarget,
+    folder: Vec<String>,
+    source: UploadSource,
+) -> Option<String> {
+    push_jobs(app, vec![path], target, folder, source, true, None)
+        .into_iter()
+        .next()
+}
+
+/// Queue a file extracted from an email, it is deleted once uploaded.
+pub fn enqueue_from_email(
+    app: &tauri::AppHandle,
+    path: PathBuf,
+    target: UploadTarget,
+    folder: Vec<String>,
+    source: UploadSource,
+    email: EmailMetadata,
+) -> Option<String> {
+    push_jobs(app, vec![path], target, folder, source, true, Some(email))
+        .into_iter()
+        .next()
+}
+
+fn push_jobs(
+    app: &tauri::AppHandle,
+    paths: Vec<PathBuf>,
+    target: UploadTarget,
+    folder: Vec<String>,
+    source: UploadSource,
+    temporary: bool,
+    email: Option<EmailMetadata>,
+) -> Vec<String> {
+    let queue = app.state::<UploadQueue>();
+    let mut ids = Vec::new();
+    let mut scans = Vec::new();
+
+    {
+        let mut inner = queue.inner.lock().unwrap();
+        for path in paths {
+            if !path.is_file() {
+                println!("📤 Skipping {:?}, not a file", path);
+                continue;
+            }
+
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let job = UploadJob {
+                id: next_upload_id(),
+                path,
+                file_name,
+                target,
+                folder: folder.clone(),
+                source,
+                status: UploadStatus::Queued,
+                attempts: 0,
+                next_attempt_at: 0,
+                last_error: None,
+                created_at: now_millis(),
+                temporary,
+                email: email.clone(),
+                uploaded: None,
+                waiting_for_session: false,
+                payment_codes: Vec::new(),
+                hints: None,
+                scanned: false,
+            };
+
+            let _ = app.emit("upload-queued", &job);
+            ids.push(job.id.clone());
+            scans.push((job.id.clone(), job.path.clone()));
+            inner.jobs.push(job);
+        }
+        queue.save(app, &inner);
+    }
+
+    for (id, path) in scans {
+        spawn_scan(app, id, path);
+    }
+    queue.wake.notify_one();
+    emit_changed(app);
+    ids
+}
+
+/// Read payment codes and receipt hints from a job's file without holding up
+/// its upload.
+fn spawn_scan(app: &tauri::AppHandle, id: String, path: PathBuf) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let (codes, hints) = {
+            let queue = app.state::<UploadQueue>();
+            let _permit = queue.scans.acquire().await;
+            // Scan the original, downscaled copies can lose the finer modules
+            tokio::join!(qr::scan(&path), receipt::extract(&path))
+        };
+        scan_done(&app, &id, codes, hints);
+    });
+}
+
+fn scan_done(
+    app: &tauri::AppHandle,
+    id: &str,
+    codes: Vec<PaymentCode>,
+    hints: Option<ReceiptHints>,
+) {
+    let queue = app.state::<UploadQueue>();
+    let mut inner = queue.inner.lock().unwrap();
+    // Cancelled or skipped as a duplicate in the meantime
+    let Some(job) = inner.jobs.iter_mut().find(|job| job.id == id) else {
+        return;
+    };
+
+    job.payment_codes = codes;
+    job.hints = hints;
+    job.scanned = true;
+    if !job.payment_codes.is_empty() {
+        let _ = app.emit(
+            "payment-code-detected",
+            PaymentCodeDetected {
+                id: &job.id,
+                file_name: &job.file_name,
+                codes: &job.payment_codes,
+            },
+        );
+    }
+    if job.status == UploadStatus::Uploaded {
+        finish(app, job);
+    }
+    queue.save(app, &inner);
+}
+
+/// Report a job that is uploaded and scanned.
+fn finish(app: &tauri::AppHandle, job: &mut UploadJob) {
+    if job.temporary {
+        let _ = std::fs::remove_file(&job.path);
+    }
+    if let Some(uploaded) = job.uploaded.as_mut() {
+        uploaded.payment_codes = job.payment_codes.clone();
+        uploaded.hints = job.hints.clone();
+        let _ = app.emit("upload-finished", uploaded.clone());
+    }
+}
+
+fn has_session(app: &tauri::AppHandle) -> bool {
+    app.try_state::<UploadSessionState>()
+        .map(|state| state.lock().unwrap().is_some())
+        .unwrap_or(false)
+}
+
+/// Team files are currently uploaded to.
+pub fn session_team(app: &tauri::AppHandle) -> Option<String> {
+    app.try_state::<UploadSessionState>()?
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|session| session.team_id.clone())
+}
+
+/// Start due jobs whenever there is a free slot, then sleep until the next
+/// retry is due or something changes.
+async fn worker(app: tauri::AppHandle) {
+    let queue = app.state::<UploadQueue>();
+
+    loop {
+        let mut next_due: Option<u64> = None;
+
+        if has_session(&app) {
+            let now = now_millis();
+            let mut inner = queue.inner.lock().unwrap();
+            let mut free_slots = MAX_CONCURRENT_UPLOADS.saturating_sub(inner.running.len());
+
+            let mut started = Vec::new();
+            for job in inner.jobs.iter_mut() {
+                if job.status != UploadStatus::Queued || job.waiting_for_session {
+                    continue;
+                }
+                if job.next_attempt_at > now {
+                    next_due = Some(
+                        next_due.map_or(job.next_attempt_at, |due| due.min(job.next_attempt_at)),
+                    );
+                    continue;
+                }
+                if free_slots == 0 {
+                    break;
+                }
+
+                job.status = UploadStatus::Uploading;
+                job.attempts += 1;
+                free_slots -= 1;
+                started.push(job.clone());
+            }
+
+            for job in started {
+                let token = CancellationToken::new();
+                inner.running.insert(job.id.clone(), token.clone());
+
+                let app_handle = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    run_job(&app_handle, job, token).await;
+                });
+            }
+        }
+
+        // Without a session there is nothing to do until the dashboard signs in
+        match next_due {
+            Some(due) => {
+                let wait = Duration::from_millis(due.saturating_sub(now_millis()));
+                let _ = tokio::time::timeout(wait, queue.wake.notified()).await;
+            }
+            None => queue.wake.notified().await,
+        }
+    }
+}
+
+async fn run_job(app: &tauri::AppHandle, job: UploadJob, token: CancellationToken) {
+    let result = tokio::select! {
+        result = upload_file(app, &job) => Some(result),
+        _ = token.cancelled() => None,
+    };
+
+    let queue = app.state::<UploadQueue>();
+    queue.plugin_uploads.lock().unwrap().remove(&job.id);
+    {
+        let mut inner = queue.inner.lock().unwrap();
+        inner.running.remove(&job.id);
+
+        match result {
+            // Cancelled, `cancel_upload` already removed the job
+            None => {}
+            Some(Ok(uploaded)) => {
+                println!("📤 Uploaded {} to {:?}", uploaded.file_name, job.target);
+                if let Some(existing) = inner.jobs.iter_mut().find(|existing| existing.id == job.id)
+                {
+                    existing.status = UploadStatus::Uploaded;
+                    existing.last_error = None;
+                    existing.uploaded = Some(uploaded);
+                    if existing.scanned {
+                        finish(app, existing);
+                    }
+                }
+            }
+            Some(Err(UploadError::Duplicate(duplicate))) => {
+                println!(
+                    "📤 Skipped {}, already uploaded as {}",
+                    job.file_name, duplicate.file_name
+                );
+                inner.jobs.retain(|existing| existing.id != job.id);
+                if job.temporary {
+                    let _ = std::fs::remove_file(&job.path);
+                }
+                duplicates::untrack(app, &job.id);
+                let _ = app.emit(
+                    "upload-duplicate",
+                    UploadDuplicate {
+                        id: &job.id,
+                        file_name: &job.file_name,
+                        duplicate: &duplicate,
+                        skipped: true,
+                    },
+                );
+            }
+            Some(Err(e)) => {
+                let Some(existing) = inner.jobs.iter_mut().find(|existing| existing.id == job.id)
+                else {
+                    return;
+                };
+
+                let waiting = matches!(e, UploadError::Offline(_) | UploadError::SignedOut(_));
+                let give_up = !waiting
+                    && (matches!(e, UploadError::Fatal(_)) || existing.attempts >= MAX_ATTEMPTS);
+                existing.last_error = Some(e.message().to_string());
+
+                let retry_at = if waiting {
+                    // Not the file's fault, so it doesn't use up an attempt
+                    existing.attempts = existing.attempts.saturating_sub(1);
+                    existing.status = UploadStatus::Queued;
+                    if matches!(e, UploadError::SignedOut(_)) {
+                        existing.waiting_for_session = true;
+                        None
+                    } else {
+                        existing.next_attempt_at =
+                            now_millis() + OFFLINE_RETRY_DELAY.as_millis() as u64;
+                        Some(existing.next_attempt_at)
+                    }
+                } else if give_up {
+                    existing.status = UploadStatus::Failed;
+                    None
+                } else {
+                    existing.status = UploadStatus::Queued;
+                    existing.next_attempt_at =
+                        now_millis() + retry_delay(existing.attempts).as_millis() as u64;
+                    Some(existing.next_attempt_at)
+                };
+
+                eprintln!(
+                    "📤 Failed to upload {} (attempt {}): {}",
+                    existing.file_name,
+                    existing.attempts,
+                    e.message()
+                );
+                let _ = app.emit(
+                    "upload-failed",
+                    UploadFailed {
+                        id: &existing.id,
+                        file_name: &existing.file_name,
+                        error: e.message(),
+                        attempts: existing.attempts,
+                        retry_at,
+                        waiting,
+                    },
+                );
+            }
+        }
+
+        queue.save(app, &inner);
+    }
+
+    queue.wake.notify_one();
+    emit_changed(app);
+}
+
+/// Upload a job's file, or a processed copy of it for images, unless the same
+/// file was uploaded before.
+async fn upload_file(app: &tauri::AppHandle, job: &UploadJob) -> Result<UploadedFile, UploadError> {
+    let fingerprint = duplicates::fingerprint(&job.path).await;
+    if let Some(fingerprint) = &fingerprint
+        && let Some(team_id) = session_team(app)
+        && let Some(duplicate) = duplicates::find(app, fingerprint, &team_id, job.target)
+    {
+        match duplicates::action_for(app, &duplicate) {
+            DuplicateAction::Skip => return Err(UploadError::Duplicate(duplicate)),
+            // Warn once, not on every retry
+            DuplicateAction::Warn if job.attempts == 1 => {
+                println!(
+                    "📤 {} looks like {}, uploading anyway",
+                    job.file_name, duplicate.file_name
+                );
+                let _ = app.emit(
+                    "upload-duplicate",
+                    UploadDuplicate {
+                        id: &job.id,
+                        file_name: &job.file_name,
+                        duplicate: &duplicate,
+                        skipped: false,
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let result = match preprocess::prepare(app, &job.id, &job.path, &job.file_name).await {
+        Some(prepared) => {
+            let result = send_file(app, job, &prepared.path, &prepared.file_name).await;
+            let _ = tokio::fs::remove_file(&prepared.path).await;
+            result
+        }
+        None => send_file(app, job, &job.path, &job.file_name).await,
+    };
+
+    let uploaded = result?;
+    if let Some(fingerprint) = fingerprint {
+        // Storage paths start with the team
+        let team_id = &uploaded.file_path[0];
+        duplicates::record(
+            app,
+            &job.id,
+            fingerprint,
+            &uploaded.file_name,
+            team_id,
+            job.target,
+        );
+    }
+
+    Ok(uploaded)
+}
+
+/// Stream a single file to storage under `<team>/inbox/` or `<team>/<folder...>/`.
+async fn send_file(
+    app: &tauri::AppHandle,
+    job: &UploadJob,
+    path: &Path,
+    file_name: &str,
+) -> Result<UploadedFile, UploadError> {
+    let session = app
+        .try_state::<UploadSessionState>()
+        .and_then(|state| state.lock().unwrap().clone())
+        .ok_or_else(|| {
+            UploadError::SignedOut("Not signed in, open Midday to upload files".to_string())
+        })?;
+
+    let mut file_name = sanitize_file_name(file_name);
+    if file_name.is_empty() {
+        return Err(UploadError::Fatal(format!(
+            "Invalid file name: {}",
+            job.file_name
+        )));
+    }
+
+    let mut file_path = vec![session.team_id.clone()];
+    match job.target {
+        UploadTarget::Inbox => file_path.push("inbox".to_string()),
+        UploadTarget::Vault => file_path.extend(job.folder.iter().cloned()),
+    }
+    file_path.push(file_name.clone());
+
+    // Nothing is overwritten, so two different receipts both called
+    // `receipt.pdf` are both kept: the second one gets the job id added
+    let (total_bytes, mimetype) =
+        match put_object(app, job, &session, path, &file_path, &file_name).await {
+            Err(UploadError::Exists(_)) => {
+                file_name = unique_file_name(&file_name, &job.id);
+                if let Some(last) = file_path.last_mut() {
+                    *last = file_name.clone();
+                }
+                put_object(app, job, &session, path, &file_path, &file_name)
+                    .await
+                    .map_err(|e| match e {
+                        UploadError::Exists(message) => UploadError::Fatal(message),
+                        e => e,
+                    })?
+            }
+            result => result?,
+        };
+
+    Ok(UploadedFile {
+        id: job.id.clone(),
+        target: job.target,
+        file_name,
+        file_path,
+        mimetype: mimetype.to_string(),
+        size: total_bytes,
+        payment_codes: Vec::new(),
+        hints: None,
+        email: job.email.clone(),
+    })
+}
+
+/// Stream a file to `file_path` in storage. Returns its size and mime type.
+async fn put_object(
+    app: &tauri::AppHandle,
+    job: &UploadJob,
+    session: &UploadSession,
+    path: &Path,
+    file_path: &[String],
+    file_name: &str,
+) -> Result<(u64, &'static str), UploadError> {
+    let mut url = tauri::Url::parse(&session.storage_url)
+        .map_err(|e| UploadError::Fatal(format!("Invalid storage URL: {}", e)))?;
+    url.path_segments_mut()
+        .map_err(|_| UploadError::Fatal("Invalid storage URL".to_string()))?
+        .pop_if_empty()
+        .push("object")
+        .push(BUCKET)
+        .extend(file_path.iter());
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| UploadError::Fatal(format!("Failed to open file: {}", e)))?;
+    let total_bytes = file
+        .metadata()
+        .await
+        .map_err(|e| UploadError::Fatal(format!("Failed to read file: {}", e)))?
+        .len();
+    let mimetype = mime_type(path);
+
+    if job.source == UploadSource::Watch {
+        put_with_plugin(app, job, session, path, url.as_str(), file_name, mimetype).await?;
+        return Ok((total_bytes, mimetype));
+    }
+
+    let app_handle = app.clone();
+    let progress_id = job.id.clone();
+    let progress_name = file_name.to_string();
+    let mut bytes_sent = 0u64;
+    let stream =
+        tokio_util::io::ReaderStream::with_capacity(file, CHUNK_SIZE).inspect_ok(move |chunk| {
+            bytes_sent += chunk.len() as u64;
+            let _ = app_handle.emit(
+                "upload-progress",
+                UploadProgress {
+                    id: &progress_id,
+                    file_name: &progress_name,
+                    bytes_sent,
+                    total_bytes,
+                },
+            );
+        });
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .bearer_auth(&session.access_token)
+        .header("apikey", &session.api_key)
+        .header(reqwest::header::CONTENT_TYPE, mimetype)
+        .header(reqwest::header::CONTENT_LENGTH, total_bytes)
+        .body(reqwest::Body::wrap_stream(stream))
+        .send()
+        .await
+        .map_err(|e| {
+            let message = format!("Upload request failed: {}", e);
+            if e.is_connect() || e.is_timeout() {
+                UploadError::Offline(message)
+            } else {
+                UploadError::Retry(message)
+            }
+        })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(failure(status.as_u16(), &body));
+    }
+
+    Ok((total_bytes, mimetype))
+}
+
+/// Hand a watched file to the dashboard, which streams it to storage with
+/// `tauri_plugin_upload` and reports back through `finish_plugin_upload`.
+async fn put_with_plugin(
+    app: &tauri::AppHandle,
+    job: &UploadJob,
+    session: &UploadSession,
+    path: &Path,
+    url: &str,
+    file_name: &str,
+    mimetype: &str,
+) -> Result<(), UploadError> {
+    // The dashboard loads even while the window is hidden
+    let listening = app
+        .try_state::<FrontendQueue>()
+        .is_some_and(|queue| queue.is_listening(PLUGIN_UPLOAD_EVENT));
+    if !listening {
+        return Err(UploadError::Offline(
+            "Waiting for Midday to finish loading".to_string(),
+        ));
+    }
+
+    let queue = app.state::<UploadQueue>();
+    let (sender, receiver) = oneshot::channel();
+    queue
+        .plugin_uploads
+        .lock()
+        .unwrap()
+        .insert(job.id.clone(), sender);
+
+    let request = PluginUploadRequest {
+        id: &job.id,
+        file_name,
+        url,
+        file_path: path,
+        headers: HashMap::from([
+            ("Authorization", format!("Bearer {}", session.access_token)),
+            ("apikey", session.api_key.clone()),
+            ("Content-Type", mimetype.to_string()),
+        ]),
+    };
+    let _ = app.emit_to("main", PLUGIN_UPLOAD_EVENT, request);
+
+    match tokio::time::timeout(PLUGIN_UPLOAD_TIMEOUT, receiver).await {
+        Ok(Ok(Ok(()))) => Ok(()),
+        Ok(Ok(Err(error))) => Err(plugin_failure(&error)),
+        // The page reloaded or the transfer hangs
+        Ok(Err(_)) | Err(_) => Err(UploadError::Retry(
+            "The upload through the dashboard didn't finish".to_string(),
+        )),
+    }
+}
+
+/// Sort a failed storage response into what to do about it.
+fn failure(status: u16, body: &str) -> UploadError {
+    let message = format!("Upload failed with {}: {}", status, body);
+
+    // Expired sessions wait for a new one, rate limits and server errors
+    // are worth retrying, anything else (e.g. a rejected file) won't get better
+    match storage_status(status, body) {
+        409 => UploadError::Exists(message),
+        401 | 403 => UploadError::SignedOut(message),
+        429 | 500..=599 => UploadError::Retry(message),
+        _ => UploadError::Fatal(message),
+    }
+}
+
+/// Storage wraps some statuses in a 400, with the real one in the body, e.g.
+/// `{"statusCode":"403","error":"Unauthorized","message":"jwt expired"}`.
+fn storage_status(status: u16, body: &str) -> u16 {
+    if status != 400 {
+        return status;
+    }
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|body| match body.get("statusCode")? {
+            serde_json::Value::String(code) => code.parse().ok(),
+            serde_json::Value::Number(code) => code.as_u64()?.try_into().ok(),
+            _ => None,
+        })
+        .unwrap_or(status)
+}
+
+/// The upload plugin's errors are `request failed with status code 400: ...`
+/// for responses and reqwest's messages for failed connections.
+fn plugin_failure(error: &str) -> UploadError {
+    if let Some((status, body)) = error
+        .strip_prefix("request failed with status code ")
+        .and_then(|rest| rest.split_once(": "))
+        && let Ok(status) = status.parse()
+    {
+        return failure(status, body);
+    }
+
+    let message = format!("Upload request failed: {}", error);
+    if error.contains("error sending request") {
+        UploadError::Offline(message)
+    } else {
+        UploadError::Retry(message)
+    }
+}
+
+#[tauri::command]
+pub fn set_upload_session(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, UploadSessionState>,
+    queue: tauri::State<'_, UploadQueue>,
+    session: Option<UploadSession>,
+) -> Result<(), String> {
+    let signed_in = session.is_some();
+    *state.lock().unwrap() = session;
+
+    // Jobs waiting for a session can start now
+    if signed_in {
+        let mut inner = queue.inner.lock().unwrap();
+        let mut revived = false;
+        for job in inner.jobs.iter_mut().filter(|job| job.waiting_for_session) {
+            job.waiting_for_session = false;
+            job.next_attempt_at = 0;
+            revived = true;
+        }
+        if revived {
+            queue.save(&app, &inner);
+        }
+        drop(inner);
+        queue.wake.notify_one();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_uploads(queue: tauri::State<'_, UploadQueue>) -> Vec<UploadJob> {
+    queue.inner.lock().unwrap().jobs.clone()
+}
+
+/// Uploads in storage the dashboard hasn't acknowledged yet, e.g. because
+/// they finished while it wasn't open. Same payload as `upload-finished`.
+#[tauri::command]
+pub fn finished_uploads(queue: tauri::State<'_, UploadQueue>) -> Vec<UploadedFile> {
+    queue
+        .inner
+        .lock()
+        .unwrap()
+        .jobs
+        .iter()
+        .filter(|job| job.scanned)
+        .filter_map(|job| job.uploaded.clone())
+        .collect()
+}
+
+/// The dashboard finished uploading a watched file with the upload plugin,
+/// `error` is the plugin's error if it failed.
+#[tauri::command]
+pub fn finish_plugin_upload(
+    queue: tauri::State<'_, UploadQueue>,
+    id: String,
+    error: Option<String>,
+) {
+    if let Some(sender) = queue.plugin_uploads.lock().unwrap().remove(&id) {
+        let _ = sender.send(error.map_or(Ok(()), Err));
+    }
+}
+
+/// Fail the transfers handed to the dashboard when its page reloads, they are
+/// retried once it is back.
+pub fn abandon_plugin_uploads(app: &tauri::AppHandle) {
+    if let Some(queue) = app.try_state::<UploadQueue>() {
+        queue.plugin_uploads.lock().unwrap().clear();
+    }
+}
+
+/// Remove a finished upload once the dashboard has created its Inbox item or
+/// Vault document.
+#[tauri::command]
+pub fn ack_upload(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, UploadQueue>,
+    id: String,
+) -> Result<(), String> {
+    {
+        let mut inner = queue.inner.lock().unwrap();
+        let before = inner.jobs.len();
+        inner
+            .jobs
+            .retain(|job| !(job.id == id && job.status == UploadStatus::Uploaded));
+        if inner.jobs.len() == before {
+            return Ok(());
+        }
+        queue.save(&app, &inner);
+    }
+
+    emit_changed(&app);
+    Ok(())
+}
+
+/// Retry a job right away, also one that gave up after too many attempts.
+#[tauri::command]
+pub fn retry_upload(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, UploadQueue>,
+    id: String,
+) -> Result<(), String> {
+    {
+        let mut inner = queue.inner.lock().unwrap();
+        let job = inner
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| format!("Unknown upload: {}", id))?;
+
+        if matches!(job.status, UploadStatus::Uploading | UploadStatus::Uploaded) {
+            return Ok(());
+        }
+
+        job.status = UploadStatus::Queued;
+        job.attempts = 0;
+        job.next_attempt_at = 0;
+        job.waiting_for_session = false;
+        queue.save(&app, &inner);
+    }
+
+    queue.wake.notify_one();
+    emit_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_upload(
+    app: tauri::AppHandle,
+    queue: tauri::State<'_, UploadQueue>,
+    id: String,
+) -> Result<(), String> {
+    {
+        let mut inner = queue.inner.lock().unwrap();
+        if !inner.jobs.iter().any(|job| job.id == id) {
+            return Err(format!("Unknown upload: {}", id));
+        }
+
+        if let Some(token) = inner.running.remove(&id) {
+            token.cancel();
+        }
+        // Files of finished uploads are already gone
+        for job in inner
+            .jobs
+            .iter()
+            .filter(|job| job.id == id && job.temporary && job.status != UploadStatus::Uploaded)
+        {
+            let _ = std::fs::remove_file(&job.path);
+        }
+        inner.jobs.retain(|job| job.id != id);
+        queue.save(&app, &inner);
+    }
+
+    duplicates::untrack(&app, &id);
+    let _ = app.emit("upload-cancelled", &id);
+    emit_changed(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_failures() {
+        let kind = |status, body| match failure(status, body) {
+            UploadError::Retry(_) => "retry",
+            UploadError::Fatal(_) => "fatal",
+            UploadError::Offline(_) => "offline",
+            UploadError::SignedOut(_) => "signed out",
+            UploadError::Exists(_) => "exists",
+            UploadError::Duplicate(_) => "duplicate",
+        };
+
+        assert_eq!(kind(409, ""), "exists");
+        assert_eq!(
+            kind(
+                400,
+                r#"{"statusCode":"409","error":"Duplicate","message":"The resource already exists"}"#
+            ),
+            "exists"
+        );
+        assert_eq!(kind(401, ""), "signed out");
+        assert_eq!(
+            kind(
+                400,
+                r#"{"statusCode":"403","error":"Unauthorized","message":"jwt expired"}"#
+            ),
+            "signed out"
+        );
+        assert_eq!(kind(400, r#"{"statusCode":401}"#), "signed out");
+        // Only the status counts, not a 409 elsewhere in the body
+        assert_eq!(
+            kind(
+                400,
+                r#"{"statusCode":"400","error":"InvalidKey","message":"Invalid key: team/inbox/409.pdf"}"#
+            ),
+            "fatal"
+        );
+        assert_eq!(kind(400, "Bad Request 409"), "fatal");
+        assert_eq!(kind(413, ""), "fatal");
+        assert_eq!(kind(429, ""), "retry");
+        assert_eq!(kind(503, ""), "retry");
+    }
+
+    #[test]
+    fn plugin_failures() {
+        assert!(matches!(
+            plugin_failure(
+                r#"request failed with status code 400: {"statusCode":"403","message":"jwt expired"}"#
+            ),
+            UploadError::SignedOut(_)
+        ));
+        assert!(matches!(
+            plugin_failure("request failed with status code 502: Bad Gateway"),
+            UploadError::Retry(_)
+        ));
+        assert!(matches!(
+            plugin_failure("error sending request for url (https://example.com/storage/v1)"),
+            UploadError::Offline(_)
+        ));
+    }
+}
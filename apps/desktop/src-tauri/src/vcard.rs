@@ -0,0 +1,417 @@
+use regex::Regex;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tauri::Manager;
+
+use crate::capture;
+use crate::content_line::{self, ContentLine};
+use crate::frontend_queue::{FrontendQueue, LISTENER_STALE_AFTER};
+
+/// Properties some address books use for VAT or tax ids.
+const VAT_PROPERTIES: &[&str] = &[
+    "X-VAT-ID",
+    "X-VATID",
+    "X-VAT-NUMBER",
+    "X-VAT",
+    "X-TAX-ID",
+    "X-TAXID",
+    "X-USTID",
+    "X-UST-IDNR",
+];
+
+/// Custom labels of Apple Contacts fields holding a VAT or tax id.
+static VAT_LABEL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(vat|tax|ust|mwst|btw|tva|iva|moms|uid)\b").unwrap());
+
+/// A VAT id mentioned in the note, e.g. `VAT ID: DE123456789`.
+static VAT_IN_NOTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:vat|ust|tva|btw|iva|mwst|uid)[\w\-. ]{0,12}[:#]?\s*([A-Z]{2}\s?[0-9A-Z]{2}[0-9A-Z ]{6,12}[0-9A-Z])\b",
+    )
+    .unwrap()
+});
+
+/// A contact, in the fields of a Midday customer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contact {
+    /// The organization, or the person for contacts without one
+    pub name: String,
+    /// The person at the organization
+    pub contact: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub website: Option<String>,
+    pub address_line_1: Option<String>,
+    pub address_line_2: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub zip: Option<String>,
+    pub country: Option<String>,
+    /// Set when the address has a two letter country code
+    pub country_code: Option<String>,
+    pub vat_number: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContactsImported {
+    file_name: String,
+    contacts: Vec<Contact>,
+}
+
+pub fn is_vcard(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("vcf") || ext.eq_ignore_ascii_case("vcard"))
+}
+
+/// Parse `.vcf` files and send the contacts to the dashboard as
+/// `contacts-imported`, which asks before creating customers.
+pub async fn import(app: &tauri::AppHandle, paths: Vec<PathBuf>) {
+    for path in paths {
+        let file = path.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || parse_file(&file))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+
+        let contacts = match result {
+            Ok(contacts) if !contacts.is_empty() => contacts,
+            Ok(_) => {
+                capture::notify(app, "No contacts found", &file_name(&path));
+                continue;
+            }
+            Err(e) => {
+                eprintln!("📇 Failed to read {:?}: {}", path, e);
+                capture::notify(app, "Couldn't read contacts", &e);
+                continue;
+            }
+        };
+
+        println!("📇 Read {} contact(s) from {:?}", contacts.len(), path);
+        let payload = ContactsImported {
+            file_name: file_name(&path),
+            contacts,
+        };
+        if let (Some(queue), Ok(payload)) = (
+            app.try_state::<FrontendQueue>(),
+            serde_json::to_value(payload),
+        ) {
+            queue.push_to_listener(app, "contacts-imported", payload, LISTENER_STALE_AFTER);
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn parse_file(path: &Path) -> Result<Vec<Contact>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    // Old exports from Outlook and phones aren't always UTF-8
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => encoding_rs::WINDOWS_1252
+            .decode(e.as_bytes())
+            .0
+            .into_owned(),
+    };
+    Ok(parse(&text))
+}
+
+/// Every `BEGIN:VCARD` ... `END:VCARD` with a name, organization or email.
+pub fn parse(text: &str) -> Vec<Contact> {
+    let mut contacts = Vec::new();
+    let mut card: Option<Vec<ContentLine>> = None;
+    for line in content_line::parse(text) {
+        match (line.name.as_str(), line.value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VCARD") => card = Some(Vec::new()),
+            ("END", "VCARD") => {
+                if let Some(contact) = card.take().and_then(|lines| contact(&lines)) {
+                    contacts.push(contact);
+                }
+            }
+            _ => {
+                if let Some(lines) = card.as_mut() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    contacts
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// vCard 4 writes phone numbers as `tel:` URIs.
+fn phone_number(text: &str) -> Option<String> {
+    let text = text.trim();
+    match text.get(..4) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("tel:") => non_empty(&text[4..]),
+        _ => non_empty(text),
+    }
+}
+
+/// The preferred property named `name`, then one of `types` in order, then
+/// the first.
+fn pick<'a>(lines: &'a [ContentLine], name: &str, types: &[&str]) -> Option<&'a ContentLine> {
+    let candidates: Vec<&ContentLine> = lines
+        .iter()
+        .filter(|line| line.name == name && !line.value.trim().is_empty())
+        .collect();
+    candidates
+        .iter()
+        .find(|line| line.is_preferred())
+        .or_else(|| {
+            types
+                .iter()
+                .find_map(|value| candidates.iter().find(|line| line.has_type(value)))
+        })
+        .or_else(|| candidates.first())
+        .copied()
+}
+
+fn contact(lines: &[ContentLine]) -> Option<Contact> {
+    let text = |name: &str| {
+        lines
+            .iter()
+            .find(|line| line.name == name)
+            .and_then(|line| non_empty(&line.text()))
+    };
+
+    // `N` is family;given;additional;prefix;suffix
+    let structured_name = lines.iter().find(|line| line.name == "N").and_then(|line| {
+        let components = line.components();
+        let part = |index: usize| {
+            components
+                .get(index)
+                .map(|part| part.trim())
+                .unwrap_or_default()
+        };
+        non_empty(
+            &[part(3), part(1), part(2), part(0), part(4)]
+                .iter()
+                .filter(|part| !part.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    });
+    let person = text("FN").or(structured_name);
+    // `ORG` is organization;unit
+    let organization = lines
+        .iter()
+        .find(|line| line.name == "ORG")
+        .and_then(|line| line.components().first().and_then(|org| non_empty(org)));
+    let email =
+        pick(lines, "EMAIL", &["work", "internet"]).and_then(|line| non_empty(&line.text()));
+
+    let (name, contact) = match (organization, person) {
+        (Some(organization), Some(person)) if person != organization => {
+            (organization, Some(person))
+        }
+        (Some(organization), _) => (organization, None),
+        (None, Some(person)) => (person, None),
+        (None, None) => (email.clone()?, None),
+    };
+
+    let mut contact = Contact {
+        name,
+        contact,
+        email,
+        phone: pick(lines, "TEL", &["work", "voice"]).and_then(|line| phone_number(&line.text())),
+        website: pick(lines, "URL", &["work"]).and_then(|line| non_empty(&line.text())),
+        vat_number: vat_number(lines),
+        note: text("NOTE"),
+        ..Default::default()
+    };
+
+    // `ADR` is PO box;extended;street;city;region;postal code;country
+    if let Some(address) = pick(lines, "ADR", &["work"]) {
+        let components = address.components();
+        let part = |index: usize| components.get(index).and_then(|part| non_empty(part));
+
+        let street = part(2).or_else(|| part(0));
+        let mut street_lines = street
+            .as_deref()
+            .map(|street| street.lines().filter_map(non_empty).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter();
+        contact.address_line_1 = street_lines.next();
+        let rest: Vec<String> = part(1).into_iter().chain(street_lines).collect();
+        contact.address_line_2 = non_empty(&rest.join(", "));
+        contact.city = part(3);
+        contact.state = part(4);
+        contact.zip = part(5);
+        contact.country = part(6);
+        contact.country_code = contact
+            .country
+            .as_deref()
+            .filter(|country| {
+                country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic())
+            })
+            .map(str::to_ascii_uppercase);
+    }
+
+    Some(contact)
+}
+
+/// A custom property, a field Apple Contacts labelled as VAT or tax id, or
+/// one written in the note.
+fn vat_number(lines: &[ContentLine]) -> Option<String> {
+    let normalize = |value: &str| non_empty(&value.replace(' ', "").to_ascii_uppercase());
+
+    if let Some(value) = lines
+        .iter()
+        .find(|line| VAT_PROPERTIES.contains(&line.name.as_str()))
+        .and_then(|line| normalize(&line.text()))
+    {
+        return Some(value);
+    }
+
+    let labelled = lines.iter().find_map(|label| {
+        let group = label.group.as_deref()?;
+        if label.name != "X-ABLABEL" || !VAT_LABEL.is_match(&label.text()) {
+            return None;
+        }
+        lines
+            .iter()
+            .find(|line| line.group.as_deref() == Some(group) && line.name != "X-ABLABEL")
+            .and_then(|line| normalize(&line.text()))
+    });
+    if labelled.is_some() {
+        return labelled;
+    }
+
+    lines
+        .iter()
+        .filter(|line| line.name == "NOTE")
+        .find_map(|line| {
+            VAT_IN_NOTE
+                .captures(&line.text())
+                .and_then(|captures| normalize(&captures[1]))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(body: &str) -> String {
+        format!(
+            "BEGIN:VCARD\r\n{}\r\nEND:VCARD\r\n",
+            body.trim().replace('\n', "\r\n")
+        )
+    }
+
+    #[test]
+    fn version_2_1() {
+        let text = card(
+            "VERSION:2.1
+N:Doe;John;;Mr.;
+ORG:Acme GmbH;Sales
+TEL;HOME;VOICE:+49 30 1
+TEL;WORK;VOICE:+49 30 2
+EMAIL;INTERNET:john@home.example
+EMAIL;PREF;INTERNET:john@acme.example
+ADR;WORK;ENCODING=QUOTED-PRINTABLE;CHARSET=UTF-8:;;Hauptstra=C3=9Fe 1;Berlin;;10115;de",
+        );
+        assert_eq!(
+            parse(&text),
+            [Contact {
+                name: "Acme GmbH".to_string(),
+                contact: Some("Mr. John Doe".to_string()),
+                email: Some("john@acme.example".to_string()),
+                phone: Some("+49 30 2".to_string()),
+                address_line_1: Some("Hauptstraße 1".to_string()),
+                city: Some("Berlin".to_string()),
+                zip: Some("10115".to_string()),
+                country: Some("de".to_string()),
+                country_code: Some("DE".to_string()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn version_3_from_apple_contacts() {
+        let text = card(
+            r"VERSION:3.0
+N:Smith;Jane;;;
+FN:Jane Smith
+EMAIL;type=INTERNET;type=HOME:jane@home.example
+EMAIL;type=INTERNET;type=WORK;type=pref:jane@work.example
+item1.URL;type=pref:https://example.com
+item1.X-ABLabel:_$!<HomePage>!$_
+item2.X-ABRELATEDNAMES:de 123 456 789
+item2.X-ABLabel:VAT ID
+ADR;type=WORK:;Suite 5;1 Main St\nBuilding B;Springfield;IL;62701;USA
+NOTE:Met at the fair\, 2023",
+        );
+        assert_eq!(
+            parse(&text),
+            [Contact {
+                name: "Jane Smith".to_string(),
+                email: Some("jane@work.example".to_string()),
+                website: Some("https://example.com".to_string()),
+                address_line_1: Some("1 Main St".to_string()),
+                address_line_2: Some("Suite 5, Building B".to_string()),
+                city: Some("Springfield".to_string()),
+                state: Some("IL".to_string()),
+                zip: Some("62701".to_string()),
+                country: Some("USA".to_string()),
+                vat_number: Some("DE123456789".to_string()),
+                note: Some("Met at the fair, 2023".to_string()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn version_4() {
+        let text = card(
+            "VERSION:4.0
+KIND:org
+FN:Globex Corporation
+ORG:Globex Corporation
+EMAIL:info@globex.example
+EMAIL;PREF=1:billing@globex.example
+TEL;VALUE=uri;TYPE=work:tel:+1-555-0100
+X-VAT-ID:gb 123 4567 89
+NOTE:Folded note
+  that continues",
+        );
+        let contacts = parse(&text);
+        assert_eq!(contacts.len(), 1);
+        let contact = &contacts[0];
+        assert_eq!(contact.name, "Globex Corporation");
+        assert_eq!(contact.contact, None);
+        assert_eq!(contact.email.as_deref(), Some("billing@globex.example"));
+        assert_eq!(contact.phone.as_deref(), Some("+1-555-0100"));
+        assert_eq!(contact.vat_number.as_deref(), Some("GB123456789"));
+        assert_eq!(contact.note.as_deref(), Some("Folded note that continues"));
+    }
+
+    #[test]
+    fn names_and_notes() {
+        let text = [
+            card("VERSION:3.0\nTEL:+1 555 0100"),
+            card("VERSION:3.0\nEMAIL:someone@example.com"),
+            card("VERSION:3.0\nFN:Max Mustermann\nNOTE:USt-IdNr.: DE 123456789"),
+        ]
+        .concat();
+        let contacts = parse(&text);
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].name, "someone@example.com");
+        assert_eq!(contacts[1].name, "Max Mustermann");
+        assert_eq!(contacts[1].vat_number.as_deref(), Some("DE123456789"));
+    }
+}
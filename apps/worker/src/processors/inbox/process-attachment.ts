@@ -28,7 +28,10 @@ export class ProcessAttachmentProcessor extends BaseProcessor<ProcessAttachmentP
       referenceId,
       website,
       senderEmail,
+      subject,
+      date: emailDate,
       inboxAccountId,
+      hints,
     } = job.data;
     const supabase = createClient();
     const db = getDb();
@@ -148,7 +151,7 @@ export class ProcessAttachmentProcessor extends BaseProcessor<ProcessAttachmentP
       });
       const createdData = await createInbox(db, {
         // NOTE: If we can't parse the name using OCR this will be the fallback name
-        displayName: filename ?? "Unknown",
+        displayName: subject ?? filename ?? "Unknown",
         teamId,
         filePath,
         fileName: filename ?? "Unknown",
@@ -333,15 +336,16 @@ export class ProcessAttachmentProcessor extends BaseProcessor<ProcessAttachmentP
         return; // Skip embedding and transaction matching for non-financial documents
       }
 
+      // Hints read by the desktop app fill in what OCR missed
       await updateInboxWithProcessedData(db, {
         id: inboxData.id,
-        amount: result.amount ?? undefined,
-        currency: result.currency ?? undefined,
-        displayName: result.name ?? undefined,
+        amount: result.amount ?? hints?.total ?? undefined,
+        currency: result.currency ?? hints?.currency ?? undefined,
+        displayName: result.name ?? hints?.vendor ?? undefined,
         website: result.website ?? undefined,
-        date: result.date ?? undefined,
-        taxAmount: result.tax_amount ?? undefined,
-        taxRate: result.tax_rate ?? undefined,
+        date: result.date ?? hints?.date ?? emailDate?.slice(0, 10),
+        taxAmount: result.tax_amount ?? hints?.vat ?? undefined,
+        taxRate: result.tax_rate ?? hints?.vatRate ?? undefined,
         taxType: result.tax_type ?? undefined,
         type: result.type as "invoice" | "expense" | null | undefined,
         invoiceNumber: result.invoice_number ?? undefined,
@@ -493,7 +497,8 @@ export class ProcessAttachmentProcessor extends BaseProcessor<ProcessAttachmentP
         throw error;
       }
 
-      // For non-retryable errors, mark as pending with fallback name
+      // For non-retryable errors, mark as pending with the desktop app's
+      // hints or the fallback name
       this.logger.info(
         "Document processing failed, marking as pending with fallback name",
         {
@@ -504,11 +509,24 @@ export class ProcessAttachmentProcessor extends BaseProcessor<ProcessAttachmentP
         },
       );
 
-      await updateInbox(db, {
-        id: inboxData.id,
-        teamId,
-        status: "pending",
-      });
+      if (hints) {
+        await updateInboxWithProcessedData(db, {
+          id: inboxData.id,
+          amount: hints.total ?? undefined,
+          currency: hints.currency ?? undefined,
+          displayName: hints.vendor ?? undefined,
+          date: hints.date ?? emailDate?.slice(0, 10),
+          taxAmount: hints.vat ?? undefined,
+          taxRate: hints.vatRate ?? undefined,
+          status: "pending",
+        });
+      } else {
+        await updateInbox(db, {
+          id: inboxData.id,
+          teamId,
+          status: "pending",
+        });
+      }
 
       throw error;
     }
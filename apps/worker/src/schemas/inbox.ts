@@ -22,6 +22,16 @@ export type MatchTransactionsBidirectionalPayload = z.infer<
   typeof matchTransactionsBidirectionalSchema
 >;
 
+// Read locally from a PDF's text by the desktop app
+export const receiptHintsSchema = z.object({
+  vendor: z.string().nullable(),
+  date: z.string().nullable(),
+  total: z.number().nullable(),
+  currency: z.string().nullable(),
+  vat: z.number().nullable(),
+  vatRate: z.number().nullable(),
+});
+
 export const processAttachmentSchema = z.object({
   teamId: z.string().uuid(),
   mimetype: z.string(),
@@ -30,7 +40,11 @@ export const processAttachmentSchema = z.object({
   referenceId: z.string().optional(),
   website: z.string().optional(),
   senderEmail: z.string().email().optional(),
+  // Subject and date (ISO 8601) of the email the file came from
+  subject: z.string().optional(),
+  date: z.string().optional(),
   inboxAccountId: z.string().uuid().optional(),
+  hints: receiptHintsSchema.optional(),
 });
 
 export type ProcessAttachmentPayload = z.infer<typeof processAttachmentSchema>;
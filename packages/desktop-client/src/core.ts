@@ -1,3 +1,5 @@
+import type { EventCallback } from "@tauri-apps/api/event";
+
 export { invoke } from "@tauri-apps/api/core";
 export { emit, listen } from "@tauri-apps/api/event";
 export { getCurrentWindow, Window } from "@tauri-apps/api/window";
@@ -14,84 +16,731 @@ export async function nativeDownload(url: string, filename: string) {
   await download(url, `${dir}/${filename}`);
 }
 
+/** Size of the pieces a Blob is streamed to the desktop shell in. */
+const SAVE_CHUNK_SIZE = 4 * 1024 * 1024;
+
 /**
- * Write a Blob to a user-selected location via save dialog.
+ * Write a Blob to a user-selected location via save dialog, then show it in
+ * the file manager. The Blob is streamed in chunks so large exports are never
+ * held in memory twice.
  */
 export async function nativeSaveFile(blob: Blob, filename: string) {
-  try {
-    const { save } = await import("@tauri-apps/plugin-dialog");
-    const { open } = await import("@tauri-apps/plugin-fs");
-    const { downloadDir } = await import("@tauri-apps/api/path");
-
-    console.log("[nativeSaveFile] Starting file save:", {
-      filename,
-      blobSize: blob.size,
-      blobType: blob.type,
-    });
+  const { invoke } = await import("@tauri-apps/api/core");
 
-    if (!blob || blob.size === 0) {
-      throw new Error("Blob is empty or invalid");
-    }
-
-    const buffer = await blob.arrayBuffer();
-    console.log("[nativeSaveFile] Converted blob to buffer:", {
-      bufferSize: buffer.byteLength,
-    });
+  if (!blob || blob.size === 0) {
+    throw new Error("Blob is empty or invalid");
+  }
 
-    if (buffer.byteLength === 0) {
-      throw new Error("Buffer is empty after conversion");
-    }
+  const target = await invoke<{ id: string; path: string } | null>(
+    "begin_save",
+    { fileName: filename },
+  );
 
-    // Extract file extension for filter
-    const extension = filename.split(".").pop()?.toLowerCase() || "";
-    const filters = extension
-      ? [{ name: "Files", extensions: [extension] }]
-      : undefined;
-
-    // Show save dialog with default path in Downloads folder
-    const defaultPath = `${await downloadDir()}/${filename}`;
-    const selectedPath = await save({
-      defaultPath,
-      filters,
-      title: "Save File",
-    });
+  if (!target) {
+    // User cancelled the dialog
+    return;
+  }
 
-    if (!selectedPath) {
-      // User cancelled the dialog
-      console.log("[nativeSaveFile] User cancelled save dialog");
-      return;
+  try {
+    for (let offset = 0; offset < blob.size; offset += SAVE_CHUNK_SIZE) {
+      const chunk = await blob
+        .slice(offset, offset + SAVE_CHUNK_SIZE)
+        .arrayBuffer();
+      await invoke("write_save_chunk", new Uint8Array(chunk), {
+        headers: { "x-save-id": target.id },
+      });
     }
 
-    // Write file to selected path
-    // The save dialog automatically adds the path to filesystem scope
-    // Use open() with write/create/truncate flags for reliable file writing
-    const file = await open(selectedPath, {
-      write: true,
-      create: true,
-      truncate: true,
+    return await invoke<string>("finish_save", {
+      id: target.id,
+      reveal: true,
     });
-    await file.write(new Uint8Array(buffer));
-    await file.close();
-
-    console.log("[nativeSaveFile] File saved successfully:", selectedPath);
-
-    // Optionally reveal file in Finder (macOS will bounce the folder icon)
-    // This is optional - comment out if you don't want to open Finder
-    try {
-      const { openUrl } = await import("@tauri-apps/plugin-opener");
-      // Open the file's parent directory in Finder
-      // On macOS, this will cause the folder icon to bounce if Downloads folder
-      const parentDir = selectedPath.substring(
-        0,
-        selectedPath.lastIndexOf("/"),
-      );
-      await openUrl(`file://${parentDir}`);
-    } catch (error) {
-      // Ignore errors - revealing file is optional
-      console.log("[nativeSaveFile] Could not reveal file:", error);
-    }
   } catch (error) {
+    await invoke("abort_save", { id: target.id });
     console.error("[nativeSaveFile] Error saving file:", error);
     throw error;
   }
 }
+
+/**
+ * Save a URL via save dialog, downloaded natively with the session cookie.
+ * Returns the saved path, or `null` if the dialog was cancelled.
+ */
+export async function nativeSaveUrl(url: string, filename?: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<string | null>("save_url", {
+    url,
+    fileName: filename,
+    reveal: true,
+  });
+}
+
+/**
+ * Show a file selected in Finder / Explorer / the file manager.
+ */
+export async function revealInFolder(path: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("reveal_in_folder", { path });
+}
+
+export type NativeUploadTarget = "inbox" | "vault";
+
+export type NativeUploadSession = {
+  storageUrl: string;
+  accessToken: string;
+  apiKey: string;
+  teamId: string;
+};
+
+export type NativeUploadedFile = {
+  id: string;
+  target: NativeUploadTarget;
+  fileName: string;
+  filePath: string[];
+  mimetype: string;
+  size: number;
+  paymentCodes: NativePaymentCode[];
+  hints: NativeReceiptHints | null;
+  /** Set for attachments of dropped `.eml` files */
+  email: NativeEmailMetadata | null;
+};
+
+export type NativeEmailMetadata = {
+  senderName: string | null;
+  senderEmail: string | null;
+  subject: string | null;
+  date: string | null;
+};
+
+/** Payload of `archive-progress`, emitted for dropped `.zip` files */
+export type NativeArchiveProgress = {
+  id: string;
+  fileName: string;
+  total: number;
+  uploaded: number;
+  failed: number;
+  duplicates: number;
+  skipped: number;
+};
+
+/**
+ * Hand the storage session to the desktop shell so it can upload natively.
+ * Pass `null` on sign out.
+ */
+export async function setUploadSession(session: NativeUploadSession | null) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("set_upload_session", { session });
+}
+
+/**
+ * Upload the files of a `native-drop` event to Inbox or a Vault folder.
+ * With `merge`, the dropped images are combined into a single PDF first.
+ * Returns one upload id per file.
+ */
+export async function uploadDroppedFiles(
+  dropId: number,
+  target: NativeUploadTarget,
+  folder?: string[],
+  merge?: boolean,
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<string[]>("upload_dropped_files", {
+    dropId,
+    target,
+    folder,
+    merge,
+  });
+}
+
+/**
+ * Combine images into a single PDF, one page per image in file name order,
+ * and upload it as one document. Returns the upload id.
+ */
+export async function mergeToPdf(
+  paths: string[],
+  target?: NativeUploadTarget,
+  folder?: string[],
+  fileName?: string,
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<string>("merge_to_pdf", { paths, target, folder, fileName });
+}
+
+/**
+ * Forget a `native-drop` that landed outside of any upload target.
+ */
+export async function discardDrop(dropId: number) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("discard_drop", { dropId });
+}
+
+export type NativeUploadJob = {
+  id: string;
+  path: string;
+  fileName: string;
+  target: NativeUploadTarget;
+  folder: string[];
+  source: "drop" | "watch" | "cli" | "capture" | "clipboard";
+  status: "queued" | "uploading" | "failed" | "uploaded";
+  attempts: number;
+  nextAttemptAt: number;
+  lastError: string | null;
+  createdAt: number;
+  temporary: boolean;
+  /** Signed out or the session expired, resumes on `setUploadSession` */
+  waitingForSession: boolean;
+  paymentCodes: NativePaymentCode[];
+  hints: NativeReceiptHints | null;
+  /** Scanned for payment codes and hints, `upload-finished` waits for it */
+  scanned: boolean;
+};
+
+/**
+ * Uploads waiting in the desktop queue, including failed ones.
+ */
+export async function listUploads() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeUploadJob[]>("list_uploads");
+}
+
+/**
+ * Uploads in storage that haven't been acknowledged with `ackUpload`, e.g.
+ * because they finished while the dashboard wasn't open.
+ */
+export async function finishedUploads() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeUploadedFile[]>("finished_uploads");
+}
+
+/**
+ * Remove a finished upload from the queue once its Inbox item or Vault
+ * document was created.
+ */
+export async function ackUpload(id: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("ack_upload", { id });
+}
+
+/**
+ * Retry a queued or failed upload right away.
+ */
+export async function retryUpload(id: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("retry_upload", { id });
+}
+
+/**
+ * Remove an upload from the queue, stopping it if it is in progress.
+ */
+export async function cancelUpload(id: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("cancel_upload", { id });
+}
+
+/** Payload of `plugin-upload-requested`, a watched file to upload */
+export type NativePluginUpload = {
+  id: string;
+  fileName: string;
+  url: string;
+  filePath: string;
+  headers: Record<string, string>;
+};
+
+/**
+ * Upload a watched file the shell handed over with `plugin-upload-requested`
+ * through the upload plugin, with progress as `upload-progress` like the
+ * shell's own uploads, and report the outcome back to the queue.
+ */
+export async function runPluginUpload(request: NativePluginUpload) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  const { emit } = await import("@tauri-apps/api/event");
+  const { upload } = await import("@tauri-apps/plugin-upload");
+
+  let failure: string | null = null;
+  try {
+    await upload(
+      request.url,
+      request.filePath,
+      ({ progressTotal, total }) => {
+        emit("upload-progress", {
+          id: request.id,
+          fileName: request.fileName,
+          bytesSent: progressTotal,
+          totalBytes: total,
+        }).catch((error) => {
+          console.error("Failed to report upload progress:", error);
+        });
+      },
+      new Map(Object.entries(request.headers)),
+    );
+  } catch (error) {
+    failure = String(error);
+  }
+
+  await invoke("finish_plugin_upload", { id: request.id, error: failure });
+}
+
+export type NativeImageSettings = {
+  enabled: boolean;
+  maxEdge: number;
+  quality: number;
+};
+
+/**
+ * How images are prepared before native uploads.
+ */
+export async function getImageSettings() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeImageSettings>("get_image_settings");
+}
+
+/**
+ * Turn image preprocessing off to upload originals.
+ */
+export async function setImageSettings(settings: NativeImageSettings) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("set_image_settings", { settings });
+}
+
+export type NativeDuplicateAction = "skip" | "warn" | "allow";
+
+export type NativeDuplicateMatch = {
+  kind: "exact" | "similar";
+  fileName: string;
+  uploadedAt: number;
+  distance: number;
+};
+
+export type NativeDuplicateCheck = {
+  path: string;
+  duplicate: NativeDuplicateMatch | null;
+  action: NativeDuplicateAction;
+};
+
+/**
+ * Compare files with everything uploaded through the desktop app, by content
+ * hash and, for images, by how they look.
+ */
+export async function checkDuplicates(
+  paths: string[],
+  target?: NativeUploadTarget,
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeDuplicateCheck[]>("check_duplicates", { paths, target });
+}
+
+export type NativeDuplicateSettings = {
+  exact: NativeDuplicateAction;
+  similar: NativeDuplicateAction;
+};
+
+export async function getDuplicateSettings() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeDuplicateSettings>("get_duplicate_settings");
+}
+
+export async function setDuplicateSettings(settings: NativeDuplicateSettings) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("set_duplicate_settings", { settings });
+}
+
+/**
+ * Forget earlier uploads so the same files can be uploaded again.
+ */
+export async function clearUploadIndex() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("clear_upload_index");
+}
+
+export type NativePaymentParty = {
+  name: string;
+  addressLines: string[];
+  country: string | null;
+};
+
+export type NativePaymentCode = {
+  kind: "swissQrBill" | "epc";
+  iban: string;
+  bic: string | null;
+  creditor: NativePaymentParty;
+  debtor: NativePaymentParty | null;
+  amount: number | null;
+  currency: string;
+  referenceType: "qrr" | "scor" | null;
+  reference: string | null;
+  message: string | null;
+  purpose: string | null;
+  billingInformation: string | null;
+};
+
+/**
+ * Read Swiss QR-bill and EPC (GiroCode) payment codes from images and PDFs.
+ * Uploads are scanned automatically, see the `payment-code-detected` event.
+ */
+export async function scanPaymentCodes(paths: string[]) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<{ path: string; codes: NativePaymentCode[] }[]>(
+    "scan_payment_codes",
+    { paths },
+  );
+}
+
+export type NativeReceiptHints = {
+  vendor: string | null;
+  date: string | null;
+  total: number | null;
+  currency: string | null;
+  vat: number | null;
+  vatRate: number | null;
+};
+
+/**
+ * Read date, total, currency, VAT and vendor from the text of PDF receipts,
+ * locally and without uploading them. `hints` is `null` for images and scans.
+ */
+export async function previewReceipts(paths: string[]) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<{ path: string; hints: NativeReceiptHints | null }[]>(
+    "preview_receipts",
+    { paths },
+  );
+}
+
+export type NativeStatementTransaction = {
+  /** YYYY-MM-DD */
+  date: string;
+  /** Negative for money going out */
+  amount: number;
+  currency: string;
+  description: string | null;
+  counterparty: string | null;
+  balance: number | null;
+};
+
+export type NativeStatement = {
+  format: "ofx" | "camt053" | "mt940";
+  account: string | null;
+  currency: string | null;
+  transactions: NativeStatementTransaction[];
+};
+
+/**
+ * Parse an OFX/QFX, CAMT.053 or MT940 bank statement locally into rows for
+ * the CSV import. Rejects with a message for other files.
+ */
+export async function parseStatement(file: Blob) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeStatement>(
+    "parse_statement",
+    new Uint8Array(await file.arrayBuffer()),
+  );
+}
+
+/** A file picked for the import, only its id reaches the shell again */
+export type NativeImportFile = { id: number; fileName: string };
+
+/**
+ * Choose a CSV or bank statement to import with the native dialog. Resolves
+ * to `null` when the dialog was cancelled.
+ */
+export async function pickImportFile() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeImportFile | null>("pick_import_file");
+}
+
+/** Take the file of a native drop onto the import */
+export async function importDroppedFile(dropId: number) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeImportFile>("import_dropped_file", { dropId });
+}
+
+/** Forget a file picked for the import */
+export async function closeImportFile(id: number) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<void>("close_import_file", { id });
+}
+
+/** Parse a bank statement picked for the import, like `parseStatement` */
+export async function parseStatementFile(id: number) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeStatement>("parse_statement_file", { id });
+}
+
+export type NativeCsvColumn = {
+  name: string;
+  kind: "date" | "number" | "currency" | "text" | "empty";
+  /** date-fns format, for date columns */
+  dateFormat: string | null;
+};
+
+export type NativeCsvInspection = {
+  encoding: "UTF-8" | "UTF-16LE" | "UTF-16BE" | "windows-1252";
+  delimiter: string;
+  /** Rows before the header, or before the data without one */
+  skipRows: number;
+  hasHeader: boolean;
+  decimalSeparator: "." | ",";
+  dateFormat: string | null;
+  columns: NativeCsvColumn[];
+  preview: string[][];
+  rows: number;
+};
+
+/**
+ * Detect the encoding, delimiter, header, decimal separator and date format
+ * of a picked CSV file, with a preview and column types. The file is
+ * streamed, not loaded into the webview.
+ */
+export async function inspectCsv(id: number) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeCsvInspection>("inspect_csv", { id });
+}
+
+/**
+ * Read data rows of a file passed to `inspectCsv`. Reading chunks in order
+ * is fastest, the file isn't read from the start again.
+ */
+export async function readCsvChunk(id: number, offset: number, limit = 1000) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<{ offset: number; rows: string[][]; done: boolean }>(
+    "read_csv_chunk",
+    { id, offset, limit },
+  );
+}
+
+/** A vCard contact, in the fields of a customer */
+export type NativeContact = {
+  name: string;
+  contact: string | null;
+  email: string | null;
+  phone: string | null;
+  website: string | null;
+  addressLine1: string | null;
+  addressLine2: string | null;
+  city: string | null;
+  state: string | null;
+  zip: string | null;
+  country: string | null;
+  countryCode: string | null;
+  vatNumber: string | null;
+  note: string | null;
+};
+
+/** A calendar event, as a tracker entry to assign to a project */
+export type NativeProposedEntry = {
+  uid: string | null;
+  /** Original start of an occurrence of a recurring event, ISO 8601 in UTC */
+  recurrenceId: string | null;
+  summary: string;
+  description: string | null;
+  /** ISO 8601 in UTC */
+  start: string;
+  stop: string;
+  /** YYYY-MM-DD, the local day of the start */
+  date: string;
+  /** Seconds */
+  duration: number;
+  recurring: boolean;
+};
+
+/** Which of `entries` were already imported for the team, in order */
+export async function importedCalendarEntries(
+  teamId: string,
+  entries: NativeProposedEntry[],
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<boolean[]>("imported_calendar_entries", { teamId, entries });
+}
+
+/** Remember imported entries, so importing the calendar again skips them */
+export async function markCalendarImported(
+  teamId: string,
+  entries: NativeProposedEntry[],
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("mark_calendar_imported", { teamId, entries });
+}
+
+export type NativeDownload = {
+  id: string;
+  url: string;
+  fileName: string;
+  path: string;
+  status: "inProgress" | "completed" | "failed" | "cancelled";
+  bytesReceived: number;
+  totalBytes: number | null;
+  error: string | null;
+  startedAt: number;
+  finishedAt: number | null;
+};
+
+/**
+ * Download history, newest first.
+ */
+export async function listDownloads() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeDownload[]>("list_downloads");
+}
+
+/**
+ * Open a downloaded file with its default application.
+ */
+export async function openDownload(id: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("open_download", { id });
+}
+
+/**
+ * Show a downloaded file in Finder / Explorer / the file manager.
+ */
+export async function showDownloadInFolder(id: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("show_download_in_folder", { id });
+}
+
+/**
+ * Stop a download that is still in progress.
+ */
+export async function cancelDownload(id: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("cancel_download", { id });
+}
+
+export type NativeExportItem = {
+  id: string;
+  url: string;
+  fileName: string;
+  originalName?: string;
+  date?: string;
+  amount?: number;
+  currency?: string;
+};
+
+/**
+ * Download documents into a single ZIP picked through the save dialog.
+ * Returns the export id used by `export-progress` and `export-finished`,
+ * or `null` if the dialog was cancelled.
+ */
+export async function bulkExport(
+  items: NativeExportItem[],
+  archiveName: string,
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<string | null>("bulk_export", { items, archiveName });
+}
+
+/**
+ * Stop a running export. The partial archive is removed.
+ */
+export async function cancelExport(id: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("cancel_export", { id });
+}
+
+export type NativePrintOptions = {
+  paperSize?: "a4" | "a3" | "a5" | "letter" | "legal";
+  /** Margins in millimetres */
+  margins?: { top: number; right: number; bottom: number; left: number };
+  landscape?: boolean;
+  /** Write a PDF to this path instead of showing the print dialog */
+  pdfPath?: string;
+};
+
+/**
+ * Open the print dialog for a document, e.g. an invoice PDF.
+ */
+export async function printUrl(url: string, options?: NativePrintOptions) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("print_url", { url, options });
+}
+
+/**
+ * Open the print dialog for what the main window currently shows.
+ */
+export async function printCurrentView(options?: NativePrintOptions) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("print_current_view", { options });
+}
+
+/** Payload of `update-progress`, emitted while an update downloads */
+export type NativeUpdateProgress = {
+  version: string;
+  /** Bytes */
+  downloaded: number;
+  total: number | null;
+};
+
+/**
+ * Check for an update and ask to install it, with the same dialogs as the
+ * tray's "Check for Updates...". Listen to `update-progress`,
+ * `update-installed` and `update-failed` for the outcome.
+ */
+export async function checkForUpdates() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("check_for_updates");
+}
+
+/** Payload of `update-ready`, an update downloaded in the background */
+export type NativeReadyUpdate = {
+  version: string;
+  notes: string | null;
+  installOnQuit: boolean;
+};
+
+/**
+ * The update downloaded in the background and waiting for a choice, if any.
+ */
+export async function pendingUpdate() {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<NativeReadyUpdate | null>("pending_update");
+}
+
+/**
+ * Install the downloaded update now (restarting the app), when the app quits,
+ * skip this version, or ask again in `days` (3 by default). Skipped and
+ * snoozed versions aren't offered again until the next release.
+ */
+export async function updateAction(
+  action: "installNow" | "installOnQuit" | "skip" | "remindLater",
+  days?: number,
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("update_action", { action, days });
+}
+
+/**
+ * `listen` for an event the shell keeps until a component listens to it,
+ * like `calendar-imported`, so it isn't lost when it arrives before the
+ * component mounts (e.g. a file opened with the app before signing in).
+ */
+export async function listenQueued<T>(
+  event: string,
+  handler: EventCallback<T>,
+) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  const { listen } = await import("@tauri-apps/api/event");
+
+  const unlisten = await listen<T>(event, handler);
+  await invoke("frontend_listening", { events: [event] });
+
+  return () => {
+    unlisten();
+    invoke("frontend_not_listening", { events: [event] }).catch((error) => {
+      console.error(`Failed to stop listening to ${event}:`, error);
+    });
+  };
+}
+
+/** Payload of `desktop-timer`, sent by `midday timer start|stop` */
+export type NativeTimerAction =
+  | { id: number; action: "start"; project: string }
+  | { id: number; action: "stop" };
+
+/**
+ * Report the outcome of a `desktop-timer` request back to the command line,
+ * which shows it as a notification.
+ */
+export async function timerResult(id: number, ok: boolean, message: string) {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("timer_result", { id, ok, message });
+}